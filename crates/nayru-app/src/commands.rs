@@ -4,7 +4,8 @@ use serde::{Deserialize, Serialize};
 use tauri::State;
 
 use nayru_core::text_prep::split_sentences;
-use nayru_core::types::TtsConfig;
+use nayru_core::types::{SpeakOptions, VoiceInfo};
+use nayru_lib::stretch::{MAX_RATE, MIN_RATE};
 use nayru_lib::tts::TtsEngine;
 
 use crate::state::{AppState, ReaderConfig};
@@ -23,6 +24,8 @@ pub struct ReaderStatus {
 pub struct TtsConfigPatch {
     pub voice: Option<String>,
     pub speed: Option<f32>,
+    /// espeak-ng language; an empty string goes back to following the voice.
+    pub language: Option<String>,
 }
 
 fn engine_or_err(state: &AppState) -> Result<&std::sync::RwLock<TtsEngine>, String> {
@@ -39,12 +42,7 @@ fn build_status(state: &AppState) -> ReaderStatus {
             let status = engine.status();
             drop(engine);
 
-            let chunks_completed = tracker.total_chunks.saturating_sub(status.queue_length);
-            let idx = if status.state != nayru_core::types::TtsState::Idle {
-                tracker.current_sentence(chunks_completed)
-            } else {
-                None
-            };
+            let idx = tracker.current_sentence(&status);
 
             let s = match status.state {
                 nayru_core::types::TtsState::Idle => "idle",
@@ -76,10 +74,12 @@ pub async fn speak_from(
 
     engine_or_err(&state)?.read().unwrap().stop();
 
-    let tracker = SentenceTracker::new(&text, sentence_index);
-    let to_speak: String = tracker.sentences.join(" ");
-
-    engine_or_err(&state)?.read().unwrap().speak(&to_speak);
+    let mut tracker = SentenceTracker::new(&text, sentence_index);
+    let queued = engine_or_err(&state)?
+        .read()
+        .unwrap()
+        .speak_sentences(&tracker.sentences, SpeakOptions::default())?;
+    tracker.utterance_id = Some(queued.utterance_id);
     *state.tracker.lock().unwrap() = tracker;
 
     let status = build_status(&state);
@@ -114,8 +114,7 @@ pub async fn tts_skip_sentence(state: State<'_, AppState>) -> Result<ReaderStatu
         let status = engine.read().unwrap().status();
         let tracker = state.tracker.lock().unwrap();
 
-        let chunks_completed = tracker.total_chunks.saturating_sub(status.queue_length);
-        let idx = match tracker.current_sentence(chunks_completed) {
+        let idx = match tracker.current_sentence(&status) {
             Some(idx) => idx + 1,
             None => return Ok(build_status(&state)),
         };
@@ -130,68 +129,117 @@ pub async fn tts_skip_sentence(state: State<'_, AppState>) -> Result<ReaderStatu
     }
 
     engine.read().unwrap().stop();
-    let tracker = SentenceTracker::new(&full_text, next_index);
-    let to_speak: String = tracker.sentences.join(" ");
-    engine.read().unwrap().speak(&to_speak);
+    let mut tracker = SentenceTracker::new(&full_text, next_index);
+    let queued = engine
+        .read()
+        .unwrap()
+        .speak_sentences(&tracker.sentences, SpeakOptions::default())?;
+    tracker.utterance_id = Some(queued.utterance_id);
     *state.tracker.lock().unwrap() = tracker;
 
     Ok(build_status(&state))
 }
 
+/// Play the current (or last) sentence again, then carry on.
+#[tauri::command]
+pub async fn tts_replay_last(state: State<'_, AppState>) -> Result<(), String> {
+    let engine = engine_or_err(&state)?.read().unwrap().clone();
+    engine.replay_last().await
+}
+
+/// Go back to the start of the previous sentence.
+#[tauri::command]
+pub async fn tts_previous_sentence(state: State<'_, AppState>) -> Result<(), String> {
+    let engine = engine_or_err(&state)?.read().unwrap().clone();
+    engine.previous().await
+}
+
+/// Go back `seconds` of played speech.
+#[tauri::command]
+pub async fn tts_rewind(seconds: f32, state: State<'_, AppState>) -> Result<(), String> {
+    let engine = engine_or_err(&state)?.read().unwrap().clone();
+    engine.rewind(seconds).await
+}
+
 #[tauri::command]
 pub async fn get_reader_status(state: State<'_, AppState>) -> Result<ReaderStatus, String> {
     Ok(build_status(&state))
 }
 
+/// Change voice, speed, or language. A running engine picks the change up
+/// live, re-voicing whatever hasn't started playing yet.
 #[tauri::command]
 pub async fn set_tts_config(
     patch: TtsConfigPatch,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
-    let new_engine = {
-        let mut config = state.config.write().unwrap();
-        let mut changed = false;
-
-        if let Some(voice) = patch.voice {
-            if voice != config.voice {
-                config.voice = voice;
-                changed = true;
-            }
+    let (voice, speed, language) = {
+        let config = state.config.read().unwrap();
+        (
+            patch.voice.unwrap_or_else(|| config.voice.clone()),
+            patch.speed.unwrap_or(config.speed),
+            match patch.language {
+                Some(language) => Some(language).filter(|l| !l.is_empty()),
+                None => config.language.clone(),
+            },
+        )
+    };
+
+    if let Some(engine) = state.engine() {
+        engine.read().unwrap().update_config(|c| {
+            c.voice = voice.clone();
+            c.speed = speed;
+            c.language = language.clone();
+        })?;
+    } else {
+        // The engine will be built from the saved config, so check it as
+        // `update_config` would; the voice once the model has loaded.
+        if !(speed.is_finite() && speed > 0.0) {
+            return Err(format!("invalid speed {speed}; must be a positive number"));
         }
-        if let Some(speed) = patch.speed {
-            if (speed - config.speed).abs() > 0.01 {
-                config.speed = speed;
-                changed = true;
-            }
+        if let Some(kokoro) = state.kokoro.get() {
+            kokoro.validate_voice(&voice)?;
         }
+    }
 
-        if changed {
-            let kokoro = state
-                .kokoro
-                .get()
-                .ok_or("Kokoro model not loaded")?
-                .clone();
-            Some(TtsEngine::new(
-                TtsConfig {
-                    voice: config.voice.clone(),
-                    speed: config.speed,
-                    ..Default::default()
-                },
-                kokoro,
-            ))
-        } else {
-            None
-        }
-    };
+    let mut config = state.config.write().unwrap();
+    config.voice = voice;
+    config.speed = speed;
+    config.language = language;
+    Ok(())
+}
 
-    if let Some(engine) = new_engine {
-        state.replace_engine(engine);
-        *state.tracker.lock().unwrap() = SentenceTracker::empty();
+/// Change the master volume without restarting the engine.
+#[tauri::command]
+pub fn tts_set_volume(volume: f32, state: State<'_, AppState>) -> Result<(), String> {
+    if !(0.0..=1.0).contains(&volume) {
+        return Err(format!("invalid volume {volume}; must be between 0 and 1"));
+    }
+    if let Some(engine) = state.engine() {
+        engine.read().unwrap().set_volume(volume)?;
     }
+    state.config.write().unwrap().volume = volume;
+    Ok(())
+}
 
+/// Change the playback rate at once, including the sentence playing.
+#[tauri::command]
+pub fn tts_set_rate(rate: f32, state: State<'_, AppState>) -> Result<(), String> {
+    if !(MIN_RATE..=MAX_RATE).contains(&rate) {
+        return Err(format!("invalid rate {rate}; must be between {MIN_RATE} and {MAX_RATE}"));
+    }
+    if let Some(engine) = state.engine() {
+        engine.read().unwrap().set_rate(rate)?;
+    }
+    state.config.write().unwrap().rate = rate;
     Ok(())
 }
 
+#[tauri::command]
+pub fn list_voices(state: State<'_, AppState>) -> Result<Vec<VoiceInfo>, String> {
+    Ok(engine_or_err(&state)?.read().unwrap().voice_infos())
+}
+
 #[tauri::command]
 pub fn get_tts_config(state: State<'_, AppState>) -> Result<ReaderConfig, String> {
     Ok(state.config.read().unwrap().clone())
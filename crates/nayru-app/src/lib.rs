@@ -26,9 +26,15 @@ pub fn run() {
             commands::tts_pause,
             commands::tts_resume,
             commands::tts_skip_sentence,
+            commands::tts_replay_last,
+            commands::tts_previous_sentence,
+            commands::tts_rewind,
+            commands::tts_set_volume,
+            commands::tts_set_rate,
             commands::get_reader_status,
             commands::set_tts_config,
             commands::get_tts_config,
+            commands::list_voices,
             commands::get_server_status,
         ])
         .setup(|app| {
@@ -40,7 +46,11 @@ pub fn run() {
         })
         .build(tauri::generate_context!())
         .expect("error building tauri application")
-        .run(|_app_handle, _event| {});
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                app_handle.state::<state::AppState>().shutdown();
+            }
+        });
 }
 
 async fn load_kokoro_model(handle: tauri::AppHandle) {
@@ -110,7 +120,27 @@ async fn load_kokoro_model(handle: tauri::AppHandle) {
     match nayru_lib::kokoro::KokoroSynth::new(&model_path, &voices_path).await {
         Ok(kokoro) => {
             tracing::info!("kokoro model loaded in {:?}", t0.elapsed());
-            state.set_kokoro(Arc::new(kokoro));
+            if let Some(dir) = nayru_lib::voice_pack::default_user_voices_dir() {
+                match kokoro.load_user_voices(&dir) {
+                    Ok(count) => tracing::info!("loaded {count} custom voices"),
+                    Err(e) => tracing::warn!("failed to load custom voices: {e}"),
+                }
+            }
+            let kokoro: Arc<dyn nayru_lib::synth::Synthesizer> = Arc::new(kokoro);
+            let cache = nayru_lib::synth_cache::default_dir().and_then(|dir| {
+                nayru_lib::synth_cache::SynthCache::open(
+                    &dir,
+                    nayru_lib::synth_cache::DEFAULT_MAX_BYTES,
+                )
+                .inspect_err(|e| tracing::warn!("synthesis cache disabled: {e}"))
+                .ok()
+            });
+            match cache {
+                Some(cache) => state.set_kokoro(Arc::new(
+                    nayru_lib::synth_cache::CachedSynth::new(kokoro, Arc::new(cache)),
+                )),
+                None => state.set_kokoro(kokoro),
+            }
             emit("ready", "Kokoro TTS is ready", None);
         }
         Err(e) => {
@@ -7,15 +7,16 @@
 use std::sync::{Arc, Mutex, OnceLock, RwLock};
 
 use nayru_core::types::TtsConfig;
-use nayru_lib::kokoro::KokoroSynth;
 use nayru_lib::manager::VoiceServiceManager;
-use nayru_lib::tts::TtsEngine;
+use nayru_lib::synth::Synthesizer;
+use nayru_lib::tts::{ShutdownMode, TtsEngine};
 
 use crate::tracker::SentenceTracker;
 
 pub struct AppState {
     engine: OnceLock<RwLock<TtsEngine>>,
-    pub kokoro: OnceLock<Arc<KokoroSynth>>,
+    /// Kokoro behind the synthesis cache.
+    pub kokoro: OnceLock<Arc<dyn Synthesizer>>,
     pub tracker: Mutex<SentenceTracker>,
     pub config: RwLock<ReaderConfig>,
     pub service_manager: VoiceServiceManager,
@@ -25,6 +26,23 @@ pub struct AppState {
 pub struct ReaderConfig {
     pub voice: String,
     pub speed: f32,
+    /// espeak-ng language; `None` follows the voice.
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Master volume, 0.0–1.0.
+    #[serde(default = "default_volume")]
+    pub volume: f32,
+    /// Playback rate, 0.5–3.0 (time-stretched, on top of `speed`).
+    #[serde(default = "default_rate")]
+    pub rate: f32,
+}
+
+fn default_volume() -> f32 {
+    1.0
+}
+
+fn default_rate() -> f32 {
+    1.0
 }
 
 impl Default for ReaderConfig {
@@ -32,6 +50,9 @@ impl Default for ReaderConfig {
         Self {
             voice: "af_heart".into(),
             speed: 1.0,
+            language: None,
+            volume: default_volume(),
+            rate: default_rate(),
         }
     }
 }
@@ -63,6 +84,9 @@ impl AppState {
             TtsConfig {
                 voice: config.voice.clone(),
                 speed: config.speed,
+                language: config.language.clone(),
+                volume: config.volume,
+                rate: config.rate,
                 ..Default::default()
             },
             kokoro,
@@ -72,15 +96,18 @@ impl AppState {
         self.engine.get()
     }
 
-    /// Store the loaded KokoroSynth instance.
-    pub fn set_kokoro(&self, kokoro: Arc<KokoroSynth>) {
-        let _ = self.kokoro.set(kokoro);
+    /// Stop speech, the engine (if it was ever started), and the voice
+    /// services. For app exit; blocks until playback has faded out.
+    pub fn shutdown(&self) {
+        if let Some(engine) = self.engine.get() {
+            let engine = engine.read().unwrap().clone();
+            tauri::async_runtime::block_on(engine.shutdown(ShutdownMode::Immediate));
+        }
+        self.service_manager.stop_sync();
     }
 
-    /// Replace the engine (used when config changes).
-    pub fn replace_engine(&self, engine: TtsEngine) {
-        if let Some(lock) = self.engine.get() {
-            *lock.write().unwrap() = engine;
-        }
+    /// Store the loaded Kokoro synthesizer.
+    pub fn set_kokoro(&self, kokoro: Arc<dyn Synthesizer>) {
+        let _ = self.kokoro.set(kokoro);
     }
 }
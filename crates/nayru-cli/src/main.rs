@@ -2,13 +2,42 @@
 //!
 //! ```text
 //! nayru serve [--port 2003] [--host 127.0.0.1] [--voice af_jadzia]
-//! nayru speak "hello world" [--server http://localhost:2003]
+//!             [--language es] [--detect-language]
+//!             [--backend kokoro|http|mock] [--backend-url http://localhost:8880]
+//!             [--lexicon ~/.config/nayru/lexicon.json]
+//!             [--user-voices ~/.local/share/nayru/voices]
+//!             [--cache-dir ~/.local/share/nayru/synth-cache] [--cache-size 256] [--no-cache]
+//!             [--output local|remote|both|null] [--output-device "USB Audio"] [--record session.wav]
+//!             [--volume 0.8] [--rate 1.5] [--loudness-target -18] [--no-normalize]
+//!             [--gap 250] [--list-gap 400] [--paragraph-gap 700] [--trim-threshold -50] [--no-trim]
+//! nayru speak "hello world" [--voice bf_emma] [--speed 1.2] [--gain 1.5] [--priority] [--ssml] [--server http://localhost:2003]
+//! nayru render chapter.md -o chapter.flac|.opus|.mp3|.wav [--voice bf_emma] [--speed 1.2]
+//!              [--loudness-target -18] [--no-normalize]
+//!              [--gap 250] [--list-gap 400] [--paragraph-gap 700] [--trim-threshold -50] [--no-trim]
+//!              [--title "Chapter 1"] [--artist ...]
+//!              [--backend kokoro|http|mock] [--model ...] [--voices ...]
+//! nayru voices [--json] [--preview ./previews] [--server ...]
+//! nayru save-voice warm "af_heart:0.6+bf_emma:0.4" [--server ...]
+//! nayru devices [--json] [--server ...] / nayru set-device ["USB Audio"] [--server ...]
+//! nayru volume 0.6 / rate 1.5 / duck [--level 0.25] [--for 30] / unduck [--server ...]
 //! nayru stop / skip / pause / resume / status [--server ...]
+//! nayru replay / previous / rewind 10 [--server ...]
+//! nayru cache stats [--dir ...] [--server ...] / nayru cache clear [--dir ...]
 //! ```
 
 use std::sync::Arc;
 
-use clap::{Parser, Subcommand};
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use nayru_lib::nayru_core::types::{
+    AudioDevice, Pauses, VoiceInfo, DEFAULT_LOUDNESS_TARGET, DEFAULT_TRIM_THRESHOLD,
+    MAX_PAUSE_MS,
+};
+use nayru_lib::audio_sink;
+use nayru_lib::encode::{self, AudioFormat, AudioTags};
+use nayru_lib::synth::Synthesizer;
+use nayru_lib::synth_cache;
+use nayru_lib::tts::ShutdownMode;
 
 /// nayru — voice server with TTS playback
 #[derive(Parser)]
@@ -32,23 +61,237 @@ enum Command {
         #[arg(long, default_value = "af_heart")]
         voice: String,
         /// TTS playback speed
-        #[arg(long, default_value = "1.0")]
+        #[arg(long, default_value = "1.0", value_parser = speed)]
         speed: f32,
-        /// Path to kokoro ONNX model file
+        /// Default espeak-ng language, e.g. es [default: from the voice]
+        #[arg(long)]
+        language: Option<String>,
+        /// Guess each sentence's language (for mixed-language documents)
+        #[arg(long)]
+        detect_language: bool,
+        /// Speech synthesis backend
+        #[arg(long, value_enum, default_value = "kokoro")]
+        backend: Backend,
+        /// Path to kokoro ONNX model file (kokoro backend)
+        #[arg(long)]
+        model: Option<String>,
+        /// Path to kokoro voices file (kokoro backend)
+        #[arg(long)]
+        voices: Option<String>,
+        /// Base URL of an OpenAI-compatible speech server (http backend)
+        #[arg(long)]
+        backend_url: Option<String>,
+        /// Pronunciation lexicon file [default: <config dir>/nayru/lexicon.json]
+        #[arg(long)]
+        lexicon: Option<std::path::PathBuf>,
+        /// Custom voices directory (kokoro backend) [default: <data dir>/nayru/voices]
+        #[arg(long)]
+        user_voices: Option<std::path::PathBuf>,
+        /// Synthesis cache directory [default: <data dir>/nayru/synth-cache]
         #[arg(long)]
-        model: String,
-        /// Path to kokoro voices file
+        cache_dir: Option<std::path::PathBuf>,
+        /// Synthesis cache size cap, in MiB
+        #[arg(long, default_value = "256")]
+        cache_size: u64,
+        /// Synthesize every clip, even ones heard before
         #[arg(long)]
-        voices: String,
+        no_cache: bool,
+        /// Where speech plays: these speakers, /audio/stream listeners, both,
+        /// or nowhere (null: runs in real time without audio, e.g. for CI)
+        #[arg(long, value_enum, default_value = "local")]
+        output: Output,
+        /// Audio device to play on, by name (see `nayru devices`) [default: system default]
+        #[arg(long)]
+        output_device: Option<String>,
+        /// Also record everything played to this WAV file
+        #[arg(long)]
+        record: Option<std::path::PathBuf>,
+        /// Master volume, 0.0–1.0
+        #[arg(long, default_value = "1.0")]
+        volume: f32,
+        /// Playback rate, 0.5–3.0, time-stretched on top of --speed
+        #[arg(long, default_value = "1.0")]
+        rate: f32,
+        /// Integrated loudness every clip is normalized to, in LUFS
+        #[arg(long, default_value_t = DEFAULT_LOUDNESS_TARGET, allow_hyphen_values = true)]
+        loudness_target: f32,
+        /// Keep each clip at the level it was synthesized at
+        #[arg(long)]
+        no_normalize: bool,
+        /// Silence between sentences, in milliseconds
+        #[arg(long, default_value_t = Pauses::default().sentence_ms, value_parser = pause_ms())]
+        gap: u64,
+        /// Silence after a list item, in milliseconds
+        #[arg(long, default_value_t = Pauses::default().list_item_ms, value_parser = pause_ms())]
+        list_gap: u64,
+        /// Silence at a paragraph break, in milliseconds
+        #[arg(long, default_value_t = Pauses::default().paragraph_ms, value_parser = pause_ms())]
+        paragraph_gap: u64,
+        /// Trim each clip's leading and trailing audio quieter than this, in dBFS
+        #[arg(long, default_value_t = DEFAULT_TRIM_THRESHOLD, allow_hyphen_values = true)]
+        trim_threshold: f32,
+        /// Keep the silence the synthesizer puts around each clip
+        #[arg(long)]
+        no_trim: bool,
     },
     /// Send text to the running server for speech
     Speak {
         /// Text to speak
         text: String,
+        /// Voice for this utterance (defaults to the server's voice)
+        #[arg(long)]
+        voice: Option<String>,
+        /// Speed for this utterance (defaults to the server's speed)
+        #[arg(long)]
+        speed: Option<f32>,
+        /// espeak-ng language code, e.g. en-gb
+        #[arg(long)]
+        language: Option<String>,
+        /// Loudness of this utterance relative to the master volume (0.0–4.0)
+        #[arg(long)]
+        gain: Option<f32>,
+        /// Speak at once over any speech playing, ducking it meanwhile
+        #[arg(long)]
+        priority: bool,
+        /// Treat the text as SSML markup
+        #[arg(long)]
+        ssml: bool,
         /// Server URL
         #[arg(long, default_value = "http://localhost:2003")]
         server: String,
     },
+    /// Synthesize a text or markdown file to an audio file, without a server
+    Render {
+        /// Input file; `-` or omitted reads stdin
+        input: Option<std::path::PathBuf>,
+        /// Output file; the extension picks the format (.wav, .flac, .opus, .mp3, .pcm)
+        #[arg(short, long)]
+        output: std::path::PathBuf,
+        /// Title tag [default: the input file name]
+        #[arg(long)]
+        title: Option<String>,
+        /// Artist tag
+        #[arg(long)]
+        artist: Option<String>,
+        /// TTS voice
+        #[arg(long, default_value = "af_heart")]
+        voice: String,
+        /// TTS speed
+        #[arg(long, default_value = "1.0", value_parser = speed)]
+        speed: f32,
+        /// espeak-ng language, e.g. es [default: from the voice]
+        #[arg(long)]
+        language: Option<String>,
+        /// Integrated loudness every clip is normalized to, in LUFS
+        #[arg(long, default_value_t = DEFAULT_LOUDNESS_TARGET, allow_hyphen_values = true)]
+        loudness_target: f32,
+        /// Keep each clip at the level it was synthesized at
+        #[arg(long)]
+        no_normalize: bool,
+        /// Silence between sentences, in milliseconds
+        #[arg(long, default_value_t = Pauses::default().sentence_ms, value_parser = pause_ms())]
+        gap: u64,
+        /// Silence after a list item, in milliseconds
+        #[arg(long, default_value_t = Pauses::default().list_item_ms, value_parser = pause_ms())]
+        list_gap: u64,
+        /// Silence at a paragraph break, in milliseconds
+        #[arg(long, default_value_t = Pauses::default().paragraph_ms, value_parser = pause_ms())]
+        paragraph_gap: u64,
+        /// Trim each clip's leading and trailing audio quieter than this, in dBFS
+        #[arg(long, default_value_t = DEFAULT_TRIM_THRESHOLD, allow_hyphen_values = true)]
+        trim_threshold: f32,
+        /// Keep the silence the synthesizer puts around each clip
+        #[arg(long)]
+        no_trim: bool,
+        /// Speech synthesis backend
+        #[arg(long, value_enum, default_value = "kokoro")]
+        backend: Backend,
+        /// Path to kokoro ONNX model file (kokoro backend)
+        #[arg(long)]
+        model: Option<String>,
+        /// Path to kokoro voices file (kokoro backend)
+        #[arg(long)]
+        voices: Option<String>,
+        /// Base URL of an OpenAI-compatible speech server (http backend)
+        #[arg(long)]
+        backend_url: Option<String>,
+        /// Pronunciation lexicon file [default: <config dir>/nayru/lexicon.json]
+        #[arg(long)]
+        lexicon: Option<std::path::PathBuf>,
+        /// Custom voices directory (kokoro backend) [default: <data dir>/nayru/voices]
+        #[arg(long)]
+        user_voices: Option<std::path::PathBuf>,
+    },
+    /// List the server's voices
+    Voices {
+        /// Print the raw JSON
+        #[arg(long)]
+        json: bool,
+        /// Also synthesize a sample of each voice into this directory
+        #[arg(long)]
+        preview: Option<std::path::PathBuf>,
+        #[arg(long, default_value = "http://localhost:2003")]
+        server: String,
+    },
+    /// Save a voice blend as a named custom voice on the running server
+    SaveVoice {
+        /// Name for the new voice
+        name: String,
+        /// Weighted blend, e.g. af_heart:0.6+bf_emma:0.4
+        blend: String,
+        #[arg(long, default_value = "http://localhost:2003")]
+        server: String,
+    },
+    /// List the running server's audio output devices
+    Devices {
+        /// Print the raw JSON
+        #[arg(long)]
+        json: bool,
+        #[arg(long, default_value = "http://localhost:2003")]
+        server: String,
+    },
+    /// Move the running server's playback to another audio device
+    SetDevice {
+        /// Device name from `nayru devices` [default: system default]
+        name: Option<String>,
+        #[arg(long, default_value = "http://localhost:2003")]
+        server: String,
+    },
+    /// Set the running server's master volume
+    Volume {
+        /// 0.0 (silent) to 1.0 (full)
+        volume: f32,
+        #[arg(long, default_value = "http://localhost:2003")]
+        server: String,
+    },
+    /// Set the running server's playback rate, applied at once (pitch is kept)
+    Rate {
+        /// 0.5 (half speed) to 3.0
+        rate: f32,
+        #[arg(long, default_value = "http://localhost:2003")]
+        server: String,
+    },
+    /// Lower the running server's speech while something else talks
+    Duck {
+        /// Fraction of the master volume to play at
+        #[arg(long, default_value_t = nayru_lib::tts::DEFAULT_DUCK_LEVEL)]
+        level: f32,
+        /// Lift the duck by itself after this many seconds
+        #[arg(long = "for")]
+        duration: Option<f64>,
+        #[arg(long, default_value = "http://localhost:2003")]
+        server: String,
+    },
+    /// Undo `nayru duck`
+    Unduck {
+        #[arg(long, default_value = "http://localhost:2003")]
+        server: String,
+    },
+    /// Inspect or empty the synthesis cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
     /// Stop all speech
     Stop {
         #[arg(long, default_value = "http://localhost:2003")]
@@ -69,6 +312,24 @@ enum Command {
         #[arg(long, default_value = "http://localhost:2003")]
         server: String,
     },
+    /// Play the current (or last) sentence again
+    Replay {
+        #[arg(long, default_value = "http://localhost:2003")]
+        server: String,
+    },
+    /// Go back to the previous sentence
+    Previous {
+        #[arg(long, default_value = "http://localhost:2003")]
+        server: String,
+    },
+    /// Go back some seconds of played speech
+    Rewind {
+        /// Seconds to go back
+        #[arg(default_value_t = 5.0)]
+        seconds: f32,
+        #[arg(long, default_value = "http://localhost:2003")]
+        server: String,
+    },
     /// Get server status
     Status {
         #[arg(long, default_value = "http://localhost:2003")]
@@ -76,6 +337,55 @@ enum Command {
     },
 }
 
+#[derive(Subcommand)]
+enum CacheAction {
+    /// Show disk usage, plus hit/miss counts if the server is running
+    Stats {
+        /// Cache directory [default: <data dir>/nayru/synth-cache]
+        #[arg(long)]
+        dir: Option<std::path::PathBuf>,
+        #[arg(long, default_value = "http://localhost:2003")]
+        server: String,
+    },
+    /// Delete every cached clip
+    Clear {
+        /// Cache directory [default: <data dir>/nayru/synth-cache]
+        #[arg(long)]
+        dir: Option<std::path::PathBuf>,
+    },
+}
+
+/// Where `serve` and `render` get their audio from.
+#[derive(Clone, Copy, ValueEnum)]
+enum Backend {
+    /// In-process Kokoro ONNX inference
+    Kokoro,
+    /// Remote OpenAI-compatible `/v1/audio/speech` server
+    Http,
+    /// Deterministic test tones, no model needed
+    Mock,
+}
+
+/// `serve --output`.
+#[derive(Clone, Copy, ValueEnum)]
+enum Output {
+    Local,
+    Remote,
+    Both,
+    Null,
+}
+
+impl From<Output> for nayru_lib::nayru_core::types::AudioOutput {
+    fn from(output: Output) -> Self {
+        match output {
+            Output::Local => Self::Local,
+            Output::Remote => Self::Remote,
+            Output::Both => Self::Both,
+            Output::Null => Self::Null,
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() {
     tracing_subscriber::fmt::init();
@@ -88,26 +398,91 @@ async fn main() {
             host,
             voice,
             speed,
+            language,
+            detect_language,
+            backend,
             model,
             voices,
+            backend_url,
+            lexicon,
+            user_voices,
+            cache_dir,
+            cache_size,
+            no_cache,
+            output,
+            output_device,
+            record,
+            volume,
+            rate,
+            loudness_target,
+            no_normalize,
+            gap,
+            list_gap,
+            paragraph_gap,
+            trim_threshold,
+            no_trim,
         } => {
-            eprintln!("loading kokoro model...");
-            let kokoro = nayru_lib::kokoro::KokoroSynth::new(
-                std::path::Path::new(&model),
-                std::path::Path::new(&voices),
-            )
-            .await
-            .expect("failed to load kokoro model");
-            let kokoro = Arc::new(kokoro);
+            let synth = build_synth(backend, model, voices, backend_url, user_voices).await;
+            let synth = match cache_dir.or_else(synth_cache::default_dir) {
+                Some(dir) if !no_cache => {
+                    match synth_cache::SynthCache::open(&dir, cache_size * 1024 * 1024) {
+                        Ok(cache) => {
+                            eprintln!("synth cache: {}", dir.display());
+                            Arc::new(synth_cache::CachedSynth::new(synth, Arc::new(cache)))
+                        }
+                        Err(e) => {
+                            eprintln!("synth cache disabled: {e}");
+                            synth
+                        }
+                    }
+                }
+                _ => synth,
+            };
 
             let config = nayru_lib::nayru_core::types::TtsConfig {
                 voice,
                 speed,
+                language,
+                detect_language,
+                output: output.into(),
+                output_device,
+                volume,
+                rate,
+                loudness_target: (!no_normalize).then_some(loudness_target),
+                trim_threshold: (!no_trim).then_some(trim_threshold),
+                pauses: Pauses {
+                    sentence_ms: gap,
+                    list_item_ms: list_gap,
+                    paragraph_ms: paragraph_gap,
+                },
                 ..Default::default()
             };
 
-            let engine = nayru_lib::tts::TtsEngine::new(config, kokoro);
-            let app = nayru_lib::server::router(engine);
+            let engine = match record {
+                Some(path) => {
+                    let recorder = audio_sink::WavRecorder::create(&path).unwrap_or_else(|e| {
+                        eprintln!("{e}");
+                        std::process::exit(1)
+                    });
+                    eprintln!("recording to {}", path.display());
+                    let (output, device) = (config.output, config.output_device.clone());
+                    nayru_lib::tts::TtsEngine::with_sink(config, synth, move || {
+                        Box::new(audio_sink::Tee::new(
+                            audio_sink::for_output(output, device),
+                            recorder,
+                        ))
+                    })
+                }
+                None => nayru_lib::tts::TtsEngine::new(config, synth),
+            };
+            if let Some(path) = lexicon.or_else(nayru_lib::lexicon::Lexicon::default_path) {
+                match engine.lexicon().load(&path) {
+                    Ok(count) => eprintln!("lexicon: {count} entries from {}", path.display()),
+                    Err(e) => eprintln!("lexicon: {e}"),
+                }
+                engine.lexicon().watch(std::time::Duration::from_secs(2));
+            }
+            let app = nayru_lib::server::router(engine.clone());
 
             let addr = format!("{host}:{port}");
             eprintln!("nayru listening on {addr}");
@@ -116,23 +491,343 @@ async fn main() {
                 .await
                 .expect("failed to bind");
 
-            axum::serve(listener, app).await.expect("server error");
+            // On Ctrl-C or SIGTERM, stop taking requests. Clients still
+            // connected (event and audio streams) are dropped on exit.
+            let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+            tokio::spawn(async move {
+                axum::serve(listener, app)
+                    .with_graceful_shutdown(async {
+                        let _ = stop_rx.await;
+                    })
+                    .await
+                    .expect("server error");
+            });
+            shutdown_signal().await;
+            let _ = stop_tx.send(());
+
+            // Let queued speech finish unless asked again.
+            eprintln!("nayru: finishing queued speech (Ctrl-C again to stop now)");
+            let drain = tokio::spawn({
+                let engine = engine.clone();
+                async move { engine.shutdown(ShutdownMode::Drain).await }
+            });
+            tokio::select! {
+                _ = drain => {}
+                () = shutdown_signal() => engine.shutdown(ShutdownMode::Immediate).await,
+            }
         }
 
-        Command::Speak { text, server } => {
+        Command::Speak {
+            text,
+            voice,
+            speed,
+            language,
+            gain,
+            priority,
+            ssml,
+            server,
+        } => {
             let resp = reqwest::Client::new()
                 .post(format!("{server}/speak"))
-                .json(&serde_json::json!({ "text": text }))
+                .json(&serde_json::json!({
+                    "text": text,
+                    "format": if ssml { "ssml" } else { "text" },
+                    "voice": voice,
+                    "speed": speed,
+                    "language": language,
+                    "gain": gain,
+                    "priority": priority,
+                }))
                 .send()
                 .await
                 .expect("request failed");
             println!("{}", resp.text().await.unwrap_or_default());
         }
 
+        Command::Render {
+            input,
+            output,
+            title,
+            artist,
+            voice,
+            speed,
+            language,
+            loudness_target,
+            no_normalize,
+            gap,
+            list_gap,
+            paragraph_gap,
+            trim_threshold,
+            no_trim,
+            backend,
+            model,
+            voices,
+            backend_url,
+            lexicon,
+            user_voices,
+        } => {
+            let format = AudioFormat::from_path(&output).unwrap_or_else(|e| {
+                eprintln!("{e}");
+                std::process::exit(1)
+            });
+            let input = input.filter(|p| p.as_os_str() != "-");
+            let title = title.or_else(|| {
+                let stem = input.as_ref()?.file_stem()?;
+                Some(stem.to_string_lossy().into_owned())
+            });
+            let text = match input {
+                Some(path) => std::fs::read_to_string(&path).unwrap_or_else(|e| {
+                    eprintln!("{}: {e}", path.display());
+                    std::process::exit(1)
+                }),
+                None => std::io::read_to_string(std::io::stdin()).unwrap_or_else(|e| {
+                    eprintln!("stdin: {e}");
+                    std::process::exit(1)
+                }),
+            };
+
+            let synth = build_synth(backend, model, voices, backend_url, user_voices).await;
+            if let Err(e) = synth.validate_voice(&voice) {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+            let lex = nayru_lib::lexicon::Lexicon::default();
+            if let Some(path) = lexicon.or_else(nayru_lib::lexicon::Lexicon::default_path) {
+                match lex.load(&path) {
+                    Ok(count) => eprintln!("lexicon: {count} entries from {}", path.display()),
+                    Err(e) => eprintln!("lexicon: {e}"),
+                }
+            }
+            let synth = lex.wrap(synth);
+
+            let config = nayru_lib::nayru_core::types::TtsConfig {
+                voice,
+                speed,
+                language,
+                loudness_target: (!no_normalize).then_some(loudness_target),
+                trim_threshold: (!no_trim).then_some(trim_threshold),
+                pauses: Pauses {
+                    sentence_ms: gap,
+                    list_item_ms: list_gap,
+                    paragraph_ms: paragraph_gap,
+                },
+                ..Default::default()
+            };
+            let audio = nayru_lib::tts::render_text(
+                synth.as_ref(),
+                &config,
+                &text,
+                &Default::default(),
+                print_progress,
+            )
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("\nrender failed: {e}");
+                std::process::exit(1)
+            });
+
+            let tags = AudioTags {
+                title,
+                artist,
+                voice: Some(config.voice),
+            };
+            let bytes = encode::encode(format, &audio.pcm, audio.sample_rate, &tags)
+                .unwrap_or_else(|e| {
+                    eprintln!("{e}");
+                    std::process::exit(1)
+                });
+            if let Err(e) = std::fs::write(&output, bytes) {
+                eprintln!("{}: {e}", output.display());
+                std::process::exit(1);
+            }
+            eprintln!(
+                "wrote {} ({:.1}s)",
+                output.display(),
+                audio.pcm.len() as f64 / audio.sample_rate as f64
+            );
+        }
+
+        Command::Voices {
+            json,
+            preview,
+            server,
+        } => {
+            let query = if preview.is_some() { "?preview=1" } else { "" };
+            let resp = reqwest::Client::new()
+                .get(format!("{server}/voices{query}"))
+                .send()
+                .await
+                .expect("request failed");
+            let voices: Vec<VoiceInfo> = resp.json().await.expect("invalid voices response");
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&voices).unwrap_or_default()
+                );
+            } else {
+                print_voices(&voices);
+            }
+            if let Some(dir) = preview {
+                save_previews(&voices, &dir);
+            }
+        }
+
+        Command::SaveVoice {
+            name,
+            blend,
+            server,
+        } => {
+            let resp = reqwest::Client::new()
+                .post(format!("{server}/voices"))
+                .json(&serde_json::json!({ "name": name, "blend": blend }))
+                .send()
+                .await
+                .expect("request failed");
+            println!("{}", resp.text().await.unwrap_or_default());
+        }
+
+        Command::Devices { json, server } => {
+            let resp = reqwest::Client::new()
+                .get(format!("{server}/devices"))
+                .send()
+                .await
+                .expect("request failed");
+            if !resp.status().is_success() {
+                eprintln!("{}", resp.text().await.unwrap_or_default());
+                std::process::exit(1);
+            }
+            let devices: Vec<AudioDevice> = resp.json().await.expect("invalid devices response");
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&devices).unwrap_or_default()
+                );
+            } else {
+                for device in &devices {
+                    println!(
+                        "{} {}{}",
+                        if device.active { "*" } else { " " },
+                        device.name,
+                        if device.default { " (default)" } else { "" },
+                    );
+                }
+            }
+        }
+
+        Command::SetDevice { name, server } => {
+            let resp = reqwest::Client::new()
+                .post(format!("{server}/devices"))
+                .json(&serde_json::json!({ "name": name }))
+                .send()
+                .await
+                .expect("request failed");
+            println!("{}", resp.text().await.unwrap_or_default());
+        }
+
+        Command::Volume { volume, server } => {
+            let resp = reqwest::Client::new()
+                .post(format!("{server}/volume"))
+                .json(&serde_json::json!({ "volume": volume }))
+                .send()
+                .await
+                .expect("request failed");
+            println!("{}", resp.text().await.unwrap_or_default());
+        }
+
+        Command::Rate { rate, server } => {
+            let resp = reqwest::Client::new()
+                .post(format!("{server}/rate"))
+                .json(&serde_json::json!({ "rate": rate }))
+                .send()
+                .await
+                .expect("request failed");
+            println!("{}", resp.text().await.unwrap_or_default());
+        }
+
+        Command::Duck {
+            level,
+            duration,
+            server,
+        } => {
+            let resp = reqwest::Client::new()
+                .post(format!("{server}/duck"))
+                .json(&serde_json::json!({
+                    "level": level,
+                    "timeout_ms": duration.map(|secs| (secs * 1000.0) as u64),
+                }))
+                .send()
+                .await
+                .expect("request failed");
+            println!("{}", resp.text().await.unwrap_or_default());
+        }
+
+        Command::Unduck { server } => {
+            let resp = reqwest::Client::new()
+                .delete(format!("{server}/duck"))
+                .send()
+                .await
+                .expect("request failed");
+            println!("{}", resp.text().await.unwrap_or_default());
+        }
+
+        Command::Cache { action } => match action {
+            CacheAction::Stats { dir, server } => {
+                let dir = cache_dir_or_exit(dir);
+                let stats = synth_cache::SynthCache::open(&dir, u64::MAX)
+                    .map(|cache| cache.stats())
+                    .unwrap_or_else(|e| {
+                        eprintln!("{e}");
+                        std::process::exit(1)
+                    });
+                println!("directory: {}", dir.display());
+                println!("clips:     {}", stats.entries);
+                println!("size:      {:.1} MiB", stats.bytes as f64 / (1024.0 * 1024.0));
+
+                // Hit counts live in the server, if one is running.
+                let status = match reqwest::Client::new()
+                    .get(format!("{server}/status"))
+                    .send()
+                    .await
+                {
+                    Ok(resp) => resp.json::<serde_json::Value>().await.ok(),
+                    Err(_) => None,
+                };
+                match status.as_ref().and_then(|s| s.get("cache")) {
+                    Some(cache) => println!(
+                        "hits:      {} (misses: {})",
+                        cache["hits"], cache["misses"]
+                    ),
+                    None => println!("hits:      - (server not running or uncached)"),
+                }
+            }
+            CacheAction::Clear { dir } => {
+                let dir = cache_dir_or_exit(dir);
+                match synth_cache::clear_dir(&dir) {
+                    Ok(removed) => println!("removed {removed} clips from {}", dir.display()),
+                    Err(e) => {
+                        eprintln!("{e}");
+                        std::process::exit(1);
+                    }
+                }
+            }
+        },
+
         Command::Stop { server } => post_simple(&server, "stop").await,
         Command::Skip { server } => post_simple(&server, "skip").await,
         Command::Pause { server } => post_simple(&server, "pause").await,
         Command::Resume { server } => post_simple(&server, "resume").await,
+        Command::Replay { server } => post_simple(&server, "replay").await,
+        Command::Previous { server } => post_simple(&server, "previous").await,
+
+        Command::Rewind { seconds, server } => {
+            let resp = reqwest::Client::new()
+                .post(format!("{server}/rewind"))
+                .json(&serde_json::json!({ "seconds": seconds }))
+                .send()
+                .await
+                .expect("request failed");
+            println!("{}", resp.text().await.unwrap_or_default());
+        }
 
         Command::Status { server } => {
             let resp = reqwest::Client::new()
@@ -145,6 +840,42 @@ async fn main() {
     }
 }
 
+/// Parses a pause flag, in milliseconds, up to [`MAX_PAUSE_MS`].
+fn pause_ms() -> clap::builder::RangedU64ValueParser {
+    clap::value_parser!(u64).range(..=MAX_PAUSE_MS)
+}
+
+/// Parses a speed flag: a positive number.
+fn speed(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(speed) if speed.is_finite() && speed > 0.0 => Ok(speed),
+        _ => Err(format!("invalid speed {s}; must be a positive number")),
+    }
+}
+
+/// Resolves on Ctrl-C, or SIGTERM on Unix.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(_) => std::future::pending().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        () = ctrl_c => {}
+        () = terminate => {}
+    }
+}
+
 async fn post_simple(server: &str, endpoint: &str) {
     let resp = reqwest::Client::new()
         .post(format!("{server}/{endpoint}"))
@@ -153,3 +884,129 @@ async fn post_simple(server: &str, endpoint: &str) {
         .expect("request failed");
     println!("{}", resp.text().await.unwrap_or_default());
 }
+
+/// Redraw a one-line progress bar on stderr.
+fn print_progress(done: usize, total: usize) {
+    const WIDTH: usize = 30;
+    let filled = WIDTH * done / total.max(1);
+    eprint!(
+        "\r[{}{}] {done}/{total} sentences",
+        "#".repeat(filled),
+        " ".repeat(WIDTH - filled)
+    );
+    if done == total {
+        eprintln!();
+    }
+}
+
+fn print_voices(voices: &[VoiceInfo]) {
+    let width = voices.iter().map(|v| v.name.len()).max().unwrap_or(0);
+    for voice in voices {
+        let kind = serde_json::to_value(voice.kind).unwrap_or_default();
+        let gender = voice
+            .gender
+            .map(|g| serde_json::to_value(g).unwrap_or_default());
+        println!(
+            "{:width$}  {:7}  {:6}  {:6}  {}",
+            voice.name,
+            kind.as_str().unwrap_or_default(),
+            voice.language.as_deref().unwrap_or("-"),
+            gender.as_ref().and_then(|g| g.as_str()).unwrap_or("-"),
+            voice.display_name,
+        );
+    }
+}
+
+/// Write each voice's preview to `<dir>/<name>.wav`.
+fn save_previews(voices: &[VoiceInfo], dir: &std::path::Path) {
+    use base64::Engine;
+
+    std::fs::create_dir_all(dir).expect("failed to create preview directory");
+    for voice in voices {
+        let Some(wav) = voice
+            .preview
+            .as_deref()
+            .and_then(|uri| uri.strip_prefix("data:audio/wav;base64,"))
+            .and_then(|data| base64::engine::general_purpose::STANDARD.decode(data).ok())
+        else {
+            eprintln!("{}: no preview", voice.name);
+            continue;
+        };
+        // Blends contain `:` and `+`, which some filesystems reject.
+        let file: String = voice
+            .name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let path = dir.join(format!("{file}.wav"));
+        match std::fs::write(&path, wav) {
+            Ok(()) => eprintln!("wrote {}", path.display()),
+            Err(e) => eprintln!("{}: {e}", path.display()),
+        }
+    }
+}
+
+/// Construct the speech backend `serve` and `render` synthesize through.
+async fn build_synth(
+    backend: Backend,
+    model: Option<String>,
+    voices: Option<String>,
+    backend_url: Option<String>,
+    user_voices: Option<std::path::PathBuf>,
+) -> Arc<dyn Synthesizer> {
+    match backend {
+        Backend::Kokoro => {
+            let (Some(model), Some(voices)) = (model, voices) else {
+                missing_arg("--model and --voices are required for the kokoro backend");
+            };
+            eprintln!("loading kokoro model...");
+            let kokoro = nayru_lib::kokoro::KokoroSynth::new(
+                std::path::Path::new(&model),
+                std::path::Path::new(&voices),
+            )
+            .await
+            .expect("failed to load kokoro model");
+            let user_voices = user_voices
+                .or_else(nayru_lib::voice_pack::default_user_voices_dir);
+            if let Some(dir) = user_voices {
+                match kokoro.load_user_voices(&dir) {
+                    Ok(count) => {
+                        eprintln!("voices: {count} custom voices from {}", dir.display())
+                    }
+                    Err(e) => eprintln!("voices: {e}"),
+                }
+            }
+            Arc::new(kokoro)
+        }
+        Backend::Http => {
+            let Some(url) = backend_url else {
+                missing_arg("--backend-url is required for the http backend");
+            };
+            eprintln!("using speech server at {url}");
+            Arc::new(
+                nayru_lib::http_synth::HttpSynth::new(url)
+                    .discover_voices()
+                    .await,
+            )
+        }
+        Backend::Mock => Arc::new(nayru_lib::mock_synth::MockSynth::default()),
+    }
+}
+
+fn cache_dir_or_exit(dir: Option<std::path::PathBuf>) -> std::path::PathBuf {
+    dir.or_else(synth_cache::default_dir)
+        .unwrap_or_else(|| missing_arg("no data directory on this system; pass --dir"))
+}
+
+/// Exit with a clap usage error.
+fn missing_arg(message: &str) -> ! {
+    Cli::command()
+        .error(ErrorKind::MissingRequiredArgument, message)
+        .exit()
+}
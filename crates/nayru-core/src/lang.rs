@@ -0,0 +1,210 @@
+//! Languages — espeak-ng codes, Kokoro voice prefixes, and detection.
+//!
+//! Languages are espeak-ng voice codes (`en-us`, `fr-fr`, `cmn`, ...), the
+//! same strings `SpeakOptions::language` carries. Kokoro v1.0 voice names
+//! start with a letter for their language (`af_heart`, `ef_dora`, ...), so a
+//! voice implies a default language.
+
+/// Language used when nothing else says otherwise.
+pub const DEFAULT_LANGUAGE: &str = "en-us";
+
+/// Kokoro v1.0 voice prefix → espeak-ng language.
+const VOICE_PREFIXES: &[(char, &str)] = &[
+    ('a', "en-us"),
+    ('b', "en-gb"),
+    ('e', "es"),
+    ('f', "fr-fr"),
+    ('h', "hi"),
+    ('i', "it"),
+    ('j', "ja"),
+    ('p', "pt-br"),
+    ('z', "cmn"),
+];
+
+/// The language a Kokoro voice speaks, from its first letter
+/// (`bf_emma` → `en-gb`). `None` for names that don't follow the scheme.
+pub fn language_for_voice(voice: &str) -> Option<&'static str> {
+    let mut chars = voice.chars();
+    let prefix = chars.next()?;
+    // Kokoro names are `<language><gender>_<name>`.
+    if !matches!(chars.next(), Some('f' | 'm')) || chars.next() != Some('_') {
+        return None;
+    }
+    VOICE_PREFIXES
+        .iter()
+        .find(|(p, _)| *p == prefix)
+        .map(|(_, language)| *language)
+}
+
+/// The language part of a code, lowercased: `en-GB` → `en`, `cmn` → `cmn`.
+pub fn base_language(language: &str) -> String {
+    language
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+/// A short introduction in `language`, for voice previews:
+/// `("es", "Dora")` → "Hola, soy Dora.". Unknown languages get English.
+pub fn sample_sentence(language: &str, name: &str) -> String {
+    match base_language(language).as_str() {
+        "es" => format!("Hola, soy {name}."),
+        "fr" => format!("Bonjour, je suis {name}."),
+        "it" => format!("Ciao, sono {name}."),
+        "pt" => format!("Olá, eu sou {name}."),
+        "hi" => format!("नमस्ते, मैं {name} हूँ।"),
+        "ja" => format!("こんにちは、{name}です。"),
+        "cmn" => format!("你好，我是{name}。"),
+        _ => format!("Hello, I'm {name}."),
+    }
+}
+
+/// Common words that mark a Latin-script language.
+const STOPWORDS: &[(&str, &[&str])] = &[
+    (
+        "en-us",
+        &[
+            "the", "and", "is", "are", "was", "of", "to", "with", "that", "this", "it", "you",
+            "for", "have", "not", "be", "they", "what", "from",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "el", "la", "los", "las", "y", "es", "que", "de", "en", "un", "una", "por", "con",
+            "para", "del", "está", "pero", "como", "muy", "se", "su", "al", "lo",
+        ],
+    ),
+    (
+        "fr-fr",
+        &[
+            "le", "la", "les", "et", "est", "que", "de", "des", "un", "une", "pour", "dans",
+            "avec", "pas", "sur", "du", "au", "ce", "qui", "je", "vous", "nous", "il", "elle",
+        ],
+    ),
+    (
+        "it",
+        &[
+            "il", "lo", "la", "gli", "le", "e", "è", "che", "di", "un", "una", "per", "con",
+            "non", "sono", "del", "della", "nel", "questo", "anche", "ma",
+        ],
+    ),
+    (
+        "pt-br",
+        &[
+            "o", "a", "os", "as", "e", "é", "que", "de", "um", "uma", "para", "com", "não",
+            "em", "do", "da", "no", "na", "mas", "você", "isso", "muito",
+        ],
+    ),
+];
+
+/// Letters that only (or mostly) occur in one of the Latin-script languages.
+const MARKERS: &[(&str, &[char])] = &[
+    ("es", &['ñ', '¿', '¡']),
+    ("fr-fr", &['ç', 'œ', 'ê', 'è', 'ë', 'î', 'û']),
+    ("pt-br", &['ã', 'õ', 'ç']),
+];
+
+/// Guess the language of a sentence.
+///
+/// Scripts decide first (Devanagari → Hindi, kana → Japanese, Han →
+/// Mandarin). Latin-script text is scored on common function words and
+/// language-specific letters for English, Spanish, French, Italian, and
+/// Portuguese. Returns `None` when the text is too short or ambiguous —
+/// callers fall back to the configured language.
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let (mut letters, mut devanagari, mut kana, mut han) = (0usize, 0usize, 0usize, 0usize);
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        letters += 1;
+        match c {
+            '\u{0900}'..='\u{097F}' => devanagari += 1,
+            '\u{3040}'..='\u{30FF}' => kana += 1,
+            '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' => han += 1,
+            _ => {}
+        }
+    }
+    if letters == 0 {
+        return None;
+    }
+    if devanagari * 2 >= letters {
+        return Some("hi");
+    }
+    if kana > 0 && (kana + han) * 2 >= letters {
+        return Some("ja");
+    }
+    if han * 2 >= letters {
+        return Some("cmn");
+    }
+
+    let lower = text.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .collect();
+    let mut scores: Vec<(&'static str, usize)> = STOPWORDS
+        .iter()
+        .map(|(language, stopwords)| {
+            let hits = words.iter().filter(|w| stopwords.contains(w)).count();
+            (*language, hits)
+        })
+        .collect();
+    for (language, chars) in MARKERS {
+        let hits = lower.chars().filter(|c| chars.contains(c)).count();
+        if let Some(score) = scores.iter_mut().find(|(l, _)| l == language) {
+            score.1 += hits * 2;
+        }
+    }
+
+    scores.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+    let (best, score) = scores[0];
+    let runner_up = scores[1].1;
+    (score >= 2 && score > runner_up).then_some(best)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn voice_prefixes() {
+        assert_eq!(language_for_voice("af_heart"), Some("en-us"));
+        assert_eq!(language_for_voice("bm_george"), Some("en-gb"));
+        assert_eq!(language_for_voice("ef_dora"), Some("es"));
+        assert_eq!(language_for_voice("ff_siwis"), Some("fr-fr"));
+        assert_eq!(language_for_voice("zf_xiaobei"), Some("cmn"));
+        assert_eq!(language_for_voice("xf_unknown"), None);
+        assert_eq!(language_for_voice("custom"), None);
+        assert_eq!(language_for_voice(""), None);
+    }
+
+    #[test]
+    fn base_language_strips_region() {
+        assert_eq!(base_language("en-GB"), "en");
+        assert_eq!(base_language("pt_br"), "pt");
+        assert_eq!(base_language("cmn"), "cmn");
+    }
+
+    #[test]
+    fn detects_scripts() {
+        assert_eq!(detect_language("नमस्ते दुनिया"), Some("hi"));
+        assert_eq!(detect_language("こんにちは、世界。"), Some("ja"));
+        assert_eq!(detect_language("你好，世界。"), Some("cmn"));
+    }
+
+    #[test]
+    fn detects_latin_languages() {
+        assert_eq!(detect_language("The cat is on the mat."), Some("en-us"));
+        assert_eq!(detect_language("El gato está en la casa con los niños."), Some("es"));
+        assert_eq!(detect_language("Le chat est dans la maison avec nous."), Some("fr-fr"));
+        assert_eq!(detect_language("Il gatto è nella casa con gli amici."), Some("it"));
+        assert_eq!(detect_language("O gato não está em casa, você sabe."), Some("pt-br"));
+    }
+
+    #[test]
+    fn ambiguous_text_is_undetected() {
+        assert_eq!(detect_language("Okay."), None);
+        assert_eq!(detect_language("42"), None);
+        assert_eq!(detect_language(""), None);
+    }
+}
@@ -2,6 +2,8 @@
 //!
 //! No async runtime, no I/O, no platform dependencies.
 
+pub mod lang;
 pub mod text_prep;
+pub mod timing;
 pub mod types;
 pub mod wav;
@@ -1,10 +1,18 @@
-//! Text preparation for TTS — markdown cleaning and sentence splitting.
+//! Text preparation for TTS — markdown cleaning, number spelling, sentence
+//! splitting, and SSML parsing.
 //!
 //! Pure functions, no I/O. Ported from `raia-app/lib/voice.ts`.
 
 use regex::Regex;
 use std::sync::LazyLock;
 
+use crate::lang::{base_language, DEFAULT_LANGUAGE};
+
+mod numbers;
+mod ssml;
+
+pub use ssml::{parse_ssml, SsmlSegment, SsmlStyle, MAX_BREAK};
+
 // Compiled regexes — allocated once, reused across calls.
 static RE_TABLE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"(?m)(?:^|\n)(\|[^\n]+\|(?:\n\|[^\n]+\|)*)").unwrap());
@@ -32,6 +40,8 @@ static RE_DOUBLE_DOT: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"\.\s*\.").unwrap());
 static RE_MULTI_SPACE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"\s{2,}").unwrap());
+static RE_PARAGRAPH_BREAK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\n[ \t]*\n\s*").unwrap());
 static RE_NUMBER: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"\d+(\.\d+)?").unwrap());
 
@@ -114,6 +124,31 @@ fn numbers_to_english(text: &str) -> String {
 /// headings, links, bullets/numbered lists, horizontal rules.
 /// Also converts numbers to English words for proper TTS pronunciation.
 pub fn clean_text_for_tts(text: &str) -> String {
+    clean_text_for_language(text, DEFAULT_LANGUAGE)
+}
+
+/// [`clean_text_for_tts`] with numbers spelled in `language` (an espeak-ng
+/// code). Languages without number words here keep their digits, which
+/// espeak-ng reads itself.
+pub fn clean_text_for_language(text: &str, language: &str) -> String {
+    spell_numbers(&strip_markdown(text), language)
+}
+
+/// Spell out the numbers in `text` in `language`: English, Spanish, French,
+/// Italian, and Portuguese. Other languages are returned unchanged.
+pub fn spell_numbers(text: &str, language: &str) -> String {
+    let base = base_language(language);
+    if base == "en" {
+        return numbers_to_english(text);
+    }
+    match numbers::Locale::for_base(&base) {
+        Some(locale) => numbers::spell_numbers(text, locale),
+        None => text.to_string(),
+    }
+}
+
+/// The markdown half of [`clean_text_for_tts`]: everything but numbers.
+pub fn strip_markdown(text: &str) -> String {
     let mut c = text.to_string();
 
     // Tables → placeholder (before code blocks, since tables can appear inside fences)
@@ -139,8 +174,6 @@ pub fn clean_text_for_tts(text: &str) -> String {
     c = RE_LEADING_DOT.replace(&c, "").into_owned();
     // Double periods → single
     c = RE_DOUBLE_DOT.replace_all(&c, ".").into_owned();
-    // Numbers → English words (before kokoro's g2p converts them to Chinese)
-    c = numbers_to_english(&c);
     // Collapse whitespace
     c = RE_MULTI_SPACE.replace_all(&c, " ").into_owned();
 
@@ -194,6 +227,22 @@ pub fn split_text(text: &str, max_len: usize) -> Vec<String> {
     result
 }
 
+/// Split text into sentences, then break any sentence longer than `max_len`
+/// with [`split_text`].
+///
+/// This is the unit of synthesis: one Kokoro call per returned chunk.
+pub fn split_sentence_chunks(text: &str, max_len: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    for sentence in split_sentences(text) {
+        if sentence.len() <= max_len {
+            chunks.push(sentence);
+        } else {
+            chunks.extend(split_text(&sentence, max_len));
+        }
+    }
+    chunks
+}
+
 /// Split text into sentences at sentence-ending punctuation (`. `, `! `, `? `)
 /// or paragraph breaks (double newlines).
 ///
@@ -260,6 +309,66 @@ pub fn split_sentences(text: &str) -> Vec<String> {
     sentences
 }
 
+/// What ends a sentence, for the pause after it. Ordered by pause length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SentenceEnd {
+    /// The text ends; nothing to pause for.
+    End,
+    /// Another sentence of the same paragraph follows.
+    Sentence,
+    /// A list item, or the line leading into a list.
+    ListItem,
+    /// A paragraph (a blank line follows).
+    Paragraph,
+}
+
+/// [`strip_markdown`] then [`split_sentences`], keeping track of what ends
+/// each sentence: a paragraph break, a list item, or just punctuation.
+///
+/// `strip_markdown` flattens paragraphs and turns list items into `. `
+/// prefixes, so the text is cut into paragraphs and items first and each
+/// piece cleaned on its own.
+pub fn split_spoken(text: &str) -> Vec<(String, SentenceEnd)> {
+    // Code blocks and tables can hold blank lines; replace them first.
+    let text = RE_TABLE.replace_all(text, "\nSee the table in our conversation.\n");
+    let text = RE_FENCED_CODE.replace_all(&text, " See the code in our conversation. ");
+
+    let mut sentences: Vec<(String, SentenceEnd)> = Vec::new();
+    let mark_end = |sentences: &mut Vec<(String, SentenceEnd)>, end: SentenceEnd| {
+        if let Some(last) = sentences.last_mut() {
+            last.1 = last.1.max(end);
+        }
+    };
+    for paragraph in RE_PARAGRAPH_BREAK.split(&text) {
+        let mut block = String::new();
+        for line in paragraph.lines() {
+            let item = RE_BULLET.is_match(line) || RE_NUMBERED.is_match(line);
+            if item && !block.trim().is_empty() {
+                sentences.extend(
+                    split_sentences(&strip_markdown(&block))
+                        .into_iter()
+                        .map(|s| (s, SentenceEnd::Sentence)),
+                );
+                mark_end(&mut sentences, SentenceEnd::ListItem);
+                block.clear();
+            }
+            block.push_str(line);
+            block.push('\n');
+        }
+        sentences.extend(
+            split_sentences(&strip_markdown(&block))
+                .into_iter()
+                .map(|s| (s, SentenceEnd::Sentence)),
+        );
+        mark_end(&mut sentences, SentenceEnd::Paragraph);
+    }
+    // Nothing to pause for after the last sentence.
+    if let Some(last) = sentences.last_mut() {
+        last.1 = SentenceEnd::End;
+    }
+    sentences
+}
+
 /// Find a word boundary, or fall back to a hard split at a char boundary.
 fn word_boundary_or_hard(window: &str, _max_len: usize) -> usize {
     if let Some(pos) = window.rfind(' ') {
@@ -402,6 +511,17 @@ mod tests {
         assert_eq!(clean_text_for_tts("In 2024, we shipped 15 features"), "In two thousand twenty four, we shipped fifteen features");
     }
 
+    #[test]
+    fn spells_numbers_per_language() {
+        assert_eq!(
+            clean_text_for_language("**Tengo** 3 gatos", "es"),
+            "Tengo tres gatos"
+        );
+        assert_eq!(clean_text_for_language("Il a 21 ans", "fr-fr"), "Il a vingt et un ans");
+        assert_eq!(clean_text_for_language("I have 3 cats", "en-gb"), "I have three cats");
+        assert_eq!(clean_text_for_language("猫が3匹", "ja"), "猫が3匹");
+    }
+
     #[test]
     fn number_to_words_basic() {
         assert_eq!(number_to_words("0"), "zero");
@@ -493,6 +613,25 @@ mod tests {
 
     // ── split_sentences ───────────────────────────────────────────
 
+    #[test]
+    fn split_spoken_marks_paragraphs_and_list_items() {
+        let text = "# Plan\n\nFirst **this**. Then that.\n\nSteps:\n- fetch\n- build it\n\n```\nlet a = 1;\n\nlet b = 2;\n```\n\nDone.";
+        assert_eq!(
+            split_spoken(text),
+            vec![
+                ("Plan".to_string(), SentenceEnd::Paragraph),
+                ("First this.".to_string(), SentenceEnd::Sentence),
+                ("Then that.".to_string(), SentenceEnd::Paragraph),
+                ("Steps:".to_string(), SentenceEnd::ListItem),
+                ("fetch".to_string(), SentenceEnd::ListItem),
+                ("build it".to_string(), SentenceEnd::Paragraph),
+                ("See the code in our conversation.".to_string(), SentenceEnd::Paragraph),
+                ("Done.".to_string(), SentenceEnd::End),
+            ]
+        );
+        assert!(split_spoken("").is_empty());
+    }
+
     #[test]
     fn split_sentences_basic() {
         let s = split_sentences("Hello world. How are you? I am fine!");
@@ -523,6 +662,16 @@ mod tests {
         assert_eq!(s, vec!["Hello.", "World."]);
     }
 
+    #[test]
+    fn split_sentence_chunks_splits_long_sentences() {
+        let long = format!("{} end.", "word ".repeat(20).trim());
+        let text = format!("Short one. {long}");
+        let chunks = split_sentence_chunks(&text, 40);
+        assert_eq!(chunks[0], "Short one.");
+        assert!(chunks.len() > 2);
+        assert!(chunks.iter().all(|c| c.len() <= 40));
+    }
+
     #[test]
     fn split_sentences_mixed_punctuation() {
         let s = split_sentences("Really? Yes! OK. Done");
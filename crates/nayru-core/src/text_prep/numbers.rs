@@ -0,0 +1,429 @@
+//! Number words for Spanish, French, Italian, and Portuguese.
+//!
+//! These languages write `1.000,5` where English writes `1,000.5`: a dot
+//! followed by groups of three digits separates thousands, a comma starts the
+//! decimals. Integers are spelled up to the hundreds of millions; larger
+//! numbers and all decimals are read digit by digit, like the English path.
+
+use regex::{Captures, Regex};
+use std::sync::LazyLock;
+
+/// `1.234.567,89`, `3,5`, `3.5`, `42`.
+static RE_NUMBER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\d{1,3}(?:\.\d{3})+(?:,\d+)?|\d+(?:[.,]\d+)?").unwrap()
+});
+
+/// A language with number words here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Locale {
+    Spanish,
+    French,
+    Italian,
+    Portuguese,
+}
+
+impl Locale {
+    /// The locale for a base language code (`es`, `fr`, `it`, `pt`).
+    pub(super) fn for_base(base: &str) -> Option<Self> {
+        match base {
+            "es" => Some(Self::Spanish),
+            "fr" => Some(Self::French),
+            "it" => Some(Self::Italian),
+            "pt" => Some(Self::Portuguese),
+            _ => None,
+        }
+    }
+
+    fn decimal_separator(self) -> &'static str {
+        match self {
+            Self::Spanish => "coma",
+            Self::French => "virgule",
+            Self::Italian => "virgola",
+            Self::Portuguese => "vírgula",
+        }
+    }
+
+    fn digit(self, d: u64) -> String {
+        self.integer(d)
+    }
+
+    fn integer(self, n: u64) -> String {
+        if n >= 1_000_000_000 {
+            return n
+                .to_string()
+                .chars()
+                .map(|c| self.digit(c.to_digit(10).unwrap_or(0) as u64))
+                .collect::<Vec<_>>()
+                .join(" ");
+        }
+        match self {
+            Self::Spanish => es_int(n),
+            Self::French => fr_int(n),
+            Self::Italian => it_int(n),
+            Self::Portuguese => pt_int(n),
+        }
+    }
+
+    /// Spell one matched number.
+    fn spell(self, s: &str) -> String {
+        let (integer, decimals) = if s.contains(',') {
+            let (i, d) = s.split_once(',').unwrap();
+            (i.replace('.', ""), Some(d))
+        } else if s.matches('.').count() == 1 && !is_grouped(s) {
+            let (i, d) = s.split_once('.').unwrap();
+            (i.to_string(), Some(d))
+        } else {
+            (s.replace('.', ""), None)
+        };
+        let mut words = self.integer(integer.parse().unwrap_or(0));
+        if let Some(decimals) = decimals {
+            words.push(' ');
+            words.push_str(self.decimal_separator());
+            for c in decimals.chars() {
+                words.push(' ');
+                words.push_str(&self.digit(c.to_digit(10).unwrap_or(0) as u64));
+            }
+        }
+        words
+    }
+}
+
+/// `1.000` (thousands) rather than `1.5` (decimal).
+fn is_grouped(s: &str) -> bool {
+    s.split_once('.').is_some_and(|(_, d)| d.len() == 3)
+}
+
+/// Replace every number in `text` with words in `locale`.
+pub(super) fn spell_numbers(text: &str, locale: Locale) -> String {
+    RE_NUMBER
+        .replace_all(text, |caps: &Captures| {
+            let m = caps.get(0).unwrap();
+            let words = locale.spell(m.as_str());
+            // Keep "H1" from becoming one word
+            if m.start() > 0 && text.as_bytes()[m.start() - 1].is_ascii_alphabetic() {
+                format!(" {words}")
+            } else {
+                words
+            }
+        })
+        .into_owned()
+}
+
+/// `rest` appended to `head` with `sep`, or `head` alone when `rest` is 0.
+fn join(head: String, sep: &str, rest: u64, spell: fn(u64) -> String) -> String {
+    if rest == 0 {
+        head
+    } else {
+        format!("{head}{sep}{}", spell(rest))
+    }
+}
+
+// ─── Spanish ───────────────────────────────────────────────────────────────
+
+const ES_UNITS: [&str; 30] = [
+    "cero", "uno", "dos", "tres", "cuatro", "cinco", "seis", "siete", "ocho", "nueve", "diez",
+    "once", "doce", "trece", "catorce", "quince", "dieciséis", "diecisiete", "dieciocho",
+    "diecinueve", "veinte", "veintiuno", "veintidós", "veintitrés", "veinticuatro",
+    "veinticinco", "veintiséis", "veintisiete", "veintiocho", "veintinueve",
+];
+const ES_TENS: [&str; 10] = [
+    "", "", "", "treinta", "cuarenta", "cincuenta", "sesenta", "setenta", "ochenta", "noventa",
+];
+const ES_HUNDREDS: [&str; 10] = [
+    "", "ciento", "doscientos", "trescientos", "cuatrocientos", "quinientos", "seiscientos",
+    "setecientos", "ochocientos", "novecientos",
+];
+
+fn es_int(n: u64) -> String {
+    match n {
+        0..30 => ES_UNITS[n as usize].to_string(),
+        30..100 => join(ES_TENS[(n / 10) as usize].to_string(), " y ", n % 10, es_int),
+        100 => "cien".to_string(),
+        101..1000 => join(ES_HUNDREDS[(n / 100) as usize].to_string(), " ", n % 100, es_int),
+        1000..1_000_000 => {
+            let head = match n / 1000 {
+                1 => "mil".to_string(),
+                k => format!("{} mil", es_apocope(es_int(k))),
+            };
+            join(head, " ", n % 1000, es_int)
+        }
+        _ => {
+            let head = match n / 1_000_000 {
+                1 => "un millón".to_string(),
+                m => format!("{} millones", es_apocope(es_int(m))),
+            };
+            join(head, " ", n % 1_000_000, es_int)
+        }
+    }
+}
+
+/// "uno" shortens before a noun: veintiún mil, treinta y un millones.
+fn es_apocope(words: String) -> String {
+    if let Some(stem) = words.strip_suffix("veintiuno") {
+        format!("{stem}veintiún")
+    } else if let Some(stem) = words.strip_suffix("uno") {
+        format!("{stem}un")
+    } else {
+        words
+    }
+}
+
+// ─── French ────────────────────────────────────────────────────────────────
+
+const FR_UNITS: [&str; 17] = [
+    "zéro", "un", "deux", "trois", "quatre", "cinq", "six", "sept", "huit", "neuf", "dix", "onze",
+    "douze", "treize", "quatorze", "quinze", "seize",
+];
+const FR_TENS: [&str; 7] = ["", "", "vingt", "trente", "quarante", "cinquante", "soixante"];
+
+fn fr_below_100(n: u64) -> String {
+    match n {
+        0..=16 => FR_UNITS[n as usize].to_string(),
+        17..=19 => format!("dix-{}", FR_UNITS[(n - 10) as usize]),
+        20..=69 => {
+            let tens = FR_TENS[(n / 10) as usize];
+            match n % 10 {
+                0 => tens.to_string(),
+                1 => format!("{tens} et un"),
+                u => format!("{tens}-{}", FR_UNITS[u as usize]),
+            }
+        }
+        71 => "soixante et onze".to_string(),
+        70..=79 => format!("soixante-{}", fr_below_100(n - 60)),
+        80 => "quatre-vingts".to_string(),
+        _ => format!("quatre-vingt-{}", fr_below_100(n - 80)),
+    }
+}
+
+/// 1..=999. `last` = nothing follows, so "cents"/"vingts" keep their plural.
+fn fr_below_1000(n: u64, last: bool) -> String {
+    let (h, r) = (n / 100, n % 100);
+    let mut words = match h {
+        0 => String::new(),
+        1 => "cent".to_string(),
+        _ if r == 0 && last => format!("{} cents", FR_UNITS[h as usize]),
+        _ => format!("{} cent", FR_UNITS[h as usize]),
+    };
+    if r > 0 {
+        if !words.is_empty() {
+            words.push(' ');
+        }
+        if r == 80 && !last {
+            words.push_str("quatre-vingt");
+        } else {
+            words.push_str(&fr_below_100(r));
+        }
+    }
+    words
+}
+
+fn fr_int(n: u64) -> String {
+    match n {
+        0 => FR_UNITS[0].to_string(),
+        1..1000 => fr_below_1000(n, true),
+        1000..1_000_000 => {
+            let head = match n / 1000 {
+                1 => "mille".to_string(),
+                k => format!("{} mille", fr_below_1000(k, false)),
+            };
+            join(head, " ", n % 1000, |r| fr_below_1000(r, true))
+        }
+        _ => {
+            let head = match n / 1_000_000 {
+                1 => "un million".to_string(),
+                m => format!("{} millions", fr_below_1000(m, true)),
+            };
+            join(head, " ", n % 1_000_000, fr_int)
+        }
+    }
+}
+
+// ─── Italian ───────────────────────────────────────────────────────────────
+
+const IT_UNITS: [&str; 20] = [
+    "zero", "uno", "due", "tre", "quattro", "cinque", "sei", "sette", "otto", "nove", "dieci",
+    "undici", "dodici", "tredici", "quattordici", "quindici", "sedici", "diciassette",
+    "diciotto", "diciannove",
+];
+const IT_TENS: [&str; 10] = [
+    "", "", "venti", "trenta", "quaranta", "cinquanta", "sessanta", "settanta", "ottanta",
+    "novanta",
+];
+
+fn it_below_100(n: u64) -> String {
+    if n < 20 {
+        return IT_UNITS[n as usize].to_string();
+    }
+    let tens = IT_TENS[(n / 10) as usize];
+    match n % 10 {
+        0 => tens.to_string(),
+        // venti + uno → ventuno, trenta + otto → trentotto
+        u @ (1 | 8) => format!("{}{}", &tens[..tens.len() - 1], IT_UNITS[u as usize]),
+        3 => format!("{tens}tré"),
+        u => format!("{tens}{}", IT_UNITS[u as usize]),
+    }
+}
+
+fn it_below_1000(n: u64) -> String {
+    let (h, r) = (n / 100, n % 100);
+    let head = match h {
+        0 => return it_below_100(r),
+        1 => "cento".to_string(),
+        _ => format!("{}cento", IT_UNITS[h as usize]),
+    };
+    if r == 0 {
+        return head;
+    }
+    let tail = it_below_100(r);
+    // cento + ottanta → centottanta
+    if tail.starts_with('o') {
+        format!("{}{tail}", &head[..head.len() - 1])
+    } else {
+        format!("{head}{tail}")
+    }
+}
+
+fn it_int(n: u64) -> String {
+    match n {
+        0..1000 => it_below_1000(n),
+        1000..1_000_000 => {
+            let head = match n / 1000 {
+                1 => "mille".to_string(),
+                k => format!("{}mila", it_below_1000(k)),
+            };
+            join(head, " ", n % 1000, it_below_1000)
+        }
+        _ => {
+            let head = match n / 1_000_000 {
+                1 => "un milione".to_string(),
+                m => format!("{} milioni", it_below_1000(m)),
+            };
+            join(head, " ", n % 1_000_000, it_int)
+        }
+    }
+}
+
+// ─── Portuguese (Brazilian) ────────────────────────────────────────────────
+
+const PT_UNITS: [&str; 20] = [
+    "zero", "um", "dois", "três", "quatro", "cinco", "seis", "sete", "oito", "nove", "dez", "onze",
+    "doze", "treze", "catorze", "quinze", "dezesseis", "dezessete", "dezoito", "dezenove",
+];
+const PT_TENS: [&str; 10] = [
+    "", "", "vinte", "trinta", "quarenta", "cinquenta", "sessenta", "setenta", "oitenta",
+    "noventa",
+];
+const PT_HUNDREDS: [&str; 10] = [
+    "", "cento", "duzentos", "trezentos", "quatrocentos", "quinhentos", "seiscentos",
+    "setecentos", "oitocentos", "novecentos",
+];
+
+fn pt_below_1000(n: u64) -> String {
+    match n {
+        0..20 => PT_UNITS[n as usize].to_string(),
+        20..100 => join(PT_TENS[(n / 10) as usize].to_string(), " e ", n % 10, pt_below_1000),
+        100 => "cem".to_string(),
+        _ => join(PT_HUNDREDS[(n / 100) as usize].to_string(), " e ", n % 100, pt_below_1000),
+    }
+}
+
+/// "e" joins a group to a remainder that is below 100 or a round hundred:
+/// mil e cinco, mil e quinhentos, but mil duzentos e trinta.
+fn pt_joiner(rest: u64) -> &'static str {
+    if rest < 100 || (rest < 1000 && rest.is_multiple_of(100)) {
+        " e "
+    } else {
+        " "
+    }
+}
+
+fn pt_int(n: u64) -> String {
+    match n {
+        0..1000 => pt_below_1000(n),
+        1000..1_000_000 => {
+            let head = match n / 1000 {
+                1 => "mil".to_string(),
+                k => format!("{} mil", pt_below_1000(k)),
+            };
+            join(head, pt_joiner(n % 1000), n % 1000, pt_below_1000)
+        }
+        _ => {
+            let head = match n / 1_000_000 {
+                1 => "um milhão".to_string(),
+                m => format!("{} milhões", pt_below_1000(m)),
+            };
+            join(head, pt_joiner(n % 1_000_000), n % 1_000_000, pt_int)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spanish() {
+        let es = |n| Locale::Spanish.integer(n);
+        assert_eq!(es(0), "cero");
+        assert_eq!(es(16), "dieciséis");
+        assert_eq!(es(22), "veintidós");
+        assert_eq!(es(45), "cuarenta y cinco");
+        assert_eq!(es(100), "cien");
+        assert_eq!(es(115), "ciento quince");
+        assert_eq!(es(500), "quinientos");
+        assert_eq!(es(1000), "mil");
+        assert_eq!(es(21_000), "veintiún mil");
+        assert_eq!(es(2024), "dos mil veinticuatro");
+        assert_eq!(es(1_000_000), "un millón");
+        assert_eq!(es(31_000_000), "treinta y un millones");
+    }
+
+    #[test]
+    fn french() {
+        let fr = |n| Locale::French.integer(n);
+        assert_eq!(fr(17), "dix-sept");
+        assert_eq!(fr(21), "vingt et un");
+        assert_eq!(fr(71), "soixante et onze");
+        assert_eq!(fr(77), "soixante-dix-sept");
+        assert_eq!(fr(80), "quatre-vingts");
+        assert_eq!(fr(91), "quatre-vingt-onze");
+        assert_eq!(fr(200), "deux cents");
+        assert_eq!(fr(201), "deux cent un");
+        assert_eq!(fr(1000), "mille");
+        assert_eq!(fr(80_000), "quatre-vingt mille");
+        assert_eq!(fr(2_000_000), "deux millions");
+    }
+
+    #[test]
+    fn italian() {
+        let it = |n| Locale::Italian.integer(n);
+        assert_eq!(it(21), "ventuno");
+        assert_eq!(it(23), "ventitré");
+        assert_eq!(it(38), "trentotto");
+        assert_eq!(it(180), "centottanta");
+        assert_eq!(it(1000), "mille");
+        assert_eq!(it(2024), "duemila ventiquattro");
+        assert_eq!(it(1_000_000), "un milione");
+    }
+
+    #[test]
+    fn portuguese() {
+        let pt = |n| Locale::Portuguese.integer(n);
+        assert_eq!(pt(16), "dezesseis");
+        assert_eq!(pt(42), "quarenta e dois");
+        assert_eq!(pt(100), "cem");
+        assert_eq!(pt(101), "cento e um");
+        assert_eq!(pt(1500), "mil e quinhentos");
+        assert_eq!(pt(1230), "mil duzentos e trinta");
+        assert_eq!(pt(2_000_000), "dois milhões");
+    }
+
+    #[test]
+    fn separators() {
+        let es = |s| spell_numbers(s, Locale::Spanish);
+        assert_eq!(es("1.000 euros"), "mil euros");
+        assert_eq!(es("3,5 kilos"), "tres coma cinco kilos");
+        assert_eq!(es("3.5 kilos"), "tres coma cinco kilos");
+        assert_eq!(es("H1"), "H uno");
+    }
+}
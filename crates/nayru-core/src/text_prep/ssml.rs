@@ -0,0 +1,806 @@
+//! SSML subset parser.
+//!
+//! Turns markup into a flat list of [`SsmlSegment`]s the engine can act on:
+//! text to phonemize, ready-made IPA, and pauses. Each text segment carries
+//! the voice, language, and rate in effect where it appeared.
+//!
+//! Supported elements: `<speak>`, `<break time|strength>`, `<prosody rate>`,
+//! `<say-as interpret-as="characters|digits|cardinal|ordinal|date|telephone">`,
+//! `<phoneme ph>` (IPA only), `<voice name>`, `<lang>`, `<sub alias>`,
+//! `<emphasis level>`. `<p>`, `<s>`, and unknown elements are transparent —
+//! their content is spoken, the tag is ignored.
+
+use std::time::Duration;
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+use super::number_to_words;
+
+static RE_ATTR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"([A-Za-z_][\w:.-]*)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap());
+static RE_TIME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*(\d+(?:\.\d+)?)\s*(ms|s)\s*$").unwrap());
+static RE_WHITESPACE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\s+").unwrap());
+
+/// Longest pause a single `<break>` may insert.
+pub const MAX_BREAK: Duration = Duration::from_secs(10);
+
+/// Voice, language, and rate in effect for a stretch of SSML.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SsmlStyle {
+    /// From `<voice name>`; `None` = the request's voice.
+    pub voice: Option<String>,
+    /// From `xml:lang` on `<speak>`, `<voice>`, or `<lang>`.
+    pub language: Option<String>,
+    /// Speed multiplier from `<prosody rate>` and `<emphasis>`, nested
+    /// elements compounding. `1.0` = unchanged.
+    pub rate: f32,
+}
+
+impl Default for SsmlStyle {
+    fn default() -> Self {
+        Self {
+            voice: None,
+            language: None,
+            rate: 1.0,
+        }
+    }
+}
+
+/// One piece of parsed SSML.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SsmlSegment {
+    /// Plain text, with whitespace collapsed. Adjacent text in the same
+    /// style is merged into one segment.
+    Text { text: String, style: SsmlStyle },
+    /// IPA from `<phoneme ph>`, to be synthesized as-is. `text` is the
+    /// element's content, for display.
+    Phoneme {
+        ipa: String,
+        text: String,
+        style: SsmlStyle,
+    },
+    /// Silence from `<break>`.
+    Break(Duration),
+}
+
+/// An open element and what to do with its content.
+struct Frame {
+    name: String,
+    style: SsmlStyle,
+    /// `Some` for elements that rewrite their content (`<sub>`, `<phoneme>`,
+    /// `<say-as>`): the content is buffered here until the element closes.
+    capture: Option<Capture>,
+}
+
+enum Capture {
+    Sub {
+        alias: String,
+    },
+    Phoneme {
+        ipa: String,
+    },
+    SayAs {
+        interpret_as: String,
+        format: Option<String>,
+    },
+}
+
+/// Parse an SSML document (or fragment) into segments.
+///
+/// The `<speak>` root is optional. Fails on malformed markup, mismatched
+/// tags, and invalid attribute values, so callers can reject the request
+/// instead of reading tags aloud.
+pub fn parse_ssml(input: &str) -> Result<Vec<SsmlSegment>, String> {
+    let mut segments = Vec::new();
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut captured = String::new();
+    let mut rest = input;
+
+    while !rest.is_empty() {
+        let Some(lt) = rest.find('<') else {
+            push_content(&mut segments, &stack, &mut captured, rest);
+            break;
+        };
+        push_content(&mut segments, &stack, &mut captured, &rest[..lt]);
+        rest = &rest[lt..];
+
+        // Comments, processing instructions, and doctypes are skipped.
+        if let Some(body) = rest.strip_prefix("<!--") {
+            let end = body.find("-->").ok_or("unterminated comment")?;
+            rest = &body[end + 3..];
+            continue;
+        }
+        if rest.starts_with("<?") || rest.starts_with("<!") {
+            let end = rest.find('>').ok_or("unterminated declaration")?;
+            rest = &rest[end + 1..];
+            continue;
+        }
+
+        let end = tag_end(rest).ok_or_else(|| format!("unterminated tag: {}", preview(rest)))?;
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        if let Some(name) = tag.strip_prefix('/') {
+            let name = name.trim();
+            let frame = stack
+                .pop()
+                .ok_or_else(|| format!("unexpected closing tag </{name}>"))?;
+            if frame.name != name {
+                return Err(format!(
+                    "mismatched closing tag </{name}>; expected </{}>",
+                    frame.name
+                ));
+            }
+            if let Some(capture) = frame.capture {
+                let content = std::mem::take(&mut captured);
+                close_capture(&mut segments, capture, &content, frame.style)?;
+            }
+            continue;
+        }
+
+        let self_closing = tag.ends_with('/');
+        let tag = tag.trim_end_matches('/');
+        let (name, attrs) = match tag.find(char::is_whitespace) {
+            Some(i) => (&tag[..i], &tag[i..]),
+            None => (tag, ""),
+        };
+        if name.is_empty() {
+            return Err("empty tag name".to_string());
+        }
+        let attrs = parse_attrs(attrs);
+        let attr = |key: &str| {
+            attrs
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str())
+        };
+
+        let mut style = stack.last().map(|f| f.style.clone()).unwrap_or_default();
+        let mut capture = None;
+        match name {
+            "break" => {
+                let pause = break_duration(attr("time"), attr("strength"))?;
+                segments.push(SsmlSegment::Break(pause));
+            }
+            "speak" | "lang" => {
+                if let Some(lang) = attr("xml:lang") {
+                    style.language = Some(lang.to_lowercase());
+                }
+            }
+            "voice" => {
+                if let Some(voice) = attr("name") {
+                    style.voice = Some(voice.to_string());
+                }
+                if let Some(lang) = attr("xml:lang").or(attr("language")) {
+                    style.language = Some(lang.to_lowercase());
+                }
+            }
+            "prosody" => {
+                if let Some(rate) = attr("rate") {
+                    style.rate *= parse_rate(rate)?;
+                }
+            }
+            "emphasis" => {
+                style.rate *= match attr("level").unwrap_or("moderate") {
+                    "strong" => 0.85,
+                    "moderate" => 0.9,
+                    "reduced" => 1.1,
+                    "none" => 1.0,
+                    other => return Err(format!("invalid emphasis level '{other}'")),
+                };
+            }
+            "sub" => {
+                let alias = attr("alias").ok_or("<sub> requires an alias attribute")?;
+                capture = Some(Capture::Sub {
+                    alias: alias.to_string(),
+                });
+            }
+            "phoneme" => {
+                if let Some(alphabet) = attr("alphabet").filter(|a| !a.eq_ignore_ascii_case("ipa"))
+                {
+                    return Err(format!(
+                        "unsupported phoneme alphabet '{alphabet}'; only ipa is supported"
+                    ));
+                }
+                let ipa = attr("ph").ok_or("<phoneme> requires a ph attribute")?;
+                capture = Some(Capture::Phoneme {
+                    ipa: ipa.trim().to_string(),
+                });
+            }
+            "say-as" => {
+                let interpret_as =
+                    attr("interpret-as").ok_or("<say-as> requires an interpret-as attribute")?;
+                capture = Some(Capture::SayAs {
+                    interpret_as: interpret_as.to_string(),
+                    format: attr("format").map(str::to_string),
+                });
+            }
+            _ => {}
+        }
+
+        // Elements nested inside a capturing element only contribute text.
+        if stack.iter().any(|f| f.capture.is_some()) {
+            capture = None;
+        }
+
+        if self_closing {
+            if let Some(capture) = capture {
+                close_capture(&mut segments, capture, "", style)?;
+            }
+        } else {
+            stack.push(Frame {
+                name: name.to_string(),
+                style,
+                capture,
+            });
+        }
+    }
+
+    if let Some(frame) = stack.last() {
+        return Err(format!("unclosed tag <{}>", frame.name));
+    }
+    Ok(segments)
+}
+
+/// Route text either into the innermost capturing element or out as a segment.
+fn push_content(
+    segments: &mut Vec<SsmlSegment>,
+    stack: &[Frame],
+    captured: &mut String,
+    raw: &str,
+) {
+    if raw.is_empty() {
+        return;
+    }
+    let text = decode_entities(raw);
+    if stack.iter().any(|f| f.capture.is_some()) {
+        captured.push_str(&text);
+        return;
+    }
+    let style = stack.last().map(|f| f.style.clone()).unwrap_or_default();
+    push_text(segments, &text, style);
+}
+
+/// Append text, merging with the previous segment when the style matches.
+fn push_text(segments: &mut Vec<SsmlSegment>, text: &str, style: SsmlStyle) {
+    let text = RE_WHITESPACE.replace_all(text, " ");
+    if text.trim().is_empty() && !matches!(segments.last(), Some(SsmlSegment::Text { .. })) {
+        return;
+    }
+    if let Some(SsmlSegment::Text {
+        text: prev,
+        style: prev_style,
+    }) = segments.last_mut()
+        && *prev_style == style
+    {
+        match text.strip_prefix(' ') {
+            Some(trimmed) if prev.ends_with(' ') => prev.push_str(trimmed),
+            _ => prev.push_str(&text),
+        }
+        return;
+    }
+    segments.push(SsmlSegment::Text {
+        text: text.into_owned(),
+        style,
+    });
+}
+
+/// Emit the segment for a closed `<sub>`, `<phoneme>`, or `<say-as>`.
+fn close_capture(
+    segments: &mut Vec<SsmlSegment>,
+    capture: Capture,
+    content: &str,
+    style: SsmlStyle,
+) -> Result<(), String> {
+    let content = RE_WHITESPACE.replace_all(content.trim(), " ");
+    match capture {
+        Capture::Sub { alias } => push_text(segments, &alias, style),
+        Capture::Phoneme { ipa } => {
+            if ipa.is_empty() {
+                return Err("<phoneme> has an empty ph attribute".to_string());
+            }
+            segments.push(SsmlSegment::Phoneme {
+                ipa,
+                text: content.into_owned(),
+                style,
+            });
+        }
+        Capture::SayAs {
+            interpret_as,
+            format,
+        } => {
+            let spoken = say_as(&interpret_as, format.as_deref(), &content);
+            push_text(segments, &spoken, style);
+        }
+    }
+    Ok(())
+}
+
+/// Index of the `>` closing the tag at the start of `s`, skipping quoted
+/// attribute values.
+fn tag_end(s: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in s.char_indices().skip(1) {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '>') => return Some(i),
+            (None, '<') => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_attrs(s: &str) -> Vec<(String, String)> {
+    RE_ATTR
+        .captures_iter(s)
+        .map(|caps| {
+            let value = caps.get(2).or(caps.get(3)).map_or("", |m| m.as_str());
+            (caps[1].to_string(), decode_entities(value))
+        })
+        .collect()
+}
+
+fn preview(s: &str) -> &str {
+    let end = s.char_indices().nth(20).map_or(s.len(), |(i, _)| i);
+    &s[..end]
+}
+
+fn decode_entities(s: &str) -> String {
+    if !s.contains('&') {
+        return s.to_string();
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').and_then(|semi| {
+            let entity = &rest[1..semi];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => entity
+                    .strip_prefix("#x")
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| entity.strip_prefix('#').map(str::parse))
+                    .and_then(Result::ok)
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, semi))
+        });
+        match decoded {
+            Some((c, semi)) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Pause for a `<break>`: an explicit `time` wins over `strength`.
+fn break_duration(time: Option<&str>, strength: Option<&str>) -> Result<Duration, String> {
+    if let Some(time) = time {
+        let caps = RE_TIME
+            .captures(time)
+            .ok_or_else(|| format!("invalid break time '{time}'; use e.g. 500ms or 1.5s"))?;
+        let value: f64 = caps[1]
+            .parse()
+            .map_err(|_| format!("invalid break time '{time}'"))?;
+        let secs = if &caps[2] == "ms" {
+            value / 1000.0
+        } else {
+            value
+        };
+        return Ok(Duration::from_secs_f64(secs).min(MAX_BREAK));
+    }
+    let millis = match strength.unwrap_or("medium") {
+        "none" => 0,
+        "x-weak" => 100,
+        "weak" => 250,
+        "medium" => 500,
+        "strong" => 750,
+        "x-strong" => 1000,
+        other => return Err(format!("invalid break strength '{other}'")),
+    };
+    Ok(Duration::from_millis(millis))
+}
+
+/// Speed multiplier for a `<prosody rate>` value: a keyword, a percentage
+/// (`80%` absolute, `+20%`/`-20%` relative), or a bare multiplier.
+fn parse_rate(rate: &str) -> Result<f32, String> {
+    let rate = rate.trim();
+    let multiplier = match rate {
+        "x-slow" => Some(0.5),
+        "slow" => Some(0.75),
+        "medium" | "default" => Some(1.0),
+        "fast" => Some(1.25),
+        "x-fast" => Some(1.5),
+        _ => match rate.strip_suffix('%') {
+            Some(pct) => {
+                let relative = pct.starts_with(['+', '-']);
+                pct.parse::<f32>()
+                    .ok()
+                    .map(|p| if relative { 1.0 + p / 100.0 } else { p / 100.0 })
+            }
+            None => rate.parse::<f32>().ok(),
+        },
+    };
+    multiplier
+        .filter(|m| m.is_finite() && *m > 0.0)
+        .ok_or_else(|| format!("invalid prosody rate '{rate}'"))
+}
+
+/// Spoken form of `<say-as>` content. Unknown interpretations and content
+/// that doesn't parse are spoken as written.
+fn say_as(interpret_as: &str, format: Option<&str>, text: &str) -> String {
+    let spoken = match interpret_as {
+        "characters" | "spell-out" => Some(spell_characters(text)),
+        "digits" => Some(spell_digits(text)),
+        "cardinal" | "number" => digits_only(text).map(|d| number_to_words(&d)),
+        "ordinal" => digits_only(text)
+            .and_then(|d| d.parse().ok())
+            .map(ordinal_words),
+        "date" => say_date(text, format),
+        "telephone" => Some(say_telephone(text)),
+        _ => None,
+    };
+    spoken
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| text.to_string())
+}
+
+/// `text` with thousands separators removed, if that leaves only digits.
+fn digits_only(text: &str) -> Option<String> {
+    let digits: String = text.chars().filter(|&c| c != ',').collect();
+    (!digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())).then_some(digits)
+}
+
+fn digit_word(c: char) -> Option<String> {
+    c.to_digit(10).map(|d| number_to_words(&d.to_string()))
+}
+
+fn spell_digits(text: &str) -> String {
+    text.chars()
+        .filter_map(digit_word)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Spell letters by name (espeak reads a lone "a" as the article) and digits
+/// as words.
+fn spell_characters(text: &str) -> String {
+    const LETTERS: [&str; 26] = [
+        "ay",
+        "bee",
+        "see",
+        "dee",
+        "ee",
+        "eff",
+        "gee",
+        "aitch",
+        "eye",
+        "jay",
+        "kay",
+        "el",
+        "em",
+        "en",
+        "oh",
+        "pee",
+        "cue",
+        "ar",
+        "ess",
+        "tee",
+        "you",
+        "vee",
+        "double you",
+        "ex",
+        "why",
+        "zee",
+    ];
+    text.chars()
+        .filter_map(|c| {
+            if c.is_ascii_alphabetic() {
+                let i = (c.to_ascii_lowercase() as u8 - b'a') as usize;
+                Some(LETTERS[i].to_string())
+            } else if c.is_alphabetic() {
+                Some(c.to_string())
+            } else {
+                digit_word(c)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn ordinal_words(n: u64) -> String {
+    let cardinal = number_to_words(&n.to_string());
+    let (head, last) = match cardinal.rsplit_once(' ') {
+        Some((head, last)) => (format!("{head} "), last),
+        None => (String::new(), cardinal.as_str()),
+    };
+    let last = match last {
+        "one" => "first".to_string(),
+        "two" => "second".to_string(),
+        "three" => "third".to_string(),
+        "five" => "fifth".to_string(),
+        "eight" => "eighth".to_string(),
+        "nine" => "ninth".to_string(),
+        "twelve" => "twelfth".to_string(),
+        w if w.ends_with('y') => format!("{}ieth", &w[..w.len() - 1]),
+        w => format!("{w}th"),
+    };
+    format!("{head}{last}")
+}
+
+/// Years the way they're read aloud: "nineteen eighty four", "two thousand
+/// five", "twenty twenty four".
+fn year_words(year: u64) -> String {
+    let (hi, lo) = (year / 100, year % 100);
+    if !(1000..10_000).contains(&year) || (2000..2010).contains(&year) || year.is_multiple_of(1000) {
+        return number_to_words(&year.to_string());
+    }
+    let hi = number_to_words(&hi.to_string());
+    match lo {
+        0 => format!("{hi} hundred"),
+        1..=9 => format!("{hi} oh {}", number_to_words(&lo.to_string())),
+        _ => format!("{hi} {}", number_to_words(&lo.to_string())),
+    }
+}
+
+/// Read a numeric date. `format` orders the fields (`ymd`, `mdy`, `dmy`,
+/// `md`, `dm`, `ym`, `my`, `y`); the default is `ymd`, as in ISO 8601.
+fn say_date(text: &str, format: Option<&str>) -> Option<String> {
+    const MONTHS: [&str; 12] = [
+        "January",
+        "February",
+        "March",
+        "April",
+        "May",
+        "June",
+        "July",
+        "August",
+        "September",
+        "October",
+        "November",
+        "December",
+    ];
+    let format = format.unwrap_or("ymd");
+    if format.chars().any(|c| !"ymd".contains(c)) {
+        return None;
+    }
+    let fields: Vec<u64> = text
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|f| !f.is_empty())
+        .map(|f| f.parse().ok())
+        .collect::<Option<_>>()?;
+    if fields.len() != format.len() {
+        return None;
+    }
+    let field = |key: char| format.find(key).map(|i| fields[i]);
+    let (year, month, day) = (field('y'), field('m'), field('d'));
+
+    let month = match month {
+        Some(m @ 1..=12) => Some(MONTHS[m as usize - 1]),
+        Some(_) => return None,
+        None => None,
+    };
+    let day = match day {
+        Some(d @ 1..=31) => Some(ordinal_words(d)),
+        Some(_) => return None,
+        None => None,
+    };
+    let year = year.map(year_words);
+
+    Some(match (month, day, year) {
+        (Some(m), Some(d), Some(y)) => format!("{m} {d}, {y}"),
+        (Some(m), Some(d), None) => format!("{m} {d}"),
+        (Some(m), None, Some(y)) => format!("{m} {y}"),
+        (None, None, Some(y)) => y,
+        _ => return None,
+    })
+}
+
+/// Read a phone number digit by digit, pausing between groups.
+fn say_telephone(text: &str) -> String {
+    let groups: Vec<String> = text
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|g| !g.is_empty())
+        .map(spell_digits)
+        .collect();
+    let spoken = groups.join(", ");
+    if text.trim_start().starts_with('+') {
+        format!("plus {spoken}")
+    } else {
+        spoken
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> SsmlSegment {
+        SsmlSegment::Text {
+            text: s.to_string(),
+            style: SsmlStyle::default(),
+        }
+    }
+
+    #[test]
+    fn plain_text_passes_through() {
+        assert_eq!(
+            parse_ssml("<speak>Hello  world.</speak>").unwrap(),
+            vec![text("Hello world.")]
+        );
+        assert_eq!(parse_ssml("no root").unwrap(), vec![text("no root")]);
+    }
+
+    #[test]
+    fn breaks() {
+        let segments = parse_ssml(
+            r#"<speak>One.<break time="500ms"/>Two.<break time="1.5s"/>Three.<break strength="strong"/></speak>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            segments,
+            vec![
+                text("One."),
+                SsmlSegment::Break(Duration::from_millis(500)),
+                text("Two."),
+                SsmlSegment::Break(Duration::from_millis(1500)),
+                text("Three."),
+                SsmlSegment::Break(Duration::from_millis(750)),
+            ]
+        );
+        assert_eq!(
+            parse_ssml(r#"<break time="1h"/>"#).unwrap_err(),
+            "invalid break time '1h'; use e.g. 500ms or 1.5s"
+        );
+        assert_eq!(
+            parse_ssml(r#"<break time="60s"/>"#).unwrap(),
+            vec![SsmlSegment::Break(MAX_BREAK)]
+        );
+    }
+
+    #[test]
+    fn prosody_and_emphasis_compound() {
+        let segments = parse_ssml(
+            r#"<prosody rate="slow">Slow <emphasis level="strong">and firm</emphasis></prosody>"#,
+        )
+        .unwrap();
+        match &segments[..] {
+            [
+                SsmlSegment::Text { text: a, style: sa },
+                SsmlSegment::Text { text: b, style: sb },
+            ] => {
+                assert_eq!(a, "Slow ");
+                assert!((sa.rate - 0.75).abs() < 1e-6);
+                assert_eq!(b, "and firm");
+                assert!((sb.rate - 0.75 * 0.85).abs() < 1e-6);
+            }
+            other => panic!("unexpected segments: {other:?}"),
+        }
+        assert!((parse_rate("+20%").unwrap() - 1.2).abs() < 1e-6);
+        assert!((parse_rate("80%").unwrap() - 0.8).abs() < 1e-6);
+        assert!(parse_rate("-100%").is_err());
+    }
+
+    #[test]
+    fn voice_and_language() {
+        let segments = parse_ssml(
+            r#"<speak xml:lang="en-GB">Hi. <voice name="am_adam">Hello.</voice></speak>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            segments,
+            vec![
+                SsmlSegment::Text {
+                    text: "Hi. ".into(),
+                    style: SsmlStyle {
+                        language: Some("en-gb".into()),
+                        ..SsmlStyle::default()
+                    },
+                },
+                SsmlSegment::Text {
+                    text: "Hello.".into(),
+                    style: SsmlStyle {
+                        voice: Some("am_adam".into()),
+                        language: Some("en-gb".into()),
+                        rate: 1.0,
+                    },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn phoneme_and_sub() {
+        let segments = parse_ssml(
+            r#"Say <phoneme alphabet="ipa" ph="təˈmɑːtoʊ">tomato</phoneme> via <sub alias="World Wide Web">WWW</sub>."#,
+        )
+        .unwrap();
+        assert_eq!(
+            segments,
+            vec![
+                text("Say "),
+                SsmlSegment::Phoneme {
+                    ipa: "təˈmɑːtoʊ".into(),
+                    text: "tomato".into(),
+                    style: SsmlStyle::default(),
+                },
+                text(" via World Wide Web."),
+            ]
+        );
+        assert!(parse_ssml(r#"<phoneme alphabet="x-sampa" ph="t@">a</phoneme>"#).is_err());
+    }
+
+    #[test]
+    fn say_as_interpretations() {
+        let spoken = |ssml: &str| match &parse_ssml(ssml).unwrap()[..] {
+            [SsmlSegment::Text { text, .. }] => text.trim().to_string(),
+            other => panic!("unexpected segments: {other:?}"),
+        };
+        assert_eq!(
+            spoken(r#"<say-as interpret-as="characters">AB1</say-as>"#),
+            "ay bee one"
+        );
+        assert_eq!(
+            spoken(r#"<say-as interpret-as="digits">402</say-as>"#),
+            "four zero two"
+        );
+        assert_eq!(
+            spoken(r#"<say-as interpret-as="ordinal">23</say-as>"#),
+            "twenty third"
+        );
+        assert_eq!(
+            spoken(r#"<say-as interpret-as="date">2024-03-05</say-as>"#),
+            "March fifth, twenty twenty four"
+        );
+        assert_eq!(
+            spoken(r#"<say-as interpret-as="date" format="mdy">12/31/1999</say-as>"#),
+            "December thirty first, nineteen ninety nine"
+        );
+        assert_eq!(
+            spoken(r#"<say-as interpret-as="telephone">555-0142</say-as>"#),
+            "five five five, zero one four two"
+        );
+        // Unparseable content is read as written.
+        assert_eq!(
+            spoken(r#"<say-as interpret-as="date">soon</say-as>"#),
+            "soon"
+        );
+    }
+
+    #[test]
+    fn entities_and_comments() {
+        assert_eq!(
+            parse_ssml("<speak>Fish &amp; chips<!-- note --> &#233;</speak>").unwrap(),
+            vec![text("Fish & chips é")]
+        );
+    }
+
+    #[test]
+    fn malformed_markup_is_rejected() {
+        assert_eq!(parse_ssml("<speak>Hi").unwrap_err(), "unclosed tag <speak>");
+        assert_eq!(
+            parse_ssml("<speak><prosody>Hi</speak>").unwrap_err(),
+            "mismatched closing tag </speak>; expected </prosody>"
+        );
+        assert!(parse_ssml("<speak>Hi</speak></speak>").is_err());
+        assert!(parse_ssml("<speak attr=\"x>Hi").is_err());
+        assert!(parse_ssml("<sub>WWW</sub>").is_err());
+    }
+}
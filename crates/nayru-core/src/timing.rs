@@ -0,0 +1,168 @@
+//! Word-level timing from per-token durations.
+//!
+//! Kokoro predicts how long each phoneme token lasts. Given those durations
+//! and which tokens belong to which phonemized word, [`align_words`] maps the
+//! words of the source text onto the audio timeline. When the model doesn't
+//! expose durations, callers pass a uniform duration per token and get an
+//! estimate proportional to token counts.
+
+use std::ops::Range;
+
+use serde::Serialize;
+
+/// A source word and when it is spoken, in seconds from the clip start.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WordTiming {
+    pub word: String,
+    pub start: f32,
+    pub end: f32,
+}
+
+/// Align the whitespace-separated words of `text` to the audio timeline.
+///
+/// - `phoneme_words`: token range of each phonemized word in `token_durations`.
+/// - `token_durations`: duration of every model token, in any unit.
+/// - `duration_secs`: length of the synthesized audio; durations are scaled
+///   so they sum to it.
+///
+/// If the phonemizer produced one word per source word, each source word
+/// takes exactly its word's tokens. Otherwise (espeak merges or splits words)
+/// source words are spread across the spoken tokens in proportion to their
+/// letter counts.
+pub fn align_words(
+    text: &str,
+    phoneme_words: &[Range<usize>],
+    token_durations: &[f32],
+    duration_secs: f32,
+) -> Vec<WordTiming> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.is_empty() || token_durations.is_empty() {
+        return Vec::new();
+    }
+
+    let total: f32 = token_durations.iter().sum();
+    let scale = if total > 0.0 {
+        duration_secs / total
+    } else {
+        0.0
+    };
+    let mut cumulative = Vec::with_capacity(token_durations.len() + 1);
+    let mut t = 0.0f32;
+    cumulative.push(t);
+    for d in token_durations {
+        t += d * scale;
+        cumulative.push(t);
+    }
+    let n_tokens = token_durations.len();
+
+    if phoneme_words.len() == words.len()
+        && phoneme_words
+            .iter()
+            .all(|r| r.start <= r.end && r.end <= n_tokens)
+    {
+        return words
+            .iter()
+            .zip(phoneme_words)
+            .map(|(word, range)| WordTiming {
+                word: word.to_string(),
+                start: cumulative[range.start],
+                end: cumulative[range.end],
+            })
+            .collect();
+    }
+
+    // Spoken span: first to last phonemized word, or everything.
+    let span = match (phoneme_words.first(), phoneme_words.last()) {
+        (Some(first), Some(last)) if first.start < last.end && last.end <= n_tokens => {
+            first.start as f32..last.end as f32
+        }
+        _ => 0.0..n_tokens as f32,
+    };
+
+    let weights: Vec<f32> = words
+        .iter()
+        .map(|w| w.chars().filter(|c| c.is_alphanumeric()).count().max(1) as f32)
+        .collect();
+    let weight_total: f32 = weights.iter().sum();
+
+    let mut out = Vec::with_capacity(words.len());
+    let mut acc = 0.0f32;
+    for (word, weight) in words.iter().zip(&weights) {
+        let from = span.start + (span.end - span.start) * acc / weight_total;
+        acc += weight;
+        let to = span.start + (span.end - span.start) * acc / weight_total;
+        out.push(WordTiming {
+            word: word.to_string(),
+            start: time_at(&cumulative, from),
+            end: time_at(&cumulative, to),
+        });
+    }
+    out
+}
+
+/// Time at fractional token position `pos`, interpolating within a token.
+fn time_at(cumulative: &[f32], pos: f32) -> f32 {
+    let last = cumulative.len() - 1;
+    let pos = pos.clamp(0.0, last as f32);
+    let i = (pos.floor() as usize).min(last);
+    if i == last {
+        return cumulative[last];
+    }
+    let frac = pos - i as f32;
+    cumulative[i] + (cumulative[i + 1] - cumulative[i]) * frac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_to_one_uses_token_ranges() {
+        // pad, h e l o, space, w o r l d, pad
+        let durations = [1.0, 2.0, 2.0, 2.0, 2.0, 1.0, 2.0, 2.0, 2.0, 2.0, 2.0, 1.0];
+        let words = [1..5, 6..11];
+        let timings = align_words("Hello world.", &words, &durations, 2.1);
+        assert_eq!(timings.len(), 2);
+        assert_eq!(timings[0].word, "Hello");
+        assert!((timings[0].start - 0.1).abs() < 1e-4);
+        assert!((timings[0].end - 0.9).abs() < 1e-4);
+        assert_eq!(timings[1].word, "world.");
+        assert!((timings[1].start - 1.0).abs() < 1e-4);
+        assert!((timings[1].end - 2.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn mismatched_word_counts_spread_by_letters() {
+        // Phonemizer merged "of the" into one word.
+        let durations = [1.0; 10];
+        let words = [Range { start: 0, end: 10 }];
+        let timings = align_words("of the", &words, &durations, 1.0);
+        assert_eq!(timings.len(), 2);
+        assert!((timings[0].start - 0.0).abs() < 1e-4);
+        assert!((timings[0].end - 0.4).abs() < 1e-4);
+        assert!((timings[1].start - 0.4).abs() < 1e-4);
+        assert!((timings[1].end - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn words_are_monotonic() {
+        let durations = [3.0, 1.0, 4.0, 1.0, 5.0, 9.0, 2.0, 6.0];
+        let timings = align_words(
+            "a quick brown fox jumps",
+            &[Range { start: 0, end: 8 }],
+            &durations,
+            3.0,
+        );
+        for pair in timings.windows(2) {
+            assert!(pair[0].start <= pair[0].end);
+            assert!((pair[0].end - pair[1].start).abs() < 1e-4);
+        }
+        assert!((timings.last().unwrap().end - 3.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn empty_inputs() {
+        assert!(align_words("", &[], &[1.0], 1.0).is_empty());
+        assert!(align_words("hello", &[], &[], 1.0).is_empty());
+    }
+}
@@ -4,17 +4,49 @@
 //! like raia-core. Keeping them in nayru-core means consumers can depend on
 //! types without pulling in tokio, rodio, or other heavy deps.
 
-use crate::text_prep::DEFAULT_MAX_CHUNK_LEN;
+use crate::lang::{base_language, detect_language, language_for_voice, DEFAULT_LANGUAGE};
+use crate::text_prep::{SentenceEnd, DEFAULT_MAX_CHUNK_LEN, MAX_BREAK};
+use crate::timing::WordTiming;
 use serde::{Deserialize, Serialize};
 
 // ─── TTS types ─────────────────────────────────────────────────────────────
 
+/// Default [`TtsConfig::loudness_target`], in LUFS.
+pub const DEFAULT_LOUDNESS_TARGET: f32 = -18.0;
+
+/// Default [`TtsConfig::trim_threshold`], in dBFS.
+pub const DEFAULT_TRIM_THRESHOLD: f32 = -50.0;
+
 /// TTS engine configuration.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct TtsConfig {
     pub voice: String,
     pub speed: f32,
     pub max_chunk_len: usize,
+    /// espeak-ng language code. `None` = the voice's language (see
+    /// [`language_for_voice`]), falling back to American English.
+    pub language: Option<String>,
+    /// Guess each sentence's language when a request doesn't name one, for
+    /// documents that mix languages.
+    pub detect_language: bool,
+    /// Where speech plays: the local audio device, remote listeners, or both.
+    pub output: AudioOutput,
+    /// Name of the audio device to play on. `None` = the system default.
+    pub output_device: Option<String>,
+    /// Master volume at startup, 0.0–1.0.
+    pub volume: f32,
+    /// Playback rate at startup, applied by time-stretching on top of
+    /// [`speed`](Self::speed). 1.0 plays clips as synthesized.
+    pub rate: f32,
+    /// Integrated loudness (LUFS) every clip is normalized to, so voices and
+    /// sentences play at an even level. `None` plays clips as synthesized.
+    pub loudness_target: Option<f32>,
+    /// Level (dBFS) below which a clip's leading and trailing audio counts as
+    /// silence and is trimmed, so [`pauses`](Self::pauses) alone set the gaps.
+    /// `None` keeps clips whole.
+    pub trim_threshold: Option<f32>,
+    /// Silence after each sentence, by what ends it.
+    pub pauses: Pauses,
 }
 
 impl Default for TtsConfig {
@@ -23,10 +55,251 @@ impl Default for TtsConfig {
             voice: "af_heart".into(),
             speed: 1.0,
             max_chunk_len: DEFAULT_MAX_CHUNK_LEN,
+            language: None,
+            detect_language: false,
+            output: AudioOutput::default(),
+            output_device: None,
+            volume: 1.0,
+            rate: 1.0,
+            loudness_target: Some(DEFAULT_LOUDNESS_TARGET),
+            trim_threshold: Some(DEFAULT_TRIM_THRESHOLD),
+            pauses: Pauses::default(),
+        }
+    }
+}
+
+/// Where the engine plays speech.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioOutput {
+    /// An audio device on this machine.
+    #[default]
+    Local,
+    /// Only listeners on the remote audio stream; no audio device is opened.
+    Remote,
+    /// The audio device, mirrored to remote listeners.
+    Both,
+    /// Nowhere: clips are consumed silently in real time, so the engine runs
+    /// (events, status, recording) on machines without audio.
+    Null,
+}
+
+impl AudioOutput {
+    pub fn is_local(self) -> bool {
+        matches!(self, Self::Local | Self::Both)
+    }
+
+    pub fn is_remote(self) -> bool {
+        matches!(self, Self::Remote | Self::Both)
+    }
+}
+
+impl TtsConfig {
+    /// The espeak-ng language to speak `text` in under `options`.
+    ///
+    /// In order: the request's language; the detected language, if
+    /// [`detect_language`](Self::detect_language) is on; the request voice's
+    /// language; the configured language; the configured voice's language;
+    /// American English. A detected language that matches the fallback's
+    /// (e.g. `en-us` detected, `en-gb` configured) keeps the fallback's region.
+    pub fn language_for(&self, options: &SpeakOptions, text: &str) -> String {
+        if let Some(language) = &options.language {
+            return language.clone();
+        }
+        let fallback = options
+            .voice
+            .as_deref()
+            .and_then(language_for_voice)
+            .or(self.language.as_deref())
+            .or_else(|| language_for_voice(&self.voice))
+            .unwrap_or(DEFAULT_LANGUAGE);
+        let detected = self
+            .detect_language
+            .then(|| detect_language(text))
+            .flatten()
+            .filter(|d| base_language(d) != base_language(fallback));
+        detected.unwrap_or(fallback).to_string()
+    }
+
+    /// The pauses to use under `options`.
+    pub fn pauses_for(&self, options: &SpeakOptions) -> Pauses {
+        options.pauses.unwrap_or(self.pauses)
+    }
+}
+
+/// Silence inserted after a sentence, by what ends it, in milliseconds.
+/// Fields missing from JSON take their defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Pauses {
+    /// Between sentences of a paragraph.
+    pub sentence_ms: u64,
+    /// After a list item, or the line leading into a list.
+    pub list_item_ms: u64,
+    /// At a paragraph break (a blank line).
+    pub paragraph_ms: u64,
+}
+
+impl Default for Pauses {
+    fn default() -> Self {
+        Self {
+            sentence_ms: 250,
+            list_item_ms: 400,
+            paragraph_ms: 700,
+        }
+    }
+}
+
+/// Longest pause, in milliseconds; the same cap as an SSML `<break>`.
+pub const MAX_PAUSE_MS: u64 = MAX_BREAK.as_millis() as u64;
+
+impl Pauses {
+    /// Fails if any pause is longer than [`MAX_PAUSE_MS`].
+    pub fn validate(&self) -> Result<(), String> {
+        let longest = self.sentence_ms.max(self.list_item_ms).max(self.paragraph_ms);
+        if longest > MAX_PAUSE_MS {
+            return Err(format!("invalid pause {longest} ms; must be at most {MAX_PAUSE_MS}"));
+        }
+        Ok(())
+    }
+
+    pub fn after(&self, end: SentenceEnd) -> std::time::Duration {
+        std::time::Duration::from_millis(match end {
+            SentenceEnd::End => 0,
+            SentenceEnd::Sentence => self.sentence_ms,
+            SentenceEnd::ListItem => self.list_item_ms,
+            SentenceEnd::Paragraph => self.paragraph_ms,
+        })
+    }
+}
+
+/// Per-utterance overrides for [`TtsConfig`].
+///
+/// `None` fields fall back to the engine's configured defaults, so two
+/// callers sharing one engine can speak in different voices.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpeakOptions {
+    #[serde(default)]
+    pub voice: Option<String>,
+    #[serde(default)]
+    pub speed: Option<f32>,
+    /// espeak-ng language code (e.g. `en-gb`). `None` = derived from the
+    /// voice (see [`TtsConfig::language_for`]).
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Loudness multiplier for this utterance (1.0 = as synthesized), on top
+    /// of the master volume.
+    #[serde(default)]
+    pub gain: Option<f32>,
+    /// Pauses between sentences for this utterance.
+    #[serde(default)]
+    pub pauses: Option<Pauses>,
+    /// Speak at once, over whatever is playing, which is ducked until this
+    /// utterance ends, instead of queueing after it.
+    #[serde(default)]
+    pub priority: bool,
+}
+
+/// Where a voice comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VoiceKind {
+    /// Shipped in the backend's voices file.
+    Builtin,
+    /// A weighted mix like `af_heart:0.6+bf_emma:0.4`.
+    Blend,
+    /// Saved by the user.
+    Custom,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VoiceGender {
+    Female,
+    Male,
+}
+
+/// An audio output device, as listed by `/devices`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioDevice {
+    pub name: String,
+    /// The system default output.
+    pub default: bool,
+    /// The device speech is playing on now.
+    pub active: bool,
+}
+
+/// A voice as listed by `/voices`, with metadata parsed from the Kokoro
+/// naming convention (`af_heart` → American English, female, "Heart").
+/// Names outside the convention have no language or gender.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VoiceInfo {
+    pub name: String,
+    pub kind: VoiceKind,
+    pub language: Option<String>,
+    pub gender: Option<VoiceGender>,
+    pub display_name: String,
+    /// Short sample as a `data:audio/wav;base64,...` URI, when requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview: Option<String>,
+}
+
+impl VoiceInfo {
+    pub fn new(name: &str, kind: VoiceKind) -> Self {
+        // A blend takes its language from the first voice, and a gender
+        // only if every voice shares it.
+        let components: Vec<&str> = name
+            .split('+')
+            .map(|part| part.split(':').next().unwrap_or_default().trim())
+            .collect();
+        let genders: Vec<Option<VoiceGender>> =
+            components.iter().map(|c| voice_gender(c)).collect();
+        let gender = genders[0].filter(|g| genders.iter().all(|other| *other == Some(*g)));
+        let display_name = components
+            .iter()
+            .map(|c| voice_display_name(c))
+            .collect::<Vec<_>>()
+            .join(" + ");
+
+        Self {
+            name: name.to_string(),
+            kind,
+            language: language_for_voice(components[0]).map(Into::into),
+            gender,
+            display_name,
+            preview: None,
         }
     }
 }
 
+/// `af_heart` → female, from the Kokoro `<language><gender>_<name>` scheme.
+fn voice_gender(voice: &str) -> Option<VoiceGender> {
+    language_for_voice(voice)?;
+    match voice.chars().nth(1) {
+        Some('f') => Some(VoiceGender::Female),
+        Some('m') => Some(VoiceGender::Male),
+        _ => None,
+    }
+}
+
+/// `af_heart` → `Heart`, `zm_yunxi` → `Yunxi`; other names as they are.
+fn voice_display_name(voice: &str) -> String {
+    match voice.split_once('_') {
+        Some((_, rest)) if language_for_voice(voice).is_some() => rest
+            .split('_')
+            .map(|word| {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<String>>()
+            .join(" "),
+        _ => voice.to_string(),
+    }
+}
+
 /// Observable TTS state.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 #[serde(rename_all = "lowercase")]
@@ -36,12 +309,91 @@ pub enum TtsState {
     Playing,
 }
 
+/// Identifies one sentence of one queued utterance.
+///
+/// Every `speak` call (or LLM stream) gets a fresh `utterance_id`;
+/// `sentence_index` counts that utterance's sentences from zero. A long
+/// sentence split into several chunks keeps one index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlaybackPosition {
+    pub utterance_id: u64,
+    pub sentence_index: usize,
+}
+
+/// Returned by `speak`: the new utterance's ID and how many chunks it queued.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct QueuedUtterance {
+    pub utterance_id: u64,
+    pub chunks: usize,
+}
+
 /// TTS status snapshot.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct TtsStatus {
     pub state: TtsState,
     pub queue_length: usize,
     pub voice: String,
+    /// The sentence currently coming out of the speakers, if any.
+    pub current: Option<PlaybackPosition>,
+    /// Synthesis cache counters, when the engine has a cache.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CacheStats>,
+    /// The audio device playing speech, if one is open.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    /// Master volume, 0.0–1.0.
+    pub volume: f32,
+    /// Playback is ducked (lowered for another speaker).
+    pub ducked: bool,
+    /// Playback rate the clips are time-stretched to (1.0 = as
+    /// synthesized). Word timings in events are at 1.0; divide by this.
+    pub rate: f32,
+}
+
+/// Synthesis cache counters. Hits and misses count since startup.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Clips on disk.
+    pub entries: usize,
+    /// Disk space used by clips.
+    pub bytes: u64,
+}
+
+/// Live engine event, pushed to `/events` subscribers as JSON.
+///
+/// Serialized with a `type` tag, e.g.
+/// `{"type": "utterance_started", "utterance_id": 3, "sentence_index": 0, "text": "Hi."}`.
+/// Start/finish fire per synthesized clip — a sentence, or part of a long one.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TtsEvent {
+    /// State, queue length, current sentence, or cache counters changed.
+    Status(TtsStatus),
+    /// A clip began playing. `words` gives each word's start/end within it.
+    UtteranceStarted {
+        #[serde(flatten)]
+        position: PlaybackPosition,
+        text: String,
+        words: Vec<WordTiming>,
+    },
+    /// A clip stopped playing. `interrupted` is true for skip/stop.
+    UtteranceFinished {
+        #[serde(flatten)]
+        position: PlaybackPosition,
+        text: String,
+        interrupted: bool,
+    },
+    /// Kokoro failed to synthesize a clip; it was dropped from the queue.
+    SynthesisError {
+        #[serde(flatten)]
+        position: PlaybackPosition,
+        text: String,
+        error: String,
+    },
+    Paused,
+    Resumed,
 }
 
 // ─── STT types ─────────────────────────────────────────────────────────────
@@ -135,3 +487,69 @@ pub struct VoiceServicesStatus {
     pub whisper: ServiceStatus,
     pub kokoro: ServiceStatus,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(voice: Option<&str>, language: Option<&str>) -> SpeakOptions {
+        SpeakOptions {
+            voice: voice.map(Into::into),
+            language: language.map(Into::into),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn language_resolution_order() {
+        let config = TtsConfig {
+            voice: "bf_emma".into(),
+            ..Default::default()
+        };
+        let text = "El gato está en la casa con los niños.";
+        assert_eq!(config.language_for(&options(None, None), text), "en-gb");
+        assert_eq!(config.language_for(&options(Some("ff_siwis"), None), text), "fr-fr");
+        assert_eq!(config.language_for(&options(Some("ff_siwis"), Some("it")), text), "it");
+
+        let config = TtsConfig {
+            language: Some("pt-br".into()),
+            detect_language: true,
+            ..config
+        };
+        assert_eq!(config.language_for(&options(None, None), "Okay."), "pt-br");
+        assert_eq!(config.language_for(&options(None, None), text), "es");
+        assert_eq!(config.language_for(&options(Some("custom"), None), "Okay."), "pt-br");
+    }
+
+    #[test]
+    fn voice_info_from_kokoro_names() {
+        let heart = VoiceInfo::new("af_heart", VoiceKind::Builtin);
+        assert_eq!(heart.language.as_deref(), Some("en-us"));
+        assert_eq!(heart.gender, Some(VoiceGender::Female));
+        assert_eq!(heart.display_name, "Heart");
+
+        let custom = VoiceInfo::new("narrator", VoiceKind::Custom);
+        assert_eq!((custom.language, custom.gender), (None, None));
+        assert_eq!(custom.display_name, "narrator");
+
+        let blend = VoiceInfo::new("bf_emma:0.6+am_adam:0.4", VoiceKind::Blend);
+        assert_eq!(blend.language.as_deref(), Some("en-gb"));
+        assert_eq!(blend.gender, None);
+        assert_eq!(blend.display_name, "Emma + Adam");
+        assert_eq!(
+            VoiceInfo::new("af_heart+bf_emma", VoiceKind::Blend).gender,
+            Some(VoiceGender::Female)
+        );
+    }
+
+    #[test]
+    fn detected_language_keeps_configured_region() {
+        let config = TtsConfig {
+            voice: "bf_emma".into(),
+            detect_language: true,
+            ..Default::default()
+        };
+        let text = "The cat is on the mat with the dog.";
+        assert_eq!(config.language_for(&SpeakOptions::default(), text), "en-gb");
+    }
+}
@@ -0,0 +1,57 @@
+//! Phonemizer latency: spawn per call vs. persistent worker vs. cached.
+//!
+//! Needs espeak-ng on PATH. Run with `cargo bench -p nayru-lib --bench phonemizer`.
+
+use std::time::{Duration, Instant};
+
+use nayru_lib::phonemizer::{
+    check_espeak, CachedPhonemizer, EspeakProcess, EspeakWorker, Phonemizer,
+};
+
+const SENTENCES: &[&str] = &[
+    "The quick brown fox jumps over the lazy dog.",
+    "It was the best of times, it was the worst of times.",
+    "Call me Ishmael.",
+    "In the beginning the Universe was created.",
+    "This has made a lot of people very angry and been widely regarded as a bad move.",
+    "All happy families are alike; each unhappy family is unhappy in its own way.",
+    "Far out in the uncharted backwaters of the unfashionable end of the western spiral arm of the Galaxy lies a small unregarded yellow sun.",
+    "It is a truth universally acknowledged that a single man in possession of a good fortune must be in want of a wife.",
+];
+
+const ROUNDS: usize = 5;
+
+async fn bench(name: &str, phonemizer: &dyn Phonemizer) {
+    let mut times = Vec::with_capacity(ROUNDS * SENTENCES.len());
+    for _ in 0..ROUNDS {
+        for sentence in SENTENCES {
+            let started = Instant::now();
+            if let Err(e) = phonemizer.phonemize(sentence, "en-us").await {
+                eprintln!("{name}: {e}");
+                return;
+            }
+            times.push(started.elapsed());
+        }
+    }
+    times.sort();
+    let mean = times.iter().sum::<Duration>() / times.len() as u32;
+    println!(
+        "{name:<10} mean {:>8.3} ms   p50 {:>8.3} ms   max {:>8.3} ms   ({} calls)",
+        mean.as_secs_f64() * 1e3,
+        times[times.len() / 2].as_secs_f64() * 1e3,
+        times[times.len() - 1].as_secs_f64() * 1e3,
+        times.len(),
+    );
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = check_espeak().await {
+        eprintln!("skipping phonemizer bench: {e}");
+        return;
+    }
+
+    bench("spawn", &EspeakProcess).await;
+    bench("worker", &EspeakWorker::new()).await;
+    bench("cached", &CachedPhonemizer::new(EspeakWorker::new(), 1024)).await;
+}
@@ -0,0 +1,77 @@
+//! Audio output devices: listing and opening by name.
+//!
+//! rodio doesn't report a device going away (its stream error callback only
+//! logs), so [`DeviceSink`](crate::audio_sink::DeviceSink) polls
+//! [`output_device_names`] and reopens when the device it's on is gone.
+
+use cpal::traits::{DeviceTrait, HostTrait};
+use rodio::{OutputStream, OutputStreamHandle, Sink};
+
+use nayru_core::types::AudioDevice;
+
+/// Output devices on the default host. `active` is left false.
+pub fn output_devices() -> Result<Vec<AudioDevice>, String> {
+    let host = cpal::default_host();
+    let default = host.default_output_device().and_then(|d| d.name().ok());
+    let devices = host
+        .output_devices()
+        .map_err(|e| format!("can't list audio devices: {e}"))?;
+    Ok(devices
+        .filter_map(|d| d.name().ok())
+        .map(|name| AudioDevice {
+            default: default.as_ref() == Some(&name),
+            name,
+            active: false,
+        })
+        .collect())
+}
+
+/// Names of the output devices present right now (empty if listing fails).
+pub(crate) fn output_device_names() -> Vec<String> {
+    output_devices()
+        .map(|devices| devices.into_iter().map(|d| d.name).collect())
+        .unwrap_or_default()
+}
+
+/// An open output device with a sink on it. Dropping it closes the device.
+pub(crate) struct DeviceOutput {
+    pub name: String,
+    pub sink: Sink,
+    handle: OutputStreamHandle,
+    _stream: OutputStream,
+}
+
+impl DeviceOutput {
+    /// Open `preferred` if it's present, else the system default.
+    pub fn open(preferred: Option<&str>) -> Result<Self, String> {
+        let host = cpal::default_host();
+        let wanted = preferred.and_then(|name| {
+            host.output_devices()
+                .ok()?
+                .find(|d| d.name().is_ok_and(|n| n == name))
+        });
+        let device = match wanted {
+            Some(device) => device,
+            None => host
+                .default_output_device()
+                .ok_or("no audio output device")?,
+        };
+        let name = device.name().unwrap_or_else(|_| "unknown".into());
+        let (stream, handle) = OutputStream::try_from_device(&device)
+            .map_err(|e| format!("failed to open audio output {name}: {e}"))?;
+        let sink = Sink::try_new(&handle).map_err(|e| format!("failed to create sink: {e}"))?;
+        Ok(Self {
+            name,
+            sink,
+            handle,
+            _stream: stream,
+        })
+    }
+
+    /// Swap in an empty, playing sink.
+    pub fn reset_sink(&mut self) -> Result<(), String> {
+        self.sink.stop();
+        self.sink = Sink::try_new(&self.handle).map_err(|e| format!("failed to create sink: {e}"))?;
+        Ok(())
+    }
+}
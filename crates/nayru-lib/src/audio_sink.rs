@@ -0,0 +1,912 @@
+//! Where the playback thread sends clips.
+//!
+//! The playback thread owns the queue order, skip, and stop; an [`AudioSink`]
+//! only has to consume the [`Clip`]s it's handed, in order. Pulling samples
+//! out of a clip is what drives its progress hooks (start/finish events,
+//! status) and its remote tap, so every sink must consume clips at some
+//! pace even when nothing is audible.
+//!
+//! - [`DeviceSink`]: rodio on an output device, moving to another device on
+//!   request or when its device disappears. With no device at all it plays
+//!   silently in real time until one appears.
+//! - [`NullSink`]: no audio; consumes clips in real time or as fast as
+//!   they're synthesized. For headless servers, remote-only output, and CI.
+//! - [`Tee`]: any sink plus a WAV recording of what it played
+//!   ([`file_sink`] is a tee over a null sink).
+//!
+//! Master volume and ducking ([`OutputLevel`]) are applied as clips are
+//! pulled, ramping gradually so changes don't click, and so is the playback
+//! rate ([`PlaybackRate`], time-stretched by [`stretch`](crate::stretch)).
+//! Volume and ducking shape what the sink plays and records, not the remote
+//! audio stream; the stream is tapped after time-stretching, so listeners
+//! get audio at the rate it plays.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rodio::Source;
+use tracing::{debug, error, info, warn};
+
+use nayru_core::types::AudioOutput;
+use nayru_core::wav::write_wav;
+
+use crate::audio_device::{output_device_names, DeviceOutput};
+use crate::audio_stream::resample;
+use crate::streaming_source::StreamingSource;
+use crate::stretch::Stretcher;
+
+/// How far ahead of the wall clock remote-only playback runs, so listeners
+/// can absorb network jitter.
+const REMOTE_LEAD: Duration = Duration::from_millis(200);
+
+/// How often [`DeviceSink`] checks that its audio device is still there.
+const DEVICE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Returned by [`AudioSink::tick`] when there's nothing to do until the next
+/// command. The playback thread caps its wait anyway.
+const IDLE: Duration = Duration::from_secs(1);
+
+/// Sample rate of recordings (Kokoro's native rate).
+const RECORDING_SAMPLE_RATE: u32 = crate::kokoro::SAMPLE_RATE;
+
+/// Time a level change takes to ramp across the full 0–1 range.
+pub(crate) const LEVEL_RAMP: Duration = Duration::from_millis(150);
+
+/// `pull` steps, and recording and tap batches, per second of audio.
+const BATCHES_PER_SECOND: usize = 50;
+
+/// Consumes clips for the playback thread. Built on the playback thread, so
+/// implementations needn't be `Send` (rodio's output stream isn't).
+pub trait AudioSink {
+    /// Play `clip` after the clips already appended.
+    fn append(&mut self, clip: Clip);
+    fn pause(&mut self);
+    fn play(&mut self);
+    /// Forget every appended clip. Cancelling them is the caller's job.
+    fn clear(&mut self);
+    /// Do periodic work (pull audio, watch devices). Returns how soon the
+    /// sink wants to be ticked again.
+    fn tick(&mut self) -> Duration;
+    /// Move to another output device (`None` = the system default). Sinks
+    /// without a device ignore this.
+    fn set_device(&mut self, _name: Option<String>) {}
+    /// The output device in use, if any.
+    fn device(&self) -> Option<String> {
+        None
+    }
+}
+
+/// The sink for an [`AudioOutput`]: the device for `Local` and `Both`, else
+/// a real-time null sink (running [`REMOTE_LEAD`] ahead for `Remote`).
+pub fn for_output(output: AudioOutput, device: Option<String>) -> Box<dyn AudioSink> {
+    match output {
+        AudioOutput::Local | AudioOutput::Both => Box::new(DeviceSink::new(device)),
+        AudioOutput::Remote => Box::new(NullSink::realtime(REMOTE_LEAD)),
+        AudioOutput::Null => Box::new(NullSink::realtime(Duration::ZERO)),
+    }
+}
+
+/// Record to a WAV file without playing anything.
+pub fn file_sink(recorder: WavRecorder, realtime: bool) -> Tee {
+    let null = if realtime {
+        NullSink::realtime(Duration::ZERO)
+    } else {
+        NullSink::unthrottled()
+    };
+    Tee::new(Box::new(null), recorder)
+}
+
+// ─── Level ─────────────────────────────────────────────────────────────────
+
+/// Master volume and duck level, shared by the playback thread (which sets
+/// them) and the clips (which ramp towards their product). Cheap to clone.
+#[derive(Clone)]
+pub struct OutputLevel(Arc<LevelState>);
+
+struct LevelState {
+    volume: AtomicU32,
+    duck: AtomicU32,
+    /// The level actually applied to the last sample, mid-ramp.
+    current: AtomicU32,
+}
+
+impl OutputLevel {
+    pub fn new(volume: f32) -> Self {
+        Self(Arc::new(LevelState {
+            volume: AtomicU32::new(volume.to_bits()),
+            duck: AtomicU32::new(1f32.to_bits()),
+            current: AtomicU32::new(volume.to_bits()),
+        }))
+    }
+
+    pub fn volume(&self) -> f32 {
+        f32::from_bits(self.0.volume.load(Ordering::Relaxed))
+    }
+
+    pub fn set_volume(&self, volume: f32) {
+        self.0.volume.store(volume.to_bits(), Ordering::Relaxed);
+    }
+
+    /// 1.0 when not ducked.
+    pub fn duck(&self) -> f32 {
+        f32::from_bits(self.0.duck.load(Ordering::Relaxed))
+    }
+
+    pub fn set_duck(&self, level: f32) {
+        self.0.duck.store(level.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Scale one sample, moving the applied level one step towards the
+    /// target for audio at `rate` samples per second.
+    fn apply(&self, sample: i16, rate: u32) -> i16 {
+        let target = self.volume() * self.duck();
+        let mut level = f32::from_bits(self.0.current.load(Ordering::Relaxed));
+        if level != target {
+            let step = 1.0 / (rate as f32 * LEVEL_RAMP.as_secs_f32());
+            level = if level < target {
+                (level + step).min(target)
+            } else {
+                (level - step).max(target)
+            };
+            self.0.current.store(level.to_bits(), Ordering::Relaxed);
+        }
+        if level == 1.0 {
+            sample
+        } else {
+            (sample as f32 * level).round() as i16
+        }
+    }
+}
+
+impl Default for OutputLevel {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+// ─── Rate ──────────────────────────────────────────────────────────────────
+
+/// Playback rate (1.0 = as synthesized), shared by the playback thread and
+/// the clips. Cheap to clone.
+#[derive(Clone)]
+pub struct PlaybackRate(Arc<AtomicU32>);
+
+impl PlaybackRate {
+    pub fn new(rate: f32) -> Self {
+        Self(Arc::new(AtomicU32::new(rate.to_bits())))
+    }
+
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    pub fn set(&self, rate: f32) {
+        self.0.store(rate.to_bits(), Ordering::Relaxed);
+    }
+}
+
+impl Default for PlaybackRate {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+// ─── Clips ─────────────────────────────────────────────────────────────────
+
+/// A queued clip, shared between the playback thread and its sink. Clones
+/// share one read position, so a clip can move to another sink mid-play.
+#[derive(Clone)]
+pub struct Clip(Arc<Mutex<ClipState>>);
+
+struct ClipState {
+    source: Option<StreamingSource>,
+    level: OutputLevel,
+    rate: PlaybackRate,
+    /// Set up the first time the clip plays at a rate other than 1.0, and
+    /// kept from then on so later rate changes are seamless.
+    stretcher: Option<Stretcher>,
+    recorder: Option<WavRecorder>,
+    /// Played samples not yet written to the recorder.
+    recorded: Vec<i16>,
+    tap: Option<SampleHook>,
+    /// Played samples, before volume, not yet handed to the tap.
+    tapped: Vec<i16>,
+    /// Priority speech mixed over this clip.
+    overlay: Option<Overlay>,
+    sample_rate: u32,
+    /// A sink has pulled at least one sample.
+    started: bool,
+    /// Samples taken from the source so far.
+    pulled: usize,
+}
+
+type SampleHook = Box<dyn FnMut(&[i16]) + Send>;
+
+impl ClipState {
+    /// The next sample, time-stretched, before and after the level. Ends
+    /// the clip when the source runs out.
+    fn step(&mut self) -> Option<(i16, i16)> {
+        let source = self.source.as_mut()?;
+        let pulled = &mut self.pulled;
+        let mut source = source.inspect(|_| *pulled += 1);
+        let rate = self.rate.get();
+        let sample = match &mut self.stretcher {
+            None if rate == 1.0 => source.next(),
+            stretcher => stretcher
+                .get_or_insert_with(|| Stretcher::new(self.sample_rate))
+                .next(rate, &mut source),
+        };
+        let Some(sample) = sample else {
+            self.source = None;
+            self.flush();
+            return None;
+        };
+        self.started = true;
+        Some((sample, self.level.apply(sample, self.sample_rate)))
+    }
+
+    fn batch_len(&self) -> usize {
+        self.sample_rate as usize / BATCHES_PER_SECOND
+    }
+
+    /// Hand buffered samples to the recorder and the tap.
+    fn flush(&mut self) {
+        if let Some(recorder) = &self.recorder
+            && !self.recorded.is_empty()
+        {
+            recorder.write(self.sample_rate, &self.recorded);
+            self.recorded.clear();
+        }
+        if let Some(tap) = &mut self.tap
+            && !self.tapped.is_empty()
+        {
+            tap(&self.tapped);
+            self.tapped.clear();
+        }
+    }
+}
+
+impl Drop for ClipState {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+impl Clip {
+    pub(crate) fn new(source: StreamingSource, level: OutputLevel, rate: PlaybackRate) -> Self {
+        let sample_rate = source.sample_rate();
+        Self(Arc::new(Mutex::new(ClipState {
+            source: Some(source),
+            level,
+            rate,
+            stretcher: None,
+            recorder: None,
+            recorded: Vec::new(),
+            tap: None,
+            tapped: Vec::new(),
+            overlay: None,
+            sample_rate,
+            started: false,
+            pulled: 0,
+        })))
+    }
+
+    /// Played to the end or cancelled.
+    pub fn is_done(&self) -> bool {
+        self.0.lock().unwrap().source.is_none()
+    }
+
+    /// A sink has started playing the clip (it may have finished since).
+    pub fn is_started(&self) -> bool {
+        self.0.lock().unwrap().started
+    }
+
+    /// How many of the source's samples have played. While time-stretching
+    /// this runs a frame (~25 ms) ahead, as the stretcher reads ahead.
+    pub fn position(&self) -> usize {
+        self.0.lock().unwrap().pulled
+    }
+
+    /// Drop the source, ending the clip in every sink that holds it.
+    pub fn cancel(&self) {
+        let mut state = self.0.lock().unwrap();
+        state.source.take();
+        state.flush();
+    }
+
+    /// Also write the samples played from now on to `recorder`.
+    pub fn record_to(&self, recorder: WavRecorder) {
+        self.0.lock().unwrap().recorder = Some(recorder);
+    }
+
+    /// Call `f` with the samples played from now on, time-stretched but
+    /// before volume and ducking, in batches of about 20 ms.
+    pub fn tap_to(&self, f: impl FnMut(&[i16]) + Send + 'static) {
+        self.0.lock().unwrap().tap = Some(Box::new(f));
+    }
+
+    /// Mix whatever `overlay` holds over this clip as it plays.
+    pub fn mix_in(&self, overlay: Overlay) {
+        self.0.lock().unwrap().overlay = Some(overlay);
+    }
+}
+
+/// Clips played over the queue rather than after it (priority speech),
+/// mixed into whichever clip is playing, one after another. Each keeps its
+/// own level, so ducking the queue doesn't duck them. They're only heard
+/// while a clip of the queue plays; the playback thread moves them into the
+/// queue when it runs dry. Cheap to clone.
+#[derive(Clone, Default)]
+pub struct Overlay(Arc<Mutex<VecDeque<Clip>>>);
+
+impl Overlay {
+    /// Play `clip` after the overlay clips already added. It must not mix
+    /// in an overlay itself.
+    pub fn push(&self, clip: Clip) {
+        self.0.lock().unwrap().push_back(clip);
+    }
+
+    /// Take out every clip, finished or not.
+    pub fn take(&self) -> Vec<Clip> {
+        self.0.lock().unwrap().drain(..).collect()
+    }
+
+    /// The next overlay sample, before and after its level.
+    fn step(&self) -> Option<(i16, i16)> {
+        let mut clips = self.0.lock().unwrap();
+        while let Some(clip) = clips.front() {
+            if let Some(sample) = clip.0.lock().unwrap().step() {
+                return Some(sample);
+            }
+            clips.pop_front();
+        }
+        None
+    }
+}
+
+impl Iterator for Clip {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        let mut guard = self.0.lock().unwrap();
+        let state = &mut *guard;
+        let (mut raw, mut sample) = state.step()?;
+        if let Some((overlay_raw, overlay)) = state.overlay.as_ref().and_then(Overlay::step) {
+            raw = raw.saturating_add(overlay_raw);
+            sample = sample.saturating_add(overlay);
+        }
+        if state.tap.is_some() {
+            state.tapped.push(raw);
+            if state.tapped.len() >= state.batch_len() {
+                state.flush();
+            }
+        }
+        if state.recorder.is_some() {
+            state.recorded.push(sample);
+            if state.recorded.len() >= state.batch_len() {
+                state.flush();
+            }
+        }
+        Some(sample)
+    }
+}
+
+impl Source for Clip {
+    fn current_frame_len(&self) -> Option<usize> {
+        let state = self.0.lock().unwrap();
+        match &state.source {
+            // The stretcher reads ahead, so the source can run dry first.
+            Some(_) if state.stretcher.is_some() => None,
+            Some(source) => source.current_frame_len(),
+            None => Some(0),
+        }
+    }
+
+    fn channels(&self) -> u16 {
+        self.0.lock().unwrap().source.as_ref().map_or(1, |s| s.channels())
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.0.lock().unwrap().sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+// ─── Device ────────────────────────────────────────────────────────────────
+
+/// rodio on an output device. Keeps its own handles to the clips it was
+/// given, so [`set_device`](AudioSink::set_device) can reopen on another
+/// device and carry on mid-sentence. Polls the device list, falling back to
+/// the default when its device disappears and returning once it's back.
+pub struct DeviceSink {
+    /// Device asked for; `None` = the system default.
+    preferred: Option<String>,
+    output: Option<DeviceOutput>,
+    clips: VecDeque<Clip>,
+    paused: bool,
+    /// Plays clips silently while there's no device.
+    fallback: NullSink,
+    /// `None` until the first tick opens a device.
+    last_check: Option<Instant>,
+}
+
+impl DeviceSink {
+    /// Play on `device` (by name), or the system default. The device is
+    /// opened on the first tick.
+    pub fn new(device: Option<String>) -> Self {
+        Self {
+            preferred: device,
+            output: None,
+            clips: VecDeque::new(),
+            paused: false,
+            fallback: NullSink::realtime(Duration::ZERO),
+            last_check: None,
+        }
+    }
+
+    /// Open a device and hand it the unfinished clips. `log_failure` is off
+    /// for retries while waiting for a device, to report each outage once.
+    fn open(&mut self, log_failure: bool) {
+        let output = match DeviceOutput::open(self.preferred.as_deref()) {
+            Ok(output) => output,
+            Err(e) => {
+                if log_failure {
+                    error!("playback: {e}; playing silently until an audio device appears");
+                } else {
+                    debug!("playback: {e}");
+                }
+                self.fallback.clear();
+                for clip in &self.clips {
+                    self.fallback.append(clip.clone());
+                }
+                return;
+            }
+        };
+        if let Some(name) = &self.preferred
+            && *name != output.name
+        {
+            warn!("playback: {name} not available, using {}", output.name);
+        }
+        info!("playback: audio output {}", output.name);
+        self.fallback.clear();
+        for clip in &self.clips {
+            output.sink.append(clip.clone());
+        }
+        if self.paused {
+            output.sink.pause();
+        }
+        self.output = Some(output);
+    }
+}
+
+impl AudioSink for DeviceSink {
+    fn append(&mut self, clip: Clip) {
+        match &self.output {
+            Some(output) => output.sink.append(clip.clone()),
+            None => self.fallback.append(clip.clone()),
+        }
+        self.clips.push_back(clip);
+    }
+
+    fn pause(&mut self) {
+        self.paused = true;
+        self.fallback.pause();
+        if let Some(output) = &self.output {
+            output.sink.pause();
+        }
+    }
+
+    fn play(&mut self) {
+        self.paused = false;
+        self.fallback.play();
+        if let Some(output) = &self.output {
+            output.sink.play();
+        }
+    }
+
+    fn clear(&mut self) {
+        self.clips.clear();
+        self.fallback.clear();
+        if let Some(output) = &mut self.output
+            && let Err(e) = output.reset_sink()
+        {
+            error!("playback: {e}");
+            self.output = None;
+        }
+    }
+
+    fn tick(&mut self) -> Duration {
+        // Only clips still to play need moving to another device.
+        self.clips.retain(|clip| !clip.is_done());
+        match self.last_check {
+            None => {
+                self.last_check = Some(Instant::now());
+                self.open(true);
+            }
+            Some(last) if last.elapsed() >= DEVICE_POLL_INTERVAL => {
+                self.last_check = Some(Instant::now());
+                let present = output_device_names();
+                let lost = self.output.as_ref().is_none_or(|o| !present.contains(&o.name));
+                let preferred_back = self.preferred.as_ref().is_some_and(|name| {
+                    present.contains(name) && self.output.as_ref().is_some_and(|o| &o.name != name)
+                });
+                if lost || preferred_back {
+                    let old = self.output.take();
+                    if let Some(old) = &old {
+                        warn!("playback: moving off audio output {}", old.name);
+                    }
+                    self.open(old.is_some());
+                }
+            }
+            Some(_) => {}
+        }
+        match self.output {
+            Some(_) => IDLE,
+            None => self.fallback.tick(),
+        }
+    }
+
+    fn set_device(&mut self, name: Option<String>) {
+        self.clips.retain(|clip| !clip.is_done());
+        self.preferred = name;
+        self.output = None;
+        self.last_check = Some(Instant::now());
+        self.open(true);
+    }
+
+    fn device(&self) -> Option<String> {
+        self.output.as_ref().map(|o| o.name.clone())
+    }
+}
+
+// ─── Null ──────────────────────────────────────────────────────────────────
+
+/// Consumes clips without playing them.
+pub struct NullSink {
+    clips: VecDeque<Clip>,
+    paused: bool,
+    /// `Some(lead)`: keep pace with the wall clock, `lead` ahead of it.
+    /// `None`: as fast as the clips' audio arrives.
+    pace: Option<Duration>,
+    /// Wall-clock time the audio pulled so far runs out.
+    played_until: Instant,
+}
+
+impl NullSink {
+    /// Consume clips at real-time speed, running `lead` ahead of the clock,
+    /// so progress events fire when they would on speakers.
+    pub fn realtime(lead: Duration) -> Self {
+        Self {
+            clips: VecDeque::new(),
+            paused: false,
+            pace: Some(lead),
+            played_until: Instant::now(),
+        }
+    }
+
+    /// Consume clips as fast as they're synthesized.
+    pub fn unthrottled() -> Self {
+        Self {
+            pace: None,
+            ..Self::realtime(Duration::ZERO)
+        }
+    }
+
+    /// Pull one step of the front clip. Returns the audio time pulled.
+    fn pull(&mut self) -> Option<Duration> {
+        let clip = self.clips.front_mut()?;
+        let rate = clip.sample_rate() as usize * clip.channels() as usize;
+        let step = (rate / BATCHES_PER_SECOND).max(1);
+        let pulled = clip.by_ref().take(step).count();
+        if pulled < step {
+            self.clips.pop_front();
+        }
+        Some(Duration::from_secs_f64(pulled as f64 / rate as f64))
+    }
+}
+
+impl AudioSink for NullSink {
+    fn append(&mut self, clip: Clip) {
+        self.clips.push_back(clip);
+    }
+
+    fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    fn play(&mut self) {
+        self.paused = false;
+    }
+
+    fn clear(&mut self) {
+        self.clips.clear();
+    }
+
+    fn tick(&mut self) -> Duration {
+        if self.paused || self.clips.is_empty() {
+            return IDLE;
+        }
+        let Some(lead) = self.pace else {
+            // A second of audio per tick, so commands still get a look in.
+            for _ in 0..BATCHES_PER_SECOND {
+                if self.pull().is_none() {
+                    break;
+                }
+            }
+            return if self.clips.is_empty() { IDLE } else { Duration::ZERO };
+        };
+        // After an idle or paused stretch, start the clock from now.
+        let now = Instant::now();
+        if self.played_until < now {
+            self.played_until = now;
+        }
+        while self.played_until <= now + lead {
+            match self.pull() {
+                Some(pulled) => self.played_until += pulled,
+                None => break,
+            }
+        }
+        self.played_until.saturating_duration_since(now + lead)
+    }
+}
+
+// ─── Recording ─────────────────────────────────────────────────────────────
+
+/// Plays through `inner` and records what it plays to a WAV file: clips
+/// back to back, without the idle time between them.
+pub struct Tee {
+    inner: Box<dyn AudioSink>,
+    recorder: WavRecorder,
+}
+
+impl Tee {
+    pub fn new(inner: Box<dyn AudioSink>, recorder: WavRecorder) -> Self {
+        Self { inner, recorder }
+    }
+}
+
+impl AudioSink for Tee {
+    fn append(&mut self, clip: Clip) {
+        clip.record_to(self.recorder.clone());
+        self.inner.append(clip);
+    }
+
+    fn pause(&mut self) {
+        self.inner.pause();
+    }
+
+    fn play(&mut self) {
+        self.inner.play();
+    }
+
+    fn clear(&mut self) {
+        self.inner.clear();
+    }
+
+    fn tick(&mut self) -> Duration {
+        self.inner.tick()
+    }
+
+    fn set_device(&mut self, name: Option<String>) {
+        self.inner.set_device(name);
+    }
+
+    fn device(&self) -> Option<String> {
+        self.inner.device()
+    }
+}
+
+/// A growing 16-bit mono WAV file. The header is kept up to date after every
+/// write, so the file is valid even if the process dies. Cheap to clone.
+#[derive(Clone)]
+pub struct WavRecorder(Arc<Mutex<Option<File>>>);
+
+impl WavRecorder {
+    /// Create (or truncate) the file.
+    pub fn create(path: &Path) -> Result<Self, String> {
+        let mut file = File::create(path)
+            .map_err(|e| format!("can't record to {}: {e}", path.display()))?;
+        file.write_all(&write_wav(&[], RECORDING_SAMPLE_RATE))
+            .map_err(|e| format!("can't record to {}: {e}", path.display()))?;
+        Ok(Self(Arc::new(Mutex::new(Some(file)))))
+    }
+
+    /// Append samples played at `sample_rate`. Stops recording (with a
+    /// warning) if the file can't be written.
+    fn write(&self, sample_rate: u32, samples: &[i16]) {
+        let mut file = self.0.lock().unwrap();
+        let Some(f) = file.as_mut() else {
+            return;
+        };
+        let samples = resample(samples, sample_rate, RECORDING_SAMPLE_RATE);
+        let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        if let Err(e) = append_wav_data(f, &bytes) {
+            warn!("recording stopped: {e}");
+            *file = None;
+        }
+    }
+}
+
+/// Append PCM bytes to a WAV file and fix up the RIFF and data sizes.
+fn append_wav_data(file: &mut File, bytes: &[u8]) -> std::io::Result<()> {
+    let end = file.seek(SeekFrom::End(0))?;
+    file.write_all(bytes)?;
+    let len = end + bytes.len() as u64;
+    file.seek(SeekFrom::Start(4))?;
+    file.write_all(&((len - 8) as u32).to_le_bytes())?;
+    file.seek(SeekFrom::Start(40))?;
+    file.write_all(&((len - 44) as u32).to_le_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaming_source::PcmChunk;
+    use std::sync::mpsc;
+
+    fn clip(samples: Vec<i16>) -> Clip {
+        let (tx, rx) = mpsc::channel();
+        tx.send(PcmChunk::Data(samples)).unwrap();
+        tx.send(PcmChunk::Done).unwrap();
+        Clip::new(
+            StreamingSource::new(rx, 1, 24_000),
+            OutputLevel::default(),
+            PlaybackRate::default(),
+        )
+    }
+
+    #[test]
+    fn clip_clones_share_one_position() {
+        let mut first = clip(vec![1, 2, 3]);
+        let mut second = first.clone();
+
+        assert_eq!(first.next(), Some(1));
+        drop(first);
+        assert_eq!(second.by_ref().collect::<Vec<_>>(), vec![2, 3]);
+        assert!(second.is_done());
+    }
+
+    #[test]
+    fn cancel_ends_every_copy() {
+        let mut clip = clip(vec![1, 2, 3]);
+        clip.clone().cancel();
+        assert_eq!(clip.next(), None);
+        assert_eq!(clip.current_frame_len(), Some(0));
+    }
+
+    #[test]
+    fn level_changes_ramp() {
+        let level = OutputLevel::default();
+        assert_eq!(level.apply(1000, 24_000), 1000);
+
+        level.set_volume(0.5);
+        let ramp: Vec<i16> = (0..4000).map(|_| level.apply(1000, 24_000)).collect();
+        assert!(ramp[0] > 990, "no jump: {}", ramp[0]);
+        assert!(ramp.windows(2).all(|w| w[1] <= w[0]));
+        assert_eq!(*ramp.last().unwrap(), 500);
+
+        level.set_duck(0.5);
+        for _ in 0..24_000 {
+            level.apply(1000, 24_000);
+        }
+        assert_eq!(level.apply(1000, 24_000), 250);
+    }
+
+    #[test]
+    fn rate_changes_take_effect_mid_clip() {
+        let rate = PlaybackRate::default();
+        let (tx, rx) = mpsc::channel();
+        tx.send(PcmChunk::Data(vec![100; 48_000])).unwrap();
+        tx.send(PcmChunk::Done).unwrap();
+        let mut clip = Clip::new(
+            StreamingSource::new(rx, 1, 24_000),
+            OutputLevel::default(),
+            rate.clone(),
+        );
+        // The first half second plays as is, the rest at double speed.
+        let first: Vec<i16> = clip.by_ref().take(12_000).collect();
+        assert!(first.iter().all(|&s| s == 100));
+        assert_eq!(clip.position(), 12_000);
+        rate.set(2.0);
+        let rest = clip.by_ref().count();
+        assert!((17_000..=19_000).contains(&rest), "{rest}");
+        assert!(clip.is_done());
+    }
+
+    #[test]
+    fn tap_sees_stretched_audio_in_batches() {
+        let rate = PlaybackRate::default();
+        rate.set(2.0);
+        let (tx, rx) = mpsc::channel();
+        tx.send(PcmChunk::Data((0..48_000).map(|i| (i % 200) as i16).collect())).unwrap();
+        tx.send(PcmChunk::Done).unwrap();
+        let clip = Clip::new(StreamingSource::new(rx, 1, 24_000), OutputLevel::default(), rate);
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let b = batches.clone();
+        clip.tap_to(move |samples| b.lock().unwrap().push(samples.to_vec()));
+
+        let played: Vec<i16> = clip.collect();
+        let batches = batches.lock().unwrap();
+        assert!(batches.iter().all(|batch| batch.len() <= 480));
+        assert_eq!(batches.concat(), played);
+        assert!((23_000..=25_000).contains(&played.len()), "{}", played.len());
+    }
+
+    #[test]
+    fn overlay_mixes_over_consecutive_clips() {
+        let overlay = Overlay::default();
+        overlay.push(clip(vec![10, 10, 10]));
+        let (mut first, mut second) = (clip(vec![1, 1]), clip(vec![2, 2]));
+        first.mix_in(overlay.clone());
+        second.mix_in(overlay.clone());
+
+        assert_eq!(first.by_ref().collect::<Vec<_>>(), vec![11, 11]);
+        assert_eq!(second.by_ref().collect::<Vec<_>>(), vec![12, 2]);
+        assert!(overlay.take().is_empty());
+    }
+
+    #[test]
+    fn unthrottled_null_sink_drains_clips() {
+        let mut sink = NullSink::unthrottled();
+        let (a, b) = (clip(vec![0; 1000]), clip(vec![0; 10]));
+        sink.append(a.clone());
+        sink.append(b.clone());
+        assert_eq!(sink.tick(), IDLE);
+        assert!(a.is_done() && b.is_done());
+    }
+
+    #[test]
+    fn realtime_null_sink_keeps_pace() {
+        let mut sink = NullSink::realtime(Duration::ZERO);
+        let long = clip(vec![0; 24_000]);
+        sink.append(long.clone());
+        let wait = sink.tick();
+        assert!(wait > Duration::ZERO && wait <= Duration::from_millis(20));
+        assert!(!long.is_done());
+
+        sink.pause();
+        assert_eq!(sink.tick(), IDLE);
+    }
+
+    #[test]
+    fn device_sink_forgets_finished_clips() {
+        let mut sink = DeviceSink::new(None);
+        let (done, playing) = (clip(vec![1, 2, 3]), clip(vec![0; 24_000]));
+        done.cancel();
+        sink.append(done);
+        sink.append(playing.clone());
+        sink.pause();
+        sink.tick();
+        assert_eq!(sink.clips.len(), 1);
+        playing.cancel();
+        sink.tick();
+        assert!(sink.clips.is_empty());
+    }
+
+    #[test]
+    fn file_sink_records_played_audio() {
+        let path = std::env::temp_dir().join(format!("nayru-record-{}.wav", std::process::id()));
+        let mut sink = file_sink(WavRecorder::create(&path).unwrap(), false);
+        sink.append(clip(vec![1, 2, 3]));
+        sink.append(clip(vec![4, 5]));
+        sink.tick();
+
+        let wav = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(u32::from_le_bytes(wav[4..8].try_into().unwrap()) as usize, wav.len() - 8);
+        assert_eq!(u32::from_le_bytes(wav[40..44].try_into().unwrap()), 10);
+        let samples: Vec<i16> = wav[44..]
+            .chunks(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect();
+        assert_eq!(samples, vec![1, 2, 3, 4, 5]);
+    }
+}
@@ -0,0 +1,200 @@
+//! Live audio for remote listeners.
+//!
+//! When the engine's [`AudioOutput`](nayru_core::types::AudioOutput) includes
+//! remote listeners, every clip the playback thread plays is also published
+//! on an [`AudioTap`] in ~20 ms frames, as it plays. `/audio/stream` and
+//! `/audio/ws` turn the tap into chunked HTTP or WebSocket streams of raw PCM
+//! or Opus.
+//!
+//! The stream runs at a fixed [`STREAM_SAMPLE_RATE`]; clips from a backend
+//! with another rate are resampled on the way in. Frames carry the epoch of
+//! the utterance they belong to, and `stop()` publishes a [`StreamFrame::Flush`]
+//! with the new epoch, so listeners can drop audio they've buffered and skip
+//! stale frames still in flight.
+
+use std::sync::Arc;
+
+use tokio::sync::broadcast;
+
+#[cfg(feature = "opus")]
+use crate::encode::{AudioTags, OpusStream};
+use crate::encode::AudioFormat;
+
+/// Sample rate of every remote stream (Kokoro's native rate).
+pub const STREAM_SAMPLE_RATE: u32 = crate::kokoro::SAMPLE_RATE;
+
+/// Frames a listener can fall behind before it skips ahead (~10 s).
+const TAP_CAPACITY: usize = 512;
+
+/// One message on the tap.
+#[derive(Debug, Clone)]
+pub enum StreamFrame {
+    /// Mono 16-bit PCM at [`STREAM_SAMPLE_RATE`].
+    Audio { epoch: u64, samples: Arc<[i16]> },
+    /// Speech was stopped; audio from epochs before `epoch` is stale.
+    Flush { epoch: u64 },
+}
+
+/// Broadcasts played audio to remote listeners. Cheap to clone.
+#[derive(Clone)]
+pub struct AudioTap {
+    tx: broadcast::Sender<StreamFrame>,
+}
+
+impl Default for AudioTap {
+    fn default() -> Self {
+        Self {
+            tx: broadcast::channel(TAP_CAPACITY).0,
+        }
+    }
+}
+
+impl AudioTap {
+    /// Publish samples played at `sample_rate`. Free when nobody listens.
+    pub fn send_audio(&self, epoch: u64, sample_rate: u32, samples: &[i16]) {
+        if self.tx.receiver_count() == 0 {
+            return;
+        }
+        let samples = resample(samples, sample_rate, STREAM_SAMPLE_RATE);
+        let _ = self.tx.send(StreamFrame::Audio {
+            epoch,
+            samples: samples.into(),
+        });
+    }
+
+    /// Tell listeners everything before `epoch` is cancelled.
+    pub fn flush(&self, epoch: u64) {
+        let _ = self.tx.send(StreamFrame::Flush { epoch });
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<StreamFrame> {
+        self.tx.subscribe()
+    }
+}
+
+/// Linear-interpolation resampling; good enough for speech between common
+/// rates. Returns the input unchanged when the rates match.
+pub(crate) fn resample(samples: &[i16], from: u32, to: u32) -> Vec<i16> {
+    if from == to || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let len = (samples.len() as u64 * to as u64 / from as u64) as usize;
+    let step = from as f64 / to as f64;
+    (0..len)
+        .map(|i| {
+            let pos = i as f64 * step;
+            let idx = pos as usize;
+            let frac = pos - idx as f64;
+            let a = samples[idx] as f64;
+            let b = samples.get(idx + 1).copied().unwrap_or(samples[idx]) as f64;
+            (a + (b - a) * frac).round() as i16
+        })
+        .collect()
+}
+
+/// Tracks the newest flush a listener has seen, to drop stale audio.
+#[derive(Debug, Default)]
+pub struct EpochFilter {
+    min_epoch: u64,
+}
+
+impl EpochFilter {
+    /// The frame's samples, or `None` for a flush or stale audio.
+    pub fn accept(&mut self, frame: &StreamFrame) -> Option<Arc<[i16]>> {
+        match frame {
+            StreamFrame::Audio { epoch, samples } if *epoch >= self.min_epoch => {
+                Some(samples.clone())
+            }
+            StreamFrame::Audio { .. } => None,
+            StreamFrame::Flush { epoch } => {
+                self.min_epoch = self.min_epoch.max(*epoch);
+                None
+            }
+        }
+    }
+}
+
+/// Encodes stream audio for one listener: raw PCM or Opus.
+pub enum LiveEncoder {
+    /// 16-bit little-endian mono.
+    Pcm,
+    #[cfg(feature = "opus")]
+    Opus(OpusStream),
+}
+
+impl LiveEncoder {
+    /// `ogg` wraps Opus in an Ogg container (for a plain HTTP stream);
+    /// otherwise each Opus packet comes out on its own.
+    pub fn new(format: AudioFormat, ogg: bool) -> Result<Self, String> {
+        match format {
+            AudioFormat::Pcm => Ok(Self::Pcm),
+            #[cfg(feature = "opus")]
+            AudioFormat::Opus if ogg => Ok(Self::Opus(OpusStream::ogg(
+                STREAM_SAMPLE_RATE,
+                &AudioTags::default(),
+            )?)),
+            #[cfg(feature = "opus")]
+            AudioFormat::Opus => Ok(Self::Opus(OpusStream::packets(STREAM_SAMPLE_RATE)?)),
+            other => Err(format!(
+                "can't stream {}; live audio is pcm{}",
+                other.name(),
+                if AudioFormat::Opus.is_available() { " or opus" } else { "" }
+            )),
+        }
+    }
+
+    /// Encode the next samples. May return nothing while Opus fills a frame.
+    pub fn push(&mut self, samples: &[i16]) -> Result<Vec<Vec<u8>>, String> {
+        match self {
+            Self::Pcm => Ok(vec![samples.iter().flat_map(|s| s.to_le_bytes()).collect()]),
+            #[cfg(feature = "opus")]
+            Self::Opus(stream) => stream.push(samples),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resample_scales_length_and_interpolates() {
+        assert_eq!(resample(&[1, 2, 3], 24_000, 24_000), vec![1, 2, 3]);
+        assert_eq!(resample(&[0, 100, 200, 300], 24_000, 48_000).len(), 8);
+        assert_eq!(resample(&[0, 100, 200, 300], 24_000, 48_000)[1], 50);
+        assert_eq!(resample(&[0; 22_050], 22_050, 24_000).len(), 24_000);
+    }
+
+    #[test]
+    fn filter_drops_audio_from_before_a_flush() {
+        let audio = |epoch| StreamFrame::Audio {
+            epoch,
+            samples: Arc::from(vec![1i16]),
+        };
+        let mut filter = EpochFilter::default();
+        assert!(filter.accept(&audio(0)).is_some());
+        assert!(filter.accept(&StreamFrame::Flush { epoch: 1 }).is_none());
+        assert!(filter.accept(&audio(0)).is_none());
+        assert!(filter.accept(&audio(1)).is_some());
+    }
+
+    #[test]
+    fn live_encoders() {
+        let mut pcm = LiveEncoder::new(AudioFormat::Pcm, true).unwrap();
+        assert_eq!(pcm.push(&[1, -2]).unwrap(), vec![vec![0x01, 0x00, 0xFE, 0xFF]]);
+        assert!(LiveEncoder::new(AudioFormat::Flac, true).is_err());
+    }
+
+    #[tokio::test]
+    async fn tap_skips_work_without_listeners() {
+        let tap = AudioTap::default();
+        tap.send_audio(0, 24_000, &[1, 2]);
+        let mut rx = tap.subscribe();
+        tap.send_audio(0, 12_000, &[1, 2]);
+        match rx.recv().await.unwrap() {
+            StreamFrame::Audio { samples, .. } => assert_eq!(samples.len(), 4),
+            other => panic!("unexpected {other:?}"),
+        }
+        assert!(rx.try_recv().is_err());
+    }
+}
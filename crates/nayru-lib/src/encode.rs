@@ -0,0 +1,293 @@
+//! Audio encoders for rendered speech.
+//!
+//! Turns the engine's 16-bit mono PCM into a file format: WAV, raw PCM,
+//! FLAC, Ogg Opus, or MP3. FLAC and the containers are written here in pure
+//! Rust; Opus and MP3 compression come from libopus and LAME behind the
+//! `opus` and `mp3` cargo features (both on by default).
+//!
+//! Title, artist, and voice tags go in a Vorbis comment block (FLAC, Opus),
+//! an ID3v2 tag (MP3), or a `LIST/INFO` chunk (WAV). Raw PCM has nowhere to
+//! put them.
+
+mod flac;
+#[cfg(feature = "mp3")]
+mod mp3;
+#[cfg(feature = "opus")]
+mod ogg;
+#[cfg(feature = "opus")]
+mod opus;
+
+use std::path::Path;
+
+#[cfg(feature = "opus")]
+pub use opus::OpusStream;
+
+use nayru_core::wav::write_wav;
+
+/// File formats [`encode`] can produce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioFormat {
+    Wav,
+    /// Raw 16-bit little-endian mono PCM at the synthesizer's rate (24 kHz
+    /// for Kokoro, same as OpenAI's `pcm`).
+    Pcm,
+    Flac,
+    /// Opus in an Ogg container.
+    Opus,
+    Mp3,
+}
+
+impl AudioFormat {
+    const ALL: [AudioFormat; 5] = [Self::Wav, Self::Pcm, Self::Flac, Self::Opus, Self::Mp3];
+
+    /// Name used by `response_format` and in error messages.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Wav => "wav",
+            Self::Pcm => "pcm",
+            Self::Flac => "flac",
+            Self::Opus => "opus",
+            Self::Mp3 => "mp3",
+        }
+    }
+
+    /// Whether this build can encode the format (Opus and MP3 are optional).
+    pub fn is_available(self) -> bool {
+        match self {
+            Self::Opus => cfg!(feature = "opus"),
+            Self::Mp3 => cfg!(feature = "mp3"),
+            Self::Wav | Self::Pcm | Self::Flac => true,
+        }
+    }
+
+    /// Names of every format this build can encode.
+    pub fn supported() -> Vec<&'static str> {
+        Self::ALL
+            .into_iter()
+            .filter(|f| f.is_available())
+            .map(Self::name)
+            .collect()
+    }
+
+    /// Parse a format name (`wav`, `pcm`, `flac`, `opus`, `mp3`).
+    pub fn parse(name: &str) -> Result<Self, String> {
+        Self::ALL
+            .into_iter()
+            .find(|f| f.name() == name && f.is_available())
+            .ok_or_else(|| {
+                format!(
+                    "unsupported audio format '{}'; supported: {}",
+                    name,
+                    Self::supported().join(", ")
+                )
+            })
+    }
+
+    /// Pick a format from a file extension (`chapter.flac`, `notes.ogg`, ...).
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let name = match ext.as_str() {
+            "ogg" | "oga" => "opus",
+            "raw" => "pcm",
+            other => other,
+        };
+        Self::parse(name).map_err(|_| {
+            format!(
+                "can't tell the audio format of {}; use one of: .{}",
+                path.display(),
+                Self::supported().join(", .")
+            )
+        })
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Wav => "audio/wav",
+            Self::Pcm => "audio/pcm",
+            Self::Flac => "audio/flac",
+            Self::Opus => "audio/ogg",
+            Self::Mp3 => "audio/mpeg",
+        }
+    }
+}
+
+/// Metadata written into formats that have room for it.
+#[derive(Debug, Clone, Default)]
+pub struct AudioTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    /// The nayru voice that spoke the audio.
+    pub voice: Option<String>,
+}
+
+impl AudioTags {
+    /// `(field, value)` pairs, using Vorbis comment field names.
+    fn fields(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("TITLE", &self.title),
+            ("ARTIST", &self.artist),
+            ("VOICE", &self.voice),
+        ]
+        .into_iter()
+        .filter_map(|(field, value)| Some((field, value.as_deref()?)))
+        .collect()
+    }
+}
+
+/// Vendor string in Vorbis comments.
+const VENDOR: &str = concat!("nayru ", env!("CARGO_PKG_VERSION"));
+
+/// Encode 16-bit mono PCM as `format`.
+pub fn encode(
+    format: AudioFormat,
+    pcm: &[i16],
+    sample_rate: u32,
+    tags: &AudioTags,
+) -> Result<Vec<u8>, String> {
+    match format {
+        AudioFormat::Wav => Ok(tag_wav(write_wav(pcm, sample_rate), tags)),
+        AudioFormat::Pcm => Ok(pcm.iter().flat_map(|s| s.to_le_bytes()).collect()),
+        AudioFormat::Flac => Ok(flac::encode(pcm, sample_rate, tags)),
+        #[cfg(feature = "opus")]
+        AudioFormat::Opus => opus::encode(pcm, sample_rate, tags),
+        #[cfg(feature = "mp3")]
+        AudioFormat::Mp3 => mp3::encode(pcm, sample_rate, tags),
+        #[allow(unreachable_patterns)]
+        other => Err(format!("{} support isn't compiled in", other.name())),
+    }
+}
+
+/// A Vorbis comment block: vendor string plus `FIELD=value` pairs, all
+/// lengths little-endian. Shared by FLAC and OpusTags.
+fn vorbis_comments(tags: &AudioTags) -> Vec<u8> {
+    let fields = tags.fields();
+    let mut buf = Vec::new();
+    buf.extend_from_slice(&(VENDOR.len() as u32).to_le_bytes());
+    buf.extend_from_slice(VENDOR.as_bytes());
+    buf.extend_from_slice(&(fields.len() as u32).to_le_bytes());
+    for (field, value) in fields {
+        let comment = format!("{field}={value}");
+        buf.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+        buf.extend_from_slice(comment.as_bytes());
+    }
+    buf
+}
+
+/// Append a `LIST/INFO` chunk (INAM title, IART artist, ICMT voice) after
+/// the data chunk and fix up the RIFF size.
+fn tag_wav(mut wav: Vec<u8>, tags: &AudioTags) -> Vec<u8> {
+    let voice = tags.voice.as_ref().map(|v| format!("voice: {v}"));
+    let entries: Vec<(&[u8; 4], &str)> = [
+        (b"INAM", tags.title.as_deref()),
+        (b"IART", tags.artist.as_deref()),
+        (b"ICMT", voice.as_deref()),
+    ]
+    .into_iter()
+    .filter_map(|(id, value)| Some((id, value?)))
+    .collect();
+    if entries.is_empty() {
+        return wav;
+    }
+
+    // The data chunk must stay word-aligned for the chunks after it.
+    if wav.len() % 2 == 1 {
+        wav.push(0);
+    }
+    let mut info = b"INFO".to_vec();
+    for (id, value) in entries {
+        // NUL-terminated, padded to an even length.
+        let len = value.len() + 1;
+        info.extend_from_slice(id);
+        info.extend_from_slice(&(len as u32).to_le_bytes());
+        info.extend_from_slice(value.as_bytes());
+        info.push(0);
+        if len % 2 == 1 {
+            info.push(0);
+        }
+    }
+    wav.extend_from_slice(b"LIST");
+    wav.extend_from_slice(&(info.len() as u32).to_le_bytes());
+    wav.extend_from_slice(&info);
+
+    let riff_len = (wav.len() - 8) as u32;
+    wav[4..8].copy_from_slice(&riff_len.to_le_bytes());
+    wav
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_parse() {
+        assert_eq!(AudioFormat::parse("wav"), Ok(AudioFormat::Wav));
+        assert_eq!(AudioFormat::parse("pcm"), Ok(AudioFormat::Pcm));
+        assert_eq!(AudioFormat::parse("flac"), Ok(AudioFormat::Flac));
+        assert!(AudioFormat::parse("aac").unwrap_err().contains("wav, pcm, flac"));
+    }
+
+    #[test]
+    fn format_from_extension() {
+        let format = |p: &str| AudioFormat::from_path(Path::new(p));
+        assert_eq!(format("out/chapter.FLAC"), Ok(AudioFormat::Flac));
+        assert_eq!(format("a.wav"), Ok(AudioFormat::Wav));
+        assert_eq!(format("a.raw"), Ok(AudioFormat::Pcm));
+        if cfg!(feature = "opus") {
+            assert_eq!(format("a.ogg"), Ok(AudioFormat::Opus));
+        }
+        assert!(format("a.txt").unwrap_err().contains(".wav"));
+        assert!(format("noext").is_err());
+    }
+
+    #[test]
+    fn pcm_format_is_little_endian() {
+        let bytes = encode(AudioFormat::Pcm, &[1, -2], 24_000, &AudioTags::default()).unwrap();
+        assert_eq!(bytes, vec![0x01, 0x00, 0xFE, 0xFF]);
+    }
+
+    #[test]
+    fn wav_format_has_header() {
+        let bytes = encode(AudioFormat::Wav, &[0; 10], 22_050, &AudioTags::default()).unwrap();
+        assert_eq!(&bytes[0..4], b"RIFF");
+        assert_eq!(bytes.len(), 44 + 20);
+        assert_eq!(u32::from_le_bytes(bytes[24..28].try_into().unwrap()), 22_050);
+    }
+
+    #[test]
+    fn wav_tags_go_in_list_chunk() {
+        let tags = AudioTags {
+            title: Some("Ch 1".into()),
+            voice: Some("af_heart".into()),
+            ..AudioTags::default()
+        };
+        let bytes = encode(AudioFormat::Wav, &[0; 10], 24_000, &tags).unwrap();
+        let riff_len = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize;
+        assert_eq!(riff_len, bytes.len() - 8);
+        assert_eq!(&bytes[64..68], b"LIST");
+        assert_eq!(&bytes[72..76], b"INFO");
+        assert_eq!(&bytes[76..80], b"INAM");
+        assert_eq!(&bytes[84..89], b"Ch 1\0");
+        let rest = String::from_utf8_lossy(&bytes[89..]);
+        assert!(rest.contains("ICMT") && rest.contains("voice: af_heart"));
+        assert_eq!(bytes.len() % 2, 0);
+    }
+
+    #[test]
+    fn vorbis_comments_layout() {
+        let tags = AudioTags {
+            artist: Some("Me".into()),
+            ..AudioTags::default()
+        };
+        let block = vorbis_comments(&tags);
+        let vendor_len = u32::from_le_bytes(block[0..4].try_into().unwrap()) as usize;
+        assert_eq!(&block[4..4 + vendor_len], VENDOR.as_bytes());
+        let rest = &block[4 + vendor_len..];
+        assert_eq!(u32::from_le_bytes(rest[0..4].try_into().unwrap()), 1);
+        assert_eq!(u32::from_le_bytes(rest[4..8].try_into().unwrap()), 9);
+        assert_eq!(&rest[8..], b"ARTIST=Me");
+    }
+}
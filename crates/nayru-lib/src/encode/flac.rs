@@ -0,0 +1,432 @@
+//! FLAC encoder: fixed-predictor subframes with Rice-coded residuals.
+//!
+//! No LPC search, so files are a little larger than `flac -8` makes, but
+//! still lossless and well under half the size of WAV for speech. Silent
+//! blocks (sentence gaps) collapse to a few bytes.
+
+use super::{vorbis_comments, AudioTags};
+
+/// Samples per frame; the last frame may be shorter.
+const BLOCK_SIZE: usize = 4096;
+const MAX_PARTITION_ORDER: u32 = 8;
+/// Largest parameter the 4-bit Rice method can signal (15 is the escape code).
+const MAX_RICE_PARAM: u32 = 14;
+
+/// Encode 16-bit mono PCM as a FLAC stream with a Vorbis comment block.
+pub(super) fn encode(pcm: &[i16], sample_rate: u32, tags: &AudioTags) -> Vec<u8> {
+    let mut frames = Vec::new();
+    let (mut min_frame, mut max_frame) = (0u32, 0u32);
+    for (number, block) in pcm.chunks(BLOCK_SIZE).enumerate() {
+        let start = frames.len();
+        write_frame(&mut frames, number as u64, block);
+        let len = (frames.len() - start) as u32;
+        min_frame = if number == 0 { len } else { min_frame.min(len) };
+        max_frame = max_frame.max(len);
+    }
+
+    let mut info = BitWriter::default();
+    info.write(BLOCK_SIZE as u64, 16); // min block size
+    info.write(BLOCK_SIZE as u64, 16); // max block size
+    info.write(min_frame as u64, 24);
+    info.write(max_frame as u64, 24);
+    info.write(sample_rate as u64, 20);
+    info.write(0, 3); // channels - 1
+    info.write(15, 5); // bits per sample - 1
+    let total = pcm.len() as u64;
+    info.write(total >> 32, 4);
+    info.write(total & 0xFFFF_FFFF, 32);
+    info.bytes.extend_from_slice(&[0; 16]); // MD5 unknown
+
+    let mut out = b"fLaC".to_vec();
+    write_metadata_block(&mut out, 0, false, &info.bytes);
+    write_metadata_block(&mut out, 4, true, &vorbis_comments(tags));
+    out.extend_from_slice(&frames);
+    out
+}
+
+fn write_metadata_block(out: &mut Vec<u8>, kind: u8, last: bool, body: &[u8]) {
+    out.push(((last as u8) << 7) | kind);
+    out.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+    out.extend_from_slice(body);
+}
+
+fn write_frame(out: &mut Vec<u8>, number: u64, block: &[i16]) {
+    let mut w = BitWriter::default();
+    w.write(0b11_1111_1111_1110, 14); // sync
+    w.write(0, 1); // reserved
+    w.write(0, 1); // fixed block size
+    let size_code = if block.len() == BLOCK_SIZE { 0b1100 } else { 0b0111 };
+    w.write(size_code, 4);
+    w.write(0b0000, 4); // sample rate from STREAMINFO
+    w.write(0b0000, 4); // mono
+    w.write(0b100, 3); // 16 bits per sample
+    w.write(0, 1); // reserved
+    write_utf8(&mut w, number);
+    if size_code == 0b0111 {
+        w.write(block.len() as u64 - 1, 16);
+    }
+    let crc = crc8(&w.bytes);
+    w.write(crc as u64, 8);
+
+    let samples: Vec<i32> = block.iter().map(|&s| s as i32).collect();
+    write_subframe(&mut w, &samples);
+    w.align();
+    let crc = crc16(&w.bytes);
+    w.write(crc as u64, 16);
+    out.extend_from_slice(&w.bytes);
+}
+
+/// Frame numbers use the UTF-8 variable-length scheme, extended to 36 bits.
+fn write_utf8(w: &mut BitWriter, value: u64) {
+    if value < 0x80 {
+        w.write(value, 8);
+        return;
+    }
+    let mut extra = 1;
+    while value >= 1 << (6 + 5 * extra) {
+        extra += 1;
+    }
+    let prefix = (0xFF00u64 >> (extra + 1)) & 0xFF;
+    w.write(prefix | (value >> (6 * extra)), 8);
+    for i in (0..extra).rev() {
+        w.write(0x80 | ((value >> (6 * i)) & 0x3F), 8);
+    }
+}
+
+fn write_subframe(w: &mut BitWriter, samples: &[i32]) {
+    if samples.iter().all(|&s| s == samples[0]) {
+        w.write(0b0000_0000, 8); // CONSTANT
+        w.write_signed(samples[0], 16);
+        return;
+    }
+
+    let max_order = 4.min(samples.len() - 1);
+    let (order, residual) = (0..=max_order)
+        .map(|order| (order, fixed_residual(samples, order)))
+        .min_by_key(|(_, r)| r.iter().map(|&x| x.unsigned_abs() as u64).sum::<u64>())
+        .expect("at least order 0");
+    let rice = RicePlan::best(&residual, samples.len(), order);
+
+    let fixed_bits = 8 + 16 * order as u64 + rice.bits;
+    if fixed_bits >= 8 + 16 * samples.len() as u64 {
+        w.write(0b0000_0010, 8); // VERBATIM
+        for &s in samples {
+            w.write_signed(s, 16);
+        }
+        return;
+    }
+
+    w.write((0b001000 | order as u64) << 1, 8); // FIXED
+    for &s in &samples[..order] {
+        w.write_signed(s, 16);
+    }
+    w.write(0b00, 2); // Rice, 4-bit parameters
+    w.write(rice.partition_order as u64, 4);
+    let mut rest = &residual[..];
+    for (i, &param) in rice.params.iter().enumerate() {
+        let len = (samples.len() >> rice.partition_order) - if i == 0 { order } else { 0 };
+        let (partition, tail) = rest.split_at(len);
+        rest = tail;
+        w.write(param as u64, 4);
+        for &r in partition {
+            let u = zigzag(r);
+            let mut q = u >> param;
+            while q >= 32 {
+                w.write(0, 32);
+                q -= 32;
+            }
+            w.write(0, q);
+            w.write(1, 1);
+            w.write((u & ((1 << param) - 1)) as u64, param);
+        }
+    }
+}
+
+/// Residual of FLAC's fixed polynomial predictor of `order` (0–4).
+fn fixed_residual(x: &[i32], order: usize) -> Vec<i32> {
+    (order..x.len())
+        .map(|i| match order {
+            0 => x[i],
+            1 => x[i] - x[i - 1],
+            2 => x[i] - 2 * x[i - 1] + x[i - 2],
+            3 => x[i] - 3 * x[i - 1] + 3 * x[i - 2] - x[i - 3],
+            _ => x[i] - 4 * x[i - 1] + 6 * x[i - 2] - 4 * x[i - 3] + x[i - 4],
+        })
+        .collect()
+}
+
+fn zigzag(r: i32) -> u32 {
+    ((r << 1) ^ (r >> 31)) as u32
+}
+
+/// Partitioning and per-partition Rice parameters for one residual.
+struct RicePlan {
+    partition_order: u32,
+    params: Vec<u32>,
+    /// Size of the whole residual section, in bits.
+    bits: u64,
+}
+
+impl RicePlan {
+    fn best(residual: &[i32], block_len: usize, order: usize) -> Self {
+        let zigzagged: Vec<u32> = residual.iter().map(|&r| zigzag(r)).collect();
+        (0..=MAX_PARTITION_ORDER)
+            .take_while(|&p| block_len.is_multiple_of(1 << p) && (block_len >> p) > order)
+            .map(|p| Self::with_order(&zigzagged, block_len, order, p))
+            .min_by_key(|plan| plan.bits)
+            .expect("partition order 0 always fits")
+    }
+
+    fn with_order(zigzagged: &[u32], block_len: usize, order: usize, p: u32) -> Self {
+        let mut params = Vec::with_capacity(1 << p);
+        let mut bits = 2 + 4;
+        let mut rest = zigzagged;
+        for i in 0..1usize << p {
+            let len = (block_len >> p) - if i == 0 { order } else { 0 };
+            let (partition, tail) = rest.split_at(len);
+            rest = tail;
+            let sum: u64 = partition.iter().map(|&u| u as u64).sum();
+            let mut k = 0;
+            while k < MAX_RICE_PARAM && ((len as u64) << (k + 1)) < sum {
+                k += 1;
+            }
+            bits += 4
+                + len as u64 * (k as u64 + 1)
+                + partition.iter().map(|&u| (u >> k) as u64).sum::<u64>();
+            params.push(k);
+        }
+        Self {
+            partition_order: p,
+            params,
+            bits,
+        }
+    }
+}
+
+/// MSB-first bit packer.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u64,
+    bits: u32,
+}
+
+impl BitWriter {
+    /// Append the low `bits` bits of `value` (at most 32).
+    fn write(&mut self, value: u64, bits: u32) {
+        debug_assert!(bits <= 32);
+        self.acc = (self.acc << bits) | (value & ((1u64 << bits) - 1));
+        self.bits += bits;
+        while self.bits >= 8 {
+            self.bits -= 8;
+            self.bytes.push((self.acc >> self.bits) as u8);
+        }
+        self.acc &= (1u64 << self.bits) - 1;
+    }
+
+    fn write_signed(&mut self, value: i32, bits: u32) {
+        self.write(value as u32 as u64, bits);
+    }
+
+    /// Zero-pad to a byte boundary.
+    fn align(&mut self) {
+        if self.bits > 0 {
+            self.write(0, 8 - self.bits);
+        }
+    }
+}
+
+/// CRC-8, polynomial 0x07 (frame headers).
+fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// CRC-16, polynomial 0x8005 (whole frames).
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x8005 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Just enough of a FLAC decoder to read back what `encode` writes.
+    struct BitReader<'a> {
+        data: &'a [u8],
+        pos: usize,
+    }
+
+    impl BitReader<'_> {
+        fn read(&mut self, bits: u32) -> u64 {
+            let mut v = 0;
+            for _ in 0..bits {
+                let bit = (self.data[self.pos / 8] >> (7 - self.pos % 8)) & 1;
+                v = (v << 1) | bit as u64;
+                self.pos += 1;
+            }
+            v
+        }
+
+        fn read_signed(&mut self, bits: u32) -> i32 {
+            let v = self.read(bits) as i64;
+            (if v >> (bits - 1) == 1 { v - (1 << bits) } else { v }) as i32
+        }
+
+        fn align(&mut self) {
+            self.pos = self.pos.div_ceil(8) * 8;
+        }
+    }
+
+    fn decode(flac: &[u8]) -> (u32, Vec<i16>, String) {
+        assert_eq!(&flac[..4], b"fLaC");
+        let mut pos = 4;
+        let (mut sample_rate, mut total, mut comments) = (0, 0, String::new());
+        loop {
+            let header = flac[pos];
+            let len = u32::from_be_bytes([0, flac[pos + 1], flac[pos + 2], flac[pos + 3]]) as usize;
+            let body = &flac[pos + 4..pos + 4 + len];
+            match header & 0x7F {
+                0 => {
+                    assert_eq!(len, 34);
+                    let mut r = BitReader { data: body, pos: 80 };
+                    sample_rate = r.read(20) as u32;
+                    assert_eq!(r.read(3), 0);
+                    assert_eq!(r.read(5), 15);
+                    total = r.read(36) as usize;
+                }
+                4 => comments = String::from_utf8_lossy(body).into_owned(),
+                _ => {}
+            }
+            pos += 4 + len;
+            if header & 0x80 != 0 {
+                break;
+            }
+        }
+
+        let mut samples = Vec::new();
+        while pos < flac.len() {
+            let mut r = BitReader { data: &flac[pos..], pos: 0 };
+            assert_eq!(r.read(14), 0b11_1111_1111_1110);
+            r.read(2);
+            let size_code = r.read(4);
+            assert_eq!(r.read(12), 0b0000_0000_1000);
+            let first = r.read(8);
+            for _ in 0..(first as u8).leading_ones().saturating_sub(1) {
+                r.read(8);
+            }
+            let n = match size_code {
+                0b1100 => BLOCK_SIZE,
+                0b0111 => r.read(16) as usize + 1,
+                other => panic!("unexpected block size code {other:#b}"),
+            };
+            assert_eq!(r.read(8) as u8, crc8(&r.data[..r.pos / 8 - 1]));
+
+            let kind = r.read(8) >> 1;
+            let mut x: Vec<i32> = Vec::with_capacity(n);
+            match kind {
+                0 => x.resize(n, r.read_signed(16)),
+                1 => (0..n).for_each(|_| x.push(r.read_signed(16))),
+                k if k & 0b111000 == 0b001000 => {
+                    let order = (k & 0b111) as usize;
+                    (0..order).for_each(|_| x.push(r.read_signed(16)));
+                    assert_eq!(r.read(2), 0);
+                    let p = r.read(4);
+                    for i in 0..1usize << p {
+                        let k = r.read(4) as u32;
+                        let len = (n >> p) - if i == 0 { order } else { 0 };
+                        for _ in 0..len {
+                            let mut q = 0;
+                            while r.read(1) == 0 {
+                                q += 1;
+                            }
+                            let u = (q << k) | r.read(k) as u32;
+                            let res = ((u >> 1) as i32) ^ -((u & 1) as i32);
+                            let j = x.len();
+                            let pred = match order {
+                                0 => 0,
+                                1 => x[j - 1],
+                                2 => 2 * x[j - 1] - x[j - 2],
+                                3 => 3 * x[j - 1] - 3 * x[j - 2] + x[j - 3],
+                                _ => 4 * x[j - 1] - 6 * x[j - 2] + 4 * x[j - 3] - x[j - 4],
+                            };
+                            x.push(pred + res);
+                        }
+                    }
+                }
+                other => panic!("unexpected subframe type {other:#b}"),
+            }
+            r.align();
+            let crc = crc16(&r.data[..r.pos / 8]);
+            assert_eq!(r.read(16) as u16, crc);
+            samples.extend(x.iter().map(|&s| s as i16));
+            pos += r.pos / 8;
+        }
+        assert_eq!(samples.len(), total);
+        (sample_rate, samples, comments)
+    }
+
+    #[test]
+    fn round_trips_losslessly() {
+        // Tone, then silence, then a noisy tone, ending on a short block.
+        let mut seed = 1u32;
+        let mut noise = || {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (seed >> 20) as i32 - 2048
+        };
+        let mut pcm: Vec<i16> = (0..6000)
+            .map(|i| ((i as f32 * 0.05).sin() * 12_000.0) as i16)
+            .collect();
+        pcm.extend(std::iter::repeat_n(0, 5000));
+        pcm.extend((0..3001).map(|i| {
+            let v = (i as f32 * 0.31).sin() * 30_000.0 + noise() as f32;
+            v.clamp(-32768.0, 32767.0) as i16
+        }));
+        pcm.extend([i16::MIN, i16::MAX, i16::MIN, 7]);
+
+        let tags = AudioTags {
+            title: Some("Chapter 1".into()),
+            voice: Some("bf_emma".into()),
+            ..AudioTags::default()
+        };
+        let flac = encode(&pcm, 24_000, &tags);
+        let (rate, decoded, comments) = decode(&flac);
+        assert_eq!(rate, 24_000);
+        assert_eq!(decoded, pcm);
+        assert!(comments.contains("TITLE=Chapter 1"));
+        assert!(comments.contains("VOICE=bf_emma"));
+        assert!(flac.len() < pcm.len() * 2 * 3 / 4);
+    }
+
+    #[test]
+    fn empty_and_tiny_inputs() {
+        let (_, decoded, _) = decode(&encode(&[], 24_000, &AudioTags::default()));
+        assert!(decoded.is_empty());
+        let (_, decoded, _) = decode(&encode(&[5], 24_000, &AudioTags::default()));
+        assert_eq!(decoded, vec![5]);
+        let (_, decoded, _) = decode(&encode(&[5, -9], 24_000, &AudioTags::default()));
+        assert_eq!(decoded, vec![5, -9]);
+    }
+
+    #[test]
+    fn long_streams_use_multibyte_frame_numbers() {
+        let mut w = BitWriter::default();
+        write_utf8(&mut w, 0x7F);
+        write_utf8(&mut w, 0x80);
+        write_utf8(&mut w, 0x1_0000);
+        assert_eq!(w.bytes, vec![0x7F, 0xC2, 0x80, 0xF0, 0x90, 0x80, 0x80]);
+    }
+}
@@ -0,0 +1,109 @@
+//! MP3 through LAME, with an ID3v2.4 tag in front.
+
+use mp3lame_encoder::{max_required_buffer_size, Bitrate, Builder, FlushNoGap, MonoPcm, Quality};
+
+use super::AudioTags;
+
+/// LAME needs this much room to flush its last frames.
+const FLUSH_BYTES: usize = 7200;
+
+pub(super) fn encode(pcm: &[i16], sample_rate: u32, tags: &AudioTags) -> Result<Vec<u8>, String> {
+    let mut builder = Builder::new().ok_or("mp3: couldn't initialize LAME")?;
+    builder
+        .set_num_channels(1)
+        .map_err(|e| format!("mp3: {e}"))?;
+    builder
+        .set_sample_rate(sample_rate)
+        .map_err(|e| format!("mp3: {e}"))?;
+    builder
+        .set_brate(Bitrate::Kbps64)
+        .map_err(|e| format!("mp3: {e}"))?;
+    builder
+        .set_quality(Quality::Good)
+        .map_err(|e| format!("mp3: {e}"))?;
+    let mut encoder = builder.build().map_err(|e| format!("mp3: {e}"))?;
+
+    let mut out = id3v2(tags);
+    out.reserve(max_required_buffer_size(pcm.len()) + FLUSH_BYTES);
+    encoder
+        .encode_to_vec(MonoPcm(pcm), &mut out)
+        .map_err(|e| format!("mp3: {e}"))?;
+    encoder
+        .flush_to_vec::<FlushNoGap>(&mut out)
+        .map_err(|e| format!("mp3: {e}"))?;
+    Ok(out)
+}
+
+/// ID3v2.4 tag with UTF-8 `TIT2` (title), `TPE1` (artist), and a
+/// `TXXX:voice` frame. Empty if there's nothing to tag.
+fn id3v2(tags: &AudioTags) -> Vec<u8> {
+    let mut frames = Vec::new();
+    let mut frame = |id: &[u8; 4], body: &[&str]| {
+        let mut content = vec![0x03]; // UTF-8
+        content.extend_from_slice(body.join("\0").as_bytes());
+        frames.extend_from_slice(id);
+        frames.extend_from_slice(&syncsafe(content.len()));
+        frames.extend_from_slice(&[0, 0]); // flags
+        frames.extend_from_slice(&content);
+    };
+    if let Some(title) = &tags.title {
+        frame(b"TIT2", &[title]);
+    }
+    if let Some(artist) = &tags.artist {
+        frame(b"TPE1", &[artist]);
+    }
+    if let Some(voice) = &tags.voice {
+        frame(b"TXXX", &["voice", voice]);
+    }
+    if frames.is_empty() {
+        return frames;
+    }
+
+    let mut tag = b"ID3".to_vec();
+    tag.extend_from_slice(&[4, 0, 0]); // v2.4.0, no flags
+    tag.extend_from_slice(&syncsafe(frames.len()));
+    tag.extend_from_slice(&frames);
+    tag
+}
+
+/// 28-bit size as four 7-bit bytes, so it can't contain an MPEG sync word.
+fn syncsafe(len: usize) -> [u8; 4] {
+    let len = len as u32;
+    [
+        ((len >> 21) & 0x7F) as u8,
+        ((len >> 14) & 0x7F) as u8,
+        ((len >> 7) & 0x7F) as u8,
+        (len & 0x7F) as u8,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn id3_tag_layout() {
+        assert!(id3v2(&AudioTags::default()).is_empty());
+
+        let tags = AudioTags {
+            title: Some("Día".into()),
+            voice: Some("ef_dora".into()),
+            ..AudioTags::default()
+        };
+        let tag = id3v2(&tags);
+        assert_eq!(&tag[..6], b"ID3\x04\x00\x00");
+        assert_eq!(tag[6..10], syncsafe(tag.len() - 10));
+        assert_eq!(&tag[10..14], b"TIT2");
+        assert_eq!(tag[14..18], syncsafe(5));
+        assert_eq!(&tag[20..25], "\x03Día".as_bytes());
+        assert_eq!(&tag[25..29], b"TXXX");
+        assert_eq!(&tag[35..], b"\x03voice\0ef_dora");
+    }
+
+    #[test]
+    fn syncsafe_sizes() {
+        assert_eq!(syncsafe(127), [0, 0, 0, 127]);
+        assert_eq!(syncsafe(128), [0, 0, 1, 0]);
+        assert_eq!(syncsafe(0x0FFF_FFFF), [0x7F; 4]);
+    }
+}
@@ -0,0 +1,136 @@
+//! Ogg page writer (RFC 3533), just enough for single-stream Opus files.
+
+/// Pages are closed once they carry about this many bytes of packets.
+const TARGET_PAGE_BYTES: usize = 4096;
+
+pub(super) struct OggWriter {
+    out: Vec<u8>,
+    serial: u32,
+    sequence: u32,
+    /// Lacing values and data of the page being filled.
+    segments: Vec<u8>,
+    data: Vec<u8>,
+    /// Granule position of the last packet on the page being filled.
+    granule: u64,
+}
+
+impl OggWriter {
+    pub(super) fn new(serial: u32) -> Self {
+        Self {
+            out: Vec::new(),
+            serial,
+            sequence: 0,
+            segments: Vec::new(),
+            data: Vec::new(),
+            granule: 0,
+        }
+    }
+
+    /// Queue a packet whose last sample is at `granule`. Packets never
+    /// span pages, so they must be under 64 KiB.
+    pub(super) fn packet(&mut self, packet: &[u8], granule: u64) {
+        let lacing = packet.len() / 255 + 1;
+        debug_assert!(lacing <= 255, "packet too large for one page");
+        if self.segments.len() + lacing > 255 || self.data.len() >= TARGET_PAGE_BYTES {
+            self.flush(false);
+        }
+        self.segments
+            .extend(std::iter::repeat_n(255, packet.len() / 255));
+        self.segments.push((packet.len() % 255) as u8);
+        self.data.extend_from_slice(packet);
+        self.granule = granule;
+    }
+
+    /// Close the current page, so the next packet starts a new one.
+    pub(super) fn flush(&mut self, last: bool) {
+        if self.segments.is_empty() && !last {
+            return;
+        }
+        let mut header_type = 0;
+        if self.sequence == 0 {
+            header_type |= 0x02; // beginning of stream
+        }
+        if last {
+            header_type |= 0x04; // end of stream
+        }
+
+        let start = self.out.len();
+        self.out.extend_from_slice(b"OggS");
+        self.out.push(0); // version
+        self.out.push(header_type);
+        self.out.extend_from_slice(&self.granule.to_le_bytes());
+        self.out.extend_from_slice(&self.serial.to_le_bytes());
+        self.out.extend_from_slice(&self.sequence.to_le_bytes());
+        self.out.extend_from_slice(&[0; 4]); // CRC, filled in below
+        self.out.push(self.segments.len() as u8);
+        self.out.append(&mut self.segments);
+        self.out.append(&mut self.data);
+        let crc = crc32(&self.out[start..]);
+        self.out[start + 22..start + 26].copy_from_slice(&crc.to_le_bytes());
+        self.sequence += 1;
+    }
+
+    /// Hand over the pages written so far, for streaming them out.
+    pub(super) fn take(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.out)
+    }
+
+    /// Close the last page (marked end-of-stream) and return the file.
+    pub(super) fn finish(mut self) -> Vec<u8> {
+        self.flush(true);
+        self.out
+    }
+}
+
+/// Ogg's CRC-32: polynomial 0x04C11DB7, no reflection, zero init.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0u32;
+    for &byte in data {
+        crc ^= (byte as u32) << 24;
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04C1_1DB7
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc_matches_reference() {
+        // CRC-32/CKSUM of the standard check string, before its final XOR.
+        assert_eq!(crc32(b"123456789"), 0x765E_7680 ^ 0xFFFF_FFFF);
+    }
+
+    #[test]
+    fn pages_carry_flags_lacing_and_checksums() {
+        let mut ogg = OggWriter::new(7);
+        ogg.packet(b"head", 0);
+        ogg.flush(false);
+        ogg.packet(&[1; 300], 960);
+        let out = ogg.finish();
+
+        let first = &out[..28 + 4];
+        assert_eq!(&first[..4], b"OggS");
+        assert_eq!(first[5], 0x02);
+        assert_eq!(u32::from_le_bytes(first[14..18].try_into().unwrap()), 7);
+        assert_eq!(first[26..28], [1, 4]);
+
+        let second = &out[32..];
+        assert_eq!(second[5], 0x04);
+        assert_eq!(u64::from_le_bytes(second[6..14].try_into().unwrap()), 960);
+        assert_eq!(u32::from_le_bytes(second[18..22].try_into().unwrap()), 1);
+        assert_eq!(second[26..29], [2, 255, 45]);
+        assert_eq!(second.len(), 29 + 300);
+
+        let mut zeroed = second.to_vec();
+        zeroed[22..26].fill(0);
+        assert_eq!(second[22..26], crc32(&zeroed).to_le_bytes());
+    }
+}
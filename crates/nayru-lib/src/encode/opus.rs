@@ -0,0 +1,247 @@
+//! Ogg Opus (RFC 7845) through libopus.
+
+use super::ogg::OggWriter;
+use super::{vorbis_comments, AudioTags};
+
+/// Input rates libopus accepts.
+const SAMPLE_RATES: &[u32] = &[8_000, 12_000, 16_000, 24_000, 48_000];
+/// 20 ms frames.
+const FRAMES_PER_SECOND: u32 = 50;
+/// Transparent for mono speech.
+const BITRATE: i32 = 32_000;
+/// Largest packet libopus produces for one frame.
+const MAX_PACKET: usize = 1275;
+/// Ogg bitstream serial number. There's only ever one stream per file.
+const SERIAL: u32 = 0x6E61_7972;
+
+/// libopus behind a buffer: takes any number of samples, returns whole
+/// 20 ms packets.
+struct PacketEncoder {
+    encoder: ::opus::Encoder,
+    sample_rate: u32,
+    frame_len: usize,
+    /// Samples short of a full frame, waiting for the next push.
+    pending: Vec<i16>,
+    /// Encoder delay, in input samples.
+    lookahead: usize,
+}
+
+impl PacketEncoder {
+    fn new(sample_rate: u32) -> Result<Self, String> {
+        if !SAMPLE_RATES.contains(&sample_rate) {
+            return Err(format!("opus can't encode {sample_rate} Hz audio"));
+        }
+        let mut encoder = ::opus::Encoder::new(
+            sample_rate,
+            ::opus::Channels::Mono,
+            ::opus::Application::Voip,
+        )
+        .map_err(opus_error)?;
+        encoder
+            .set_bitrate(::opus::Bitrate::Bits(BITRATE))
+            .map_err(opus_error)?;
+        let lookahead = encoder.get_lookahead().map_err(opus_error)? as usize;
+        Ok(Self {
+            encoder,
+            sample_rate,
+            frame_len: (sample_rate / FRAMES_PER_SECOND) as usize,
+            pending: Vec::new(),
+            lookahead,
+        })
+    }
+
+    /// Granule positions always count 48 kHz samples, whatever the input rate.
+    fn granule_scale(&self) -> u64 {
+        (48_000 / self.sample_rate) as u64
+    }
+
+    fn pre_skip(&self) -> u64 {
+        self.lookahead as u64 * self.granule_scale()
+    }
+
+    /// Encode every whole frame `pcm` completes.
+    fn push(&mut self, pcm: &[i16]) -> Result<Vec<Vec<u8>>, String> {
+        self.pending.extend_from_slice(pcm);
+        let whole = self.pending.len() / self.frame_len * self.frame_len;
+        let mut packet = [0u8; MAX_PACKET];
+        let packets = self.pending[..whole]
+            .chunks(self.frame_len)
+            .map(|frame| {
+                let len = self.encoder.encode(frame, &mut packet).map_err(opus_error)?;
+                Ok(packet[..len].to_vec())
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        self.pending.drain(..whole);
+        Ok(packets)
+    }
+}
+
+fn opus_error(e: ::opus::Error) -> String {
+    format!("opus: {e}")
+}
+
+/// Queue the OpusHead and OpusTags header pages.
+fn write_headers(ogg: &mut OggWriter, encoder: &PacketEncoder, tags: &AudioTags) {
+    let mut head = b"OpusHead".to_vec();
+    head.push(1); // version
+    head.push(1); // channels
+    head.extend_from_slice(&(encoder.pre_skip() as u16).to_le_bytes());
+    head.extend_from_slice(&encoder.sample_rate.to_le_bytes());
+    head.extend_from_slice(&0i16.to_le_bytes()); // output gain
+    head.push(0); // channel mapping family
+    ogg.packet(&head, 0);
+    ogg.flush(false);
+    let mut comments = b"OpusTags".to_vec();
+    comments.extend_from_slice(&vorbis_comments(tags));
+    ogg.packet(&comments, 0);
+    ogg.flush(false);
+}
+
+pub(super) fn encode(pcm: &[i16], sample_rate: u32, tags: &AudioTags) -> Result<Vec<u8>, String> {
+    let mut encoder = PacketEncoder::new(sample_rate)?;
+    let scale = encoder.granule_scale();
+    let end = encoder.pre_skip() + pcm.len() as u64 * scale;
+    let frame_len = encoder.frame_len;
+
+    let mut ogg = OggWriter::new(SERIAL);
+    write_headers(&mut ogg, &encoder, tags);
+
+    // Pad past the encoder's lookahead so the last real samples come out.
+    let padded = (pcm.len() + encoder.lookahead).div_ceil(frame_len).max(1) * frame_len;
+    let mut packets = encoder.push(pcm)?;
+    packets.extend(encoder.push(&vec![0; padded - pcm.len()])?);
+    for (i, packet) in packets.iter().enumerate() {
+        // The final page's granule trims the padding back off.
+        let granule = ((i + 1) * frame_len) as u64 * scale;
+        ogg.packet(packet, granule.min(end));
+    }
+    Ok(ogg.finish())
+}
+
+/// Opus encoding for live audio, as bare packets or a growing Ogg stream.
+pub struct OpusStream {
+    encoder: PacketEncoder,
+    /// Ogg pages around the packets; `None` for bare packets.
+    ogg: Option<OggWriter>,
+    packets_written: u64,
+}
+
+impl OpusStream {
+    /// Bare 20 ms Opus packets, e.g. one per WebSocket message.
+    pub fn packets(sample_rate: u32) -> Result<Self, String> {
+        Ok(Self {
+            encoder: PacketEncoder::new(sample_rate)?,
+            ogg: None,
+            packets_written: 0,
+        })
+    }
+
+    /// An endless Ogg Opus stream. The header pages come out of the first
+    /// [`push`](Self::push).
+    pub fn ogg(sample_rate: u32, tags: &AudioTags) -> Result<Self, String> {
+        let encoder = PacketEncoder::new(sample_rate)?;
+        let mut ogg = OggWriter::new(SERIAL);
+        write_headers(&mut ogg, &encoder, tags);
+        Ok(Self {
+            encoder,
+            ogg: Some(ogg),
+            packets_written: 0,
+        })
+    }
+
+    /// Encode `pcm`. Returns the new packets, or for Ogg a single buffer
+    /// holding every page completed since the last push.
+    pub fn push(&mut self, pcm: &[i16]) -> Result<Vec<Vec<u8>>, String> {
+        let packets = self.encoder.push(pcm)?;
+        let Some(ogg) = &mut self.ogg else {
+            return Ok(packets);
+        };
+        let frame = self.encoder.frame_len as u64 * self.encoder.granule_scale();
+        for packet in &packets {
+            self.packets_written += 1;
+            ogg.packet(packet, self.packets_written * frame);
+        }
+        // Close the page now so listeners hear these packets without waiting
+        // for a full page.
+        ogg.flush(false);
+        let pages = ogg.take();
+        Ok(if pages.is_empty() { Vec::new() } else { vec![pages] })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `(header_type, granule, packets)` for each page.
+    fn pages(mut ogg: &[u8]) -> Vec<(u8, u64, Vec<Vec<u8>>)> {
+        let mut pages = Vec::new();
+        while !ogg.is_empty() {
+            assert_eq!(&ogg[..4], b"OggS");
+            let granule = u64::from_le_bytes(ogg[6..14].try_into().unwrap());
+            let count = ogg[26] as usize;
+            let lacing = &ogg[27..27 + count];
+            let mut data = &ogg[27 + count..];
+            let mut packets = Vec::new();
+            let mut packet = Vec::new();
+            for &len in lacing {
+                packet.extend_from_slice(&data[..len as usize]);
+                data = &data[len as usize..];
+                if len < 255 {
+                    packets.push(std::mem::take(&mut packet));
+                }
+            }
+            pages.push((ogg[5], granule, packets));
+            ogg = data;
+        }
+        pages
+    }
+
+    #[test]
+    fn headers_tags_and_end_granule() {
+        let tags = AudioTags {
+            title: Some("Notes".into()),
+            ..AudioTags::default()
+        };
+        let pcm = vec![0i16; 24_000 + 100];
+        let pages = pages(&encode(&pcm, 24_000, &tags).unwrap());
+
+        let head = &pages[0].2[0];
+        assert_eq!(pages[0].0, 0x02);
+        assert_eq!(&head[..8], b"OpusHead");
+        let pre_skip = u16::from_le_bytes([head[10], head[11]]) as u64;
+        assert_eq!(u32::from_le_bytes(head[12..16].try_into().unwrap()), 24_000);
+
+        assert_eq!(&pages[1].2[0][..8], b"OpusTags");
+        assert!(String::from_utf8_lossy(&pages[1].2[0]).contains("TITLE=Notes"));
+
+        let (flags, granule, _) = pages.last().unwrap();
+        assert_eq!(*flags, 0x04);
+        assert_eq!(*granule, pre_skip + 2 * pcm.len() as u64);
+        let audio_packets: usize = pages[2..].iter().map(|p| p.2.len()).sum();
+        assert!(audio_packets >= 51);
+    }
+
+    #[test]
+    fn live_ogg_stream_pages() {
+        let mut stream = OpusStream::ogg(24_000, &AudioTags::default()).unwrap();
+        let first = stream.push(&[0; 100]).unwrap().concat();
+        let headers = pages(&first);
+        assert_eq!(headers.len(), 2);
+        assert_eq!(&headers[1].2[0][..8], b"OpusTags");
+
+        let more = stream.push(&[0; 2 * 480 - 100]).unwrap().concat();
+        let audio = pages(&more);
+        assert_eq!(audio.len(), 1);
+        assert_eq!(audio[0].2.len(), 2);
+        assert_eq!(audio[0].1, 2 * 960);
+
+        let mut bare = OpusStream::packets(24_000).unwrap();
+        assert_eq!(bare.push(&[0; 480 * 3 + 1]).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn rejects_unsupported_rates() {
+        assert!(encode(&[0; 100], 22_050, &AudioTags::default()).is_err());
+    }
+}
@@ -0,0 +1,283 @@
+//! Recently played clips, for replay, previous-sentence, and rewind.
+//!
+//! Once a clip has played its audio would be gone, so the playback thread
+//! keeps each one (text, word timings, and PCM) in a [`History`] when it
+//! starts, up to [`HISTORY_LIMIT`] of audio. Going back is planned here as
+//! a list of [`Segment`]s — which clips to play again, and from where — that
+//! the playback thread queues ahead of whatever was still to come.
+//!
+//! Everything is relative to the clip playing now, or when nothing is, the
+//! last one played. Clips are numbered in the order they first played; a
+//! clip that's played again keeps its number, so going back from a replay
+//! goes back from where the replay is.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use nayru_core::timing::WordTiming;
+use nayru_core::types::PlaybackPosition;
+
+/// How much audio the history keeps.
+pub const HISTORY_LIMIT: Duration = Duration::from_secs(300);
+
+/// One synthesized clip, as played.
+#[derive(Debug)]
+pub struct PlayedClip {
+    pub position: PlaybackPosition,
+    pub text: String,
+    pub words: Vec<WordTiming>,
+    /// Mono 16-bit PCM, pauses included.
+    pub samples: Vec<i16>,
+    pub sample_rate: u32,
+}
+
+impl PlayedClip {
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.samples.len() as f64 / self.sample_rate as f64)
+    }
+
+    /// Word timings for playing from sample `start`: shifted to match, and
+    /// without the words already past.
+    pub fn words_from(&self, start: usize) -> Vec<WordTiming> {
+        let offset = start as f32 / self.sample_rate as f32;
+        self.words
+            .iter()
+            .filter(|w| w.end > offset)
+            .map(|w| WordTiming {
+                word: w.word.clone(),
+                start: (w.start - offset).max(0.0),
+                end: w.end - offset,
+            })
+            .collect()
+    }
+}
+
+/// Play history clip `id` again from sample `start`.
+#[derive(Debug, Clone)]
+pub struct Segment {
+    pub id: u64,
+    pub clip: Arc<PlayedClip>,
+    pub start: usize,
+}
+
+/// Played clips, oldest first, trimmed to a total duration.
+pub struct History {
+    clips: VecDeque<(u64, Arc<PlayedClip>)>,
+    next_id: u64,
+    total: Duration,
+    limit: Duration,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(HISTORY_LIMIT)
+    }
+}
+
+impl History {
+    pub fn new(limit: Duration) -> Self {
+        Self {
+            clips: VecDeque::new(),
+            next_id: 0,
+            total: Duration::ZERO,
+            limit,
+        }
+    }
+
+    /// Add a clip that just started playing. Returns its number. The oldest
+    /// clips go once the total passes the limit; the newest always stays.
+    pub fn push(&mut self, clip: Arc<PlayedClip>) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.total += clip.duration();
+        self.clips.push_back((id, clip));
+        while self.total > self.limit && self.clips.len() > 1 {
+            let (_, oldest) = self.clips.pop_front().unwrap();
+            self.total -= oldest.duration();
+        }
+        id
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clips.is_empty()
+    }
+
+    /// The whole sentence `current` belongs to (the last one played if
+    /// `None`), up to and including `current`.
+    pub fn replay_last(&self, current: Option<u64>) -> Vec<Segment> {
+        let Some(end) = self.current_index(current) else {
+            return Vec::new();
+        };
+        self.segments(self.sentence_start(end), 0, end)
+    }
+
+    /// The sentence before `current`'s, then `current`'s own. With nothing
+    /// playing, the last sentence played (the one before whatever's next).
+    pub fn previous(&self, current: Option<u64>) -> Vec<Segment> {
+        let Some(end) = self.current_index(current) else {
+            return Vec::new();
+        };
+        let start = self.sentence_start(end);
+        let start = match current {
+            Some(_) if start > 0 => self.sentence_start(start - 1),
+            _ => start,
+        };
+        self.segments(start, 0, end)
+    }
+
+    /// Everything from `by` before the playback point through the clip at
+    /// that point. `current` is the clip playing and how many of its samples
+    /// have played; with nothing playing, the point is the end of the last
+    /// clip. Stops at the oldest clip kept.
+    pub fn rewind(&self, by: Duration, current: Option<(u64, usize)>) -> Vec<Segment> {
+        let Some(end) = self.current_index(current.map(|(id, _)| id)) else {
+            return Vec::new();
+        };
+        let mut index = end;
+        let mut offset = match current {
+            Some((_, played)) => played.min(self.clips[end].1.samples.len()),
+            None => self.clips[end].1.samples.len(),
+        };
+        let mut left = by.as_secs_f64();
+        loop {
+            let clip = &self.clips[index].1;
+            let back = left * clip.sample_rate as f64;
+            if back <= offset as f64 {
+                offset -= (back.round() as usize).min(offset);
+                break;
+            }
+            left -= offset as f64 / clip.sample_rate as f64;
+            if index == 0 {
+                offset = 0;
+                break;
+            }
+            index -= 1;
+            offset = self.clips[index].1.samples.len();
+        }
+        self.segments(index, offset, end)
+    }
+
+    /// Index of clip `id`, or of the newest clip for `None` (or an `id`
+    /// that has been trimmed away).
+    fn current_index(&self, id: Option<u64>) -> Option<usize> {
+        id.and_then(|id| self.clips.iter().position(|(i, _)| *i == id))
+            .or_else(|| self.clips.len().checked_sub(1))
+    }
+
+    /// First index of the run of clips ending at `index` that share its
+    /// sentence (a long sentence plays as several clips).
+    fn sentence_start(&self, index: usize) -> usize {
+        let position = self.clips[index].1.position;
+        let mut start = index;
+        while start > 0 && self.clips[start - 1].1.position == position {
+            start -= 1;
+        }
+        start
+    }
+
+    fn segments(&self, first: usize, offset: usize, last: usize) -> Vec<Segment> {
+        (first..=last)
+            .map(|i| {
+                let (id, clip) = &self.clips[i];
+                Segment {
+                    id: *id,
+                    clip: clip.clone(),
+                    start: if i == first { offset } else { 0 },
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A one-second clip at 1 kHz.
+    fn clip(utterance_id: u64, sentence_index: usize) -> Arc<PlayedClip> {
+        Arc::new(PlayedClip {
+            position: PlaybackPosition {
+                utterance_id,
+                sentence_index,
+            },
+            text: format!("{utterance_id}.{sentence_index}"),
+            words: vec![
+                WordTiming {
+                    word: "a".into(),
+                    start: 0.1,
+                    end: 0.4,
+                },
+                WordTiming {
+                    word: "b".into(),
+                    start: 0.5,
+                    end: 0.9,
+                },
+            ],
+            samples: vec![0; 1_000],
+            sample_rate: 1_000,
+        })
+    }
+
+    fn plan(segments: &[Segment]) -> Vec<(&str, usize)> {
+        segments.iter().map(|s| (s.clip.text.as_str(), s.start)).collect()
+    }
+
+    #[test]
+    fn trims_to_limit_but_keeps_newest() {
+        let mut history = History::new(Duration::from_millis(2_500));
+        for i in 0..4 {
+            history.push(clip(1, i));
+        }
+        assert_eq!(
+            plan(&history.rewind(Duration::from_secs(60), None)),
+            vec![("1.2", 0), ("1.3", 0)]
+        );
+
+        let mut tiny = History::new(Duration::from_millis(10));
+        tiny.push(clip(1, 0));
+        assert!(!tiny.is_empty());
+    }
+
+    #[test]
+    fn replay_and_previous_go_by_sentence() {
+        let mut history = History::default();
+        assert!(history.replay_last(None).is_empty());
+        history.push(clip(1, 0));
+        // Sentence 1 was long and played as two clips.
+        let first_half = history.push(clip(1, 1));
+        let second_half = history.push(clip(1, 1));
+
+        assert_eq!(plan(&history.replay_last(None)), vec![("1.1", 0), ("1.1", 0)]);
+        assert_eq!(plan(&history.replay_last(Some(first_half))), vec![("1.1", 0)]);
+        assert_eq!(
+            plan(&history.previous(Some(second_half))),
+            vec![("1.0", 0), ("1.1", 0), ("1.1", 0)]
+        );
+        // Nothing playing: previous is the sentence that just finished.
+        assert_eq!(plan(&history.previous(None)), vec![("1.1", 0), ("1.1", 0)]);
+        // Already at the first sentence: start it over.
+        assert_eq!(plan(&history.previous(Some(0))), vec![("1.0", 0)]);
+    }
+
+    #[test]
+    fn rewind_crosses_clips_from_the_playback_point() {
+        let mut history = History::default();
+        history.push(clip(1, 0));
+        let current = history.push(clip(1, 1));
+
+        let back = |ms, current| history.rewind(Duration::from_millis(ms), current);
+        assert_eq!(plan(&back(300, Some((current, 800)))), vec![("1.1", 500)]);
+        assert_eq!(plan(&back(1_000, Some((current, 800)))), vec![("1.0", 800), ("1.1", 0)]);
+        assert_eq!(plan(&back(60_000, None)), vec![("1.0", 0), ("1.1", 0)]);
+    }
+
+    #[test]
+    fn words_shift_with_the_start() {
+        let words = clip(1, 0).words_from(450);
+        assert_eq!(words.len(), 1);
+        assert_eq!(words[0].word, "b");
+        assert!((words[0].start - 0.05).abs() < 1e-6);
+        assert!((words[0].end - 0.45).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,239 @@
+//! Remote synthesis over an OpenAI-compatible speech API.
+//!
+//! Posts each clip to `{base_url}/v1/audio/speech` with `response_format:
+//! "pcm"` (16-bit little-endian mono at 24 kHz, per the OpenAI spec) — works
+//! against Kokoros, Kokoro-FastAPI, or another nayru's `/v1/audio/speech`.
+//!
+//! The API has no word timings, so they're estimated by spreading the words
+//! across the clip by length.
+
+use std::time::Instant;
+
+use futures_util::future::BoxFuture;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use tracing::{debug, warn};
+
+use nayru_core::timing::align_words;
+
+use crate::synth::{Synthesis, Synthesizer};
+
+/// Sample rate of OpenAI `pcm` responses.
+const PCM_SAMPLE_RATE: u32 = 24_000;
+
+/// Model name sent when none is configured.
+pub const DEFAULT_MODEL: &str = "kokoro";
+
+/// [`Synthesizer`] backed by a remote OpenAI-compatible server.
+#[derive(Debug, Clone)]
+pub struct HttpSynth {
+    client: reqwest::Client,
+    base_url: String,
+    model: String,
+    api_key: Option<String>,
+    /// Voices the server offers; empty = unknown, so any name is accepted.
+    voices: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct VoicesResponse {
+    voices: Vec<String>,
+}
+
+/// An entry of nayru's own `GET /voices`; only the name is needed.
+#[derive(Deserialize)]
+struct NamedVoice {
+    name: String,
+}
+
+impl HttpSynth {
+    /// A backend for the server at `base_url` (e.g. `http://localhost:8880`).
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            model: DEFAULT_MODEL.into(),
+            api_key: None,
+            voices: Vec::new(),
+        }
+    }
+
+    /// Model name to request (ignored by most Kokoro servers).
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Bearer token for servers that require one.
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Restrict requests to these voices.
+    pub fn with_voices<I, S>(mut self, voices: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.voices = voices.into_iter().map(Into::into).collect();
+        self.voices.sort();
+        self.voices.dedup();
+        self
+    }
+
+    /// Ask the server for its voices: `GET /v1/audio/voices`, as served by
+    /// Kokoro-FastAPI, falling back to another nayru's `GET /voices`. On
+    /// failure the list stays as it was.
+    pub async fn discover_voices(mut self) -> Self {
+        let result = match self.get_json::<VoicesResponse>("/v1/audio/voices").await {
+            Ok(list) => Ok(list.voices),
+            Err(first) => self
+                .get_json::<Vec<NamedVoice>>("/voices")
+                .await
+                .map(|list| list.into_iter().map(|voice| voice.name).collect())
+                .map_err(|e| format!("{first}; {e}")),
+        };
+        match result {
+            Ok(voices) => {
+                debug!("http synth: {} voices at {}", voices.len(), self.base_url);
+                self = self.with_voices(voices);
+            }
+            Err(e) => warn!("http synth: couldn't list voices at {}: {e}", self.base_url),
+        }
+        self
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T, String> {
+        let url = format!("{}{path}", self.base_url);
+        let resp = self
+            .authorized(self.client.get(&url))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| e.to_string())?;
+        resp.json::<T>().await.map_err(|e| format!("{url}: {e}"))
+    }
+
+    fn authorized(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+
+    async fn request(&self, text: &str, voice: &str, speed: f32) -> Result<Synthesis, String> {
+        self.validate_voice(voice)?;
+
+        let started = Instant::now();
+        let resp = self
+            .authorized(self.client.post(format!("{}/v1/audio/speech", self.base_url)))
+            .json(&serde_json::json!({
+                "model": self.model,
+                "input": text,
+                "voice": voice,
+                "speed": speed,
+                "response_format": "pcm",
+            }))
+            .send()
+            .await
+            .map_err(|e| format!("speech request failed: {e}"))?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(format!("speech server returned {status}: {body}"));
+        }
+        let bytes = resp
+            .bytes()
+            .await
+            .map_err(|e| format!("speech response: {e}"))?;
+
+        let samples = pcm_to_f32(&bytes);
+        let secs = samples.len() as f32 / PCM_SAMPLE_RATE as f32;
+        Ok(Synthesis {
+            words: align_words(text, &[], &[1.0], secs),
+            samples,
+            sample_rate: PCM_SAMPLE_RATE,
+            took: started.elapsed(),
+        })
+    }
+}
+
+impl Synthesizer for HttpSynth {
+    fn synth<'a>(
+        &'a self,
+        text: &'a str,
+        voice: &'a str,
+        speed: f32,
+        _language: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Synthesis, String>> {
+        Box::pin(self.request(text, voice, speed))
+    }
+
+    fn voices(&self) -> Vec<String> {
+        self.voices.clone()
+    }
+
+    fn validate_voice(&self, voice: &str) -> Result<(), String> {
+        if self.voices.is_empty() || self.voices.iter().any(|v| v == voice) {
+            return Ok(());
+        }
+        Err(crate::synth::unknown_voice(voice, &self.voices))
+    }
+}
+
+/// 16-bit little-endian PCM to f32 samples. A trailing odd byte is dropped.
+fn pcm_to_f32(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pcm_decoding() {
+        let bytes = [0x00, 0x00, 0xff, 0x7f, 0x00, 0x80, 0x01];
+        assert_eq!(pcm_to_f32(&bytes), vec![0.0, 32767.0 / 32768.0, -1.0]);
+    }
+
+    #[test]
+    fn base_url_is_normalized() {
+        assert_eq!(HttpSynth::new("http://host:8880/").base_url, "http://host:8880");
+    }
+
+    #[test]
+    fn unknown_voice_list_accepts_anything() {
+        let open = HttpSynth::new("http://host");
+        assert!(open.validate_voice("anything").is_ok());
+
+        let listed = HttpSynth::new("http://host").with_voices(["af_heart"]);
+        assert!(listed.validate_voice("af_heart").is_ok());
+        assert!(listed.validate_voice("other").is_err());
+    }
+
+    #[tokio::test]
+    async fn discovers_voices_from_another_nayru() {
+        use std::sync::Arc;
+
+        use nayru_core::types::TtsConfig;
+
+        use crate::audio_sink::NullSink;
+        use crate::mock_synth::MockSynth;
+        use crate::tts::TtsEngine;
+
+        let engine = TtsEngine::with_sink(TtsConfig::default(), Arc::new(MockSynth::default()), || {
+            Box::new(NullSink::unthrottled())
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(axum::serve(listener, crate::server::router(engine)).into_future());
+
+        let synth = HttpSynth::new(url).discover_voices().await;
+        assert_eq!(synth.voices(), ["af_heart", "am_adam", "bf_emma"]);
+    }
+}
@@ -1,41 +1,62 @@
 //! Direct Kokoro ONNX inference with espeak-ng phonemization.
 //!
 //! Bypasses kokoro-tts's built-in minimal espeak dictionary (which produces
-//! low-quality English phonemes) by calling system espeak-ng for IPA conversion,
-//! then running the ONNX model directly via `ort`.
+//! low-quality English phonemes) by phonemizing with system espeak-ng through
+//! a [`Phonemizer`], then running the ONNX model directly via `ort`.
+//!
+//! Word timings come from the model's `duration` output when the graph has
+//! one; otherwise every token is assumed to take equal time.
 
-use std::collections::HashMap;
-use std::path::Path;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use std::time::SystemTime;
 
+use futures_util::future::BoxFuture;
 use kokoro_tts::get_token_ids;
 use ndarray::Array;
 use ort::inputs;
 use ort::session::{RunOptions, Session};
-use ort::value::TensorRef;
+use ort::value::{DynValue, TensorRef};
 use tokio::sync::Mutex;
-use tracing::{debug, warn};
+use tracing::debug;
+
+use nayru_core::lang::{language_for_voice, DEFAULT_LANGUAGE};
+use nayru_core::timing::align_words;
+use nayru_core::types::VoiceKind;
+
+use crate::phonemizer::{
+    check_espeak, CachedPhonemizer, EspeakWorker, Phonemizer, DEFAULT_CACHE_CAPACITY,
+};
+use crate::synth::{SpeechPart, Synthesis, Synthesizer};
+use crate::synth_cache::file_fingerprint;
+use crate::voice_pack::{decode_voices, VoiceLibrary};
 
-/// Voice style pack: indexed by [sequence_length][style_index][feature_dim].
-type VoicePack = Vec<Vec<Vec<f32>>>;
+/// Kokoro output sample rate.
+pub const SAMPLE_RATE: u32 = 24_000;
 
 /// In-process Kokoro synthesizer with espeak-ng phonemization.
 pub struct KokoroSynth {
     model: Arc<Mutex<Session>>,
-    voices: Arc<HashMap<String, VoicePack>>,
+    voices: VoiceLibrary,
+    /// Model and voices files, for synthesis cache keys.
+    files_id: String,
+    phonemizer: Arc<dyn Phonemizer>,
 }
 
 impl KokoroSynth {
     /// Load the ONNX model and voices file.
+    ///
+    /// Fails if espeak-ng isn't installed. Phonemization goes through a
+    /// persistent espeak-ng worker with an LRU cache in front.
     pub async fn new(model_path: &Path, voices_path: &Path) -> Result<Self, String> {
+        check_espeak().await?;
+
         let voices_data = tokio::fs::read(voices_path)
             .await
             .map_err(|e| format!("failed to read voices: {e}"))?;
 
-        let (voices, _): (HashMap<String, VoicePack>, _) =
-            bincode::decode_from_slice(&voices_data, bincode::config::standard())
-                .map_err(|e| format!("failed to decode voices: {e}"))?;
+        let voices = decode_voices(&voices_data)?;
 
         let model = Session::builder()
             .map_err(|e| format!("ort session builder: {e}"))?
@@ -44,17 +65,97 @@ impl KokoroSynth {
 
         Ok(Self {
             model: Arc::new(Mutex::new(model)),
-            voices: Arc::new(voices),
+            voices: VoiceLibrary::new(voices),
+            files_id: format!(
+                "kokoro:{}:{}",
+                file_fingerprint(model_path),
+                file_fingerprint(voices_path)
+            ),
+            phonemizer: Arc::new(CachedPhonemizer::new(
+                EspeakWorker::new(),
+                DEFAULT_CACHE_CAPACITY,
+            )),
         })
     }
 
+    /// Use `phonemizer` instead of the default espeak-ng worker.
+    pub fn with_phonemizer(mut self, phonemizer: Arc<dyn Phonemizer>) -> Self {
+        self.phonemizer = phonemizer;
+        self
+    }
+
+    /// Load user voices from `dir` (see [`VoiceLibrary::load_dir`]); saved
+    /// voices go there too. Returns the number loaded.
+    pub fn load_user_voices(&self, dir: &Path) -> Result<usize, String> {
+        self.voices.load_dir(dir)
+    }
+
+    /// Save a voice or blend (`af_heart:0.6+bf_emma:0.4`) as user voice `name`.
+    pub fn save_voice(&self, name: &str, blend: &str) -> Result<PathBuf, String> {
+        self.voices.save(name, blend)
+    }
+
+    /// Names of all stock and user voices, sorted.
+    pub fn voice_names(&self) -> Vec<String> {
+        self.voices.names()
+    }
+
+    /// Check that `voice_name` is a loaded voice or a blend of them.
+    pub fn validate_voice(&self, voice_name: &str) -> Result<(), String> {
+        self.voices.get(voice_name).map(|_| ())
+    }
+
     /// Synthesize text to f32 audio samples at 24kHz.
-    pub async fn synth(&self, text: &str, voice_name: &str, speed: f32) -> Result<(Vec<f32>, Duration), String> {
-        let ipa = text_to_ipa(text).await?;
-        debug!("phonemes: {}", ipa);
+    ///
+    /// `language` is an espeak-ng voice (e.g. `en-gb`); `None` means the
+    /// voice's own language (`bf_emma` → `en-gb`), or `en-us`.
+    pub async fn synth(
+        &self,
+        text: &str,
+        voice_name: &str,
+        speed: f32,
+        language: Option<&str>,
+    ) -> Result<Synthesis, String> {
+        let parts = [SpeechPart::Text(text.to_string())];
+        self.synth_parts(&parts, text, voice_name, speed, language).await
+    }
 
-        let pack = self.voices.get(voice_name)
-            .ok_or_else(|| format!("voice '{}' not found in voices.bin", voice_name))?;
+    /// Synthesize a clip made of text and IPA parts, joined with spaces.
+    ///
+    /// Only [`SpeechPart::Text`] goes through espeak-ng. `text` is what the
+    /// clip reads as, used for word timings.
+    pub async fn synth_parts(
+        &self,
+        parts: &[SpeechPart],
+        text: &str,
+        voice_name: &str,
+        speed: f32,
+        language: Option<&str>,
+    ) -> Result<Synthesis, String> {
+        let pack = self.voices.get(voice_name)?;
+        if pack.is_empty() {
+            return Err(format!("voice '{voice_name}' has an empty pack"));
+        }
+
+        let language = language
+            .or_else(|| language_for_voice(voice_name))
+            .unwrap_or(DEFAULT_LANGUAGE);
+        let mut phonemized = Vec::with_capacity(parts.len());
+        for part in parts {
+            match part {
+                // Punctuation left between IPA parts has nothing to phonemize.
+                SpeechPart::Text(t) if !t.chars().any(char::is_alphanumeric) => {}
+                SpeechPart::Text(t) => {
+                    phonemized.push(self.phonemizer.phonemize(t, language).await?)
+                }
+                SpeechPart::Ipa(ipa) => phonemized.push(ipa.clone()),
+            }
+        }
+        let ipa = phonemized.join(" ");
+        if ipa.is_empty() {
+            return Err("nothing to phonemize".to_string());
+        }
+        debug!("phonemes: {}", ipa);
 
         let tokens = get_token_ids(&ipa, false);
         let seq_len = tokens.len();
@@ -103,33 +204,111 @@ impl KokoroSynth {
             .try_extract_tensor::<f32>()
             .map_err(|e| format!("extract audio: {e}"))?;
 
-        Ok((audio.to_owned(), elapsed))
+        let durations = output
+            .get("duration")
+            .and_then(extract_durations)
+            .filter(|d| d.len() == seq_len)
+            .unwrap_or_else(|| vec![1.0; seq_len]);
+        let words = align_words(
+            text,
+            &phoneme_word_ranges(&ipa, seq_len),
+            &durations,
+            audio.len() as f32 / SAMPLE_RATE as f32,
+        );
+
+        Ok(Synthesis {
+            samples: audio.to_owned(),
+            sample_rate: SAMPLE_RATE,
+            took: elapsed,
+            words,
+        })
     }
 }
 
-/// Convert text to IPA using system espeak-ng.
-async fn text_to_ipa(text: &str) -> Result<String, String> {
-    let output = tokio::process::Command::new("espeak-ng")
-        .args(["--ipa", "-q", "-v", "en-us", text])
-        .output()
-        .await
-        .map_err(|e| format!("espeak-ng failed to execute: {e}"))?;
+impl Synthesizer for KokoroSynth {
+    fn synth<'a>(
+        &'a self,
+        text: &'a str,
+        voice: &'a str,
+        speed: f32,
+        language: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Synthesis, String>> {
+        Box::pin(KokoroSynth::synth(self, text, voice, speed, language))
+    }
+
+    fn synth_parts<'a>(
+        &'a self,
+        parts: &'a [SpeechPart],
+        text: &'a str,
+        voice: &'a str,
+        speed: f32,
+        language: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Synthesis, String>> {
+        Box::pin(KokoroSynth::synth_parts(
+            self, parts, text, voice, speed, language,
+        ))
+    }
+
+    fn voices(&self) -> Vec<String> {
+        self.voice_names()
+    }
+
+    fn validate_voice(&self, voice: &str) -> Result<(), String> {
+        KokoroSynth::validate_voice(self, voice)
+    }
+
+    fn voice_kind(&self, voice: &str) -> VoiceKind {
+        self.voices.kind(voice)
+    }
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        warn!("espeak-ng stderr: {}", stderr);
+    fn save_voice(&self, name: &str, blend: &str) -> Result<(), String> {
+        KokoroSynth::save_voice(self, name, blend).map(|_| ())
     }
 
-    // Replace newlines with spaces (espeak-ng outputs one line per clause)
-    let ipa = String::from_utf8_lossy(&output.stdout)
-        .replace('\n', " ")
-        .split_whitespace()
-        .collect::<Vec<_>>()
-        .join(" ");
+    fn check_save_voice(&self, name: &str, blend: &str) -> Result<(), String> {
+        self.voices.check_save(name, blend)
+    }
 
-    if ipa.is_empty() {
-        return Err("espeak-ng returned empty output".to_string());
+    fn cache_id(&self, voice: &str) -> Option<String> {
+        let voice_id = self.voices.content_id(voice).ok()?;
+        Some(format!("{}:{voice_id}", self.files_id))
     }
+}
 
-    Ok(ipa)
+/// Per-token durations from the model's `duration` output (int or float).
+fn extract_durations(value: &DynValue) -> Option<Vec<f32>> {
+    if let Ok((_, d)) = value.try_extract_tensor::<i64>() {
+        return Some(d.iter().map(|&v| v as f32).collect());
+    }
+    value
+        .try_extract_tensor::<f32>()
+        .ok()
+        .map(|(_, d)| d.to_vec())
+}
+
+/// Token range of each space-separated IPA word within a sequence of
+/// `total_tokens` tokens.
+///
+/// Assumes `get_token_ids` maps the IPA string symbol by symbol, spaces
+/// included, optionally wrapped in a pad token at each end. Returns an empty
+/// list if the counts don't add up, which makes [`align_words`] fall back to
+/// spreading words across the whole clip.
+fn phoneme_word_ranges(ipa: &str, total_tokens: usize) -> Vec<Range<usize>> {
+    let counts: Vec<usize> = ipa
+        .split(' ')
+        .map(|w| get_token_ids(w, false).iter().filter(|&&t| t != 0).count())
+        .collect();
+    let body = counts.iter().sum::<usize>() + counts.len().saturating_sub(1);
+    let mut pos = match total_tokens.checked_sub(body) {
+        Some(0) => 0,
+        Some(2) => 1,
+        _ => return Vec::new(),
+    };
+
+    let mut ranges = Vec::with_capacity(counts.len());
+    for count in counts {
+        ranges.push(pos..pos + count);
+        pos += count + 1;
+    }
+    ranges
 }
@@ -0,0 +1,621 @@
+//! User pronunciation lexicon.
+//!
+//! Maps words, phrases, or regexes to IPA or to a respelling, for names and
+//! acronyms espeak-ng gets wrong. Entries are applied to each clip's text
+//! before phonemization: a respelling replaces the matched text, IPA becomes
+//! a [`SpeechPart::Ipa`] passed straight to the model.
+//!
+//! The lexicon lives in a JSON file (by default `lexicon.json` in the nayru
+//! config dir) holding an array of entries:
+//!
+//! ```json
+//! [
+//!   { "pattern": "nayru", "ipa": "nˈaɪɹuː" },
+//!   { "pattern": "SQL", "respell": "sequel", "case_sensitive": true },
+//!   { "pattern": "v(\\d+)", "regex": true, "respell": "version $1" }
+//! ]
+//! ```
+//!
+//! Matching is case-insensitive and whole-word unless an entry says
+//! otherwise. When several entries match, the earliest match in the text
+//! wins, then the entry listed first. [`Lexicon::watch`] reloads the file
+//! when it changes on disk.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use futures_util::future::BoxFuture;
+use regex::{Captures, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use nayru_core::types::{CacheStats, VoiceKind};
+
+use crate::synth::{SpeechPart, Synthesis, Synthesizer};
+
+/// File name of the lexicon inside the config dir.
+const FILE_NAME: &str = "lexicon.json";
+
+/// How a matched word should be spoken.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Pronunciation {
+    /// IPA passed to the model as-is.
+    Ipa(String),
+    /// Text spoken instead of the match. For regex entries, `$1`/`${name}`
+    /// expand to capture groups.
+    Respell(String),
+}
+
+/// One lexicon rule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LexiconEntry {
+    /// Word or phrase to match, or a regex when `regex` is set. Unique
+    /// within a lexicon.
+    pub pattern: String,
+    #[serde(flatten)]
+    pub pronunciation: Pronunciation,
+    /// Treat `pattern` as a regular expression.
+    #[serde(default)]
+    pub regex: bool,
+    /// Match case exactly (default: ignore case).
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// Only match where the match isn't part of a longer word (default: on).
+    #[serde(default = "default_whole_word")]
+    pub whole_word: bool,
+}
+
+fn default_whole_word() -> bool {
+    true
+}
+
+/// Shared, hot-reloadable set of [`LexiconEntry`]s. Clones share state.
+#[derive(Clone, Default)]
+pub struct Lexicon {
+    inner: Arc<RwLock<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    /// Backing file; `None` keeps the lexicon in memory only.
+    path: Option<PathBuf>,
+    /// Modification time of `path` when last read or written.
+    modified: Option<SystemTime>,
+    entries: Vec<LexiconEntry>,
+    rules: Vec<Rule>,
+}
+
+/// A compiled entry.
+struct Rule {
+    regex: Regex,
+    whole_word: bool,
+    expand: bool,
+    pronunciation: Pronunciation,
+}
+
+impl Lexicon {
+    /// `lexicon.json` in the nayru config dir (e.g. `~/.config/nayru`).
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("nayru").join(FILE_NAME))
+    }
+
+    /// Back the lexicon with `path` and load it. A missing file is an empty
+    /// lexicon; it's created on the first [`insert`](Self::insert). On error
+    /// the lexicon is left unchanged. Returns the number of entries.
+    pub fn load(&self, path: impl Into<PathBuf>) -> Result<usize, String> {
+        let path = path.into();
+        let (entries, modified) = read_file(&path)?;
+        let rules = compile_all(&entries)?;
+        let count = entries.len();
+
+        let mut inner = self.inner.write().unwrap();
+        inner.path = Some(path);
+        inner.modified = modified;
+        inner.entries = entries;
+        inner.rules = rules;
+        Ok(count)
+    }
+
+    /// The backing file, if any.
+    pub fn path(&self) -> Option<PathBuf> {
+        self.inner.read().unwrap().path.clone()
+    }
+
+    /// Reload the backing file if its modification time changed.
+    pub fn reload_if_changed(&self) -> Result<bool, String> {
+        let (path, seen) = {
+            let inner = self.inner.read().unwrap();
+            match &inner.path {
+                Some(path) => (path.clone(), inner.modified),
+                None => return Ok(false),
+            }
+        };
+        if modified_time(&path) == seen {
+            return Ok(false);
+        }
+        let count = self.load(path)?;
+        info!("lexicon: reloaded, {count} entries");
+        Ok(true)
+    }
+
+    /// `synth` with this lexicon applied to every clip, for synthesizing
+    /// outside a [`TtsEngine`](crate::tts::TtsEngine) (which applies it itself).
+    pub fn wrap(&self, synth: Arc<dyn Synthesizer>) -> Arc<dyn Synthesizer> {
+        Arc::new(LexiconSynth {
+            inner: synth,
+            lexicon: self.clone(),
+        })
+    }
+
+    /// Poll the backing file every `interval` and reload it when it changes.
+    /// A file that fails to parse is logged and the old entries are kept.
+    pub fn watch(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let lexicon = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = lexicon.reload_if_changed() {
+                    warn!("lexicon: {e}");
+                    // Don't retry the same broken file every tick.
+                    let mut inner = lexicon.inner.write().unwrap();
+                    inner.modified = inner.path.as_deref().and_then(modified_time);
+                }
+            }
+        })
+    }
+
+    /// All entries, in match-priority order.
+    pub fn entries(&self) -> Vec<LexiconEntry> {
+        self.inner.read().unwrap().entries.clone()
+    }
+
+    /// Fails if `entry` can't be inserted: its pattern is empty or an
+    /// invalid regex.
+    pub fn check(entry: &LexiconEntry) -> Result<(), String> {
+        compile(entry).map(|_| ())
+    }
+
+    /// Add `entry`, replacing any entry with the same pattern, and save.
+    /// Fails as [`check`](Self::check) does, or if the file can't be
+    /// written; either way the lexicon is left unchanged.
+    pub fn insert(&self, entry: LexiconEntry) -> Result<(), String> {
+        let rule = compile(&entry)?;
+        let mut inner = self.inner.write().unwrap();
+        let mut entries = inner.entries.clone();
+        let found = entries.iter().position(|e| e.pattern == entry.pattern);
+        match found {
+            Some(i) => entries[i] = entry,
+            None => entries.push(entry),
+        }
+        inner.save(entries)?;
+        match found {
+            Some(i) => inner.rules[i] = rule,
+            None => inner.rules.push(rule),
+        }
+        Ok(())
+    }
+
+    /// Remove the entry with `pattern` and save. Returns whether it existed.
+    /// If the file can't be written, the lexicon is left unchanged.
+    pub fn remove(&self, pattern: &str) -> Result<bool, String> {
+        let mut inner = self.inner.write().unwrap();
+        let Some(i) = inner.entries.iter().position(|e| e.pattern == pattern) else {
+            return Ok(false);
+        };
+        let mut entries = inner.entries.clone();
+        entries.remove(i);
+        inner.save(entries)?;
+        inner.rules.remove(i);
+        Ok(true)
+    }
+
+    /// Apply the lexicon to the text parts of a clip. IPA parts pass through.
+    pub fn apply(&self, parts: &[SpeechPart]) -> Vec<SpeechPart> {
+        let inner = self.inner.read().unwrap();
+        let mut out = Vec::with_capacity(parts.len());
+        for part in parts {
+            match part {
+                SpeechPart::Text(text) => apply_rules(&inner.rules, text, &mut out),
+                SpeechPart::Ipa(_) => out.push(part.clone()),
+            }
+        }
+        out
+    }
+
+    fn is_empty(&self) -> bool {
+        self.inner.read().unwrap().rules.is_empty()
+    }
+}
+
+impl Inner {
+    /// Write `entries` to the backing file, if there is one, then make
+    /// them the lexicon's. On error nothing changes; the caller updates
+    /// `rules` to match on success.
+    fn save(&mut self, entries: Vec<LexiconEntry>) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            self.entries = entries;
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("create {}: {e}", dir.display()))?;
+        }
+        let json = serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?;
+        // Write then rename so the watcher never reads a half-written file.
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, json + "\n").map_err(|e| format!("write {}: {e}", tmp.display()))?;
+        std::fs::rename(&tmp, path).map_err(|e| format!("write {}: {e}", path.display()))?;
+        self.modified = modified_time(path);
+        self.entries = entries;
+        debug!("lexicon: saved {} entries", self.entries.len());
+        Ok(())
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn read_file(path: &Path) -> Result<(Vec<LexiconEntry>, Option<SystemTime>), String> {
+    let modified = modified_time(path);
+    let data = match std::fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((Vec::new(), None)),
+        Err(e) => return Err(format!("read {}: {e}", path.display())),
+    };
+    if data.trim().is_empty() {
+        return Ok((Vec::new(), modified));
+    }
+    let entries = serde_json::from_str(&data)
+        .map_err(|e| format!("invalid lexicon {}: {e}", path.display()))?;
+    Ok((entries, modified))
+}
+
+fn compile_all(entries: &[LexiconEntry]) -> Result<Vec<Rule>, String> {
+    entries.iter().map(compile).collect()
+}
+
+fn compile(entry: &LexiconEntry) -> Result<Rule, String> {
+    if entry.pattern.is_empty() {
+        return Err("lexicon pattern is empty".to_string());
+    }
+    let source = if entry.regex {
+        entry.pattern.clone()
+    } else {
+        regex::escape(&entry.pattern)
+    };
+    let regex = RegexBuilder::new(&source)
+        .case_insensitive(!entry.case_sensitive)
+        .build()
+        .map_err(|e| format!("invalid lexicon pattern '{}': {e}", entry.pattern))?;
+    Ok(Rule {
+        regex,
+        whole_word: entry.whole_word,
+        expand: entry.regex,
+        pronunciation: entry.pronunciation.clone(),
+    })
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl Rule {
+    /// First non-empty match at or after `from` that satisfies `whole_word`.
+    ///
+    /// Word boundaries are checked by hand rather than with `\b`, so patterns
+    /// that start or end with punctuation (`C++`, `.NET`) still match.
+    fn find<'t>(&self, text: &'t str, mut from: usize) -> Option<Captures<'t>> {
+        while from <= text.len() {
+            let caps = self.regex.captures_at(text, from)?;
+            let m = caps.get(0)?;
+            let bounded = !self.whole_word
+                || (!text[..m.start()].chars().next_back().is_some_and(is_word_char)
+                    && !text[m.end()..].chars().next().is_some_and(is_word_char));
+            if !m.is_empty() && bounded {
+                return Some(caps);
+            }
+            from = m.start() + text[m.start()..].chars().next().map_or(1, char::len_utf8);
+        }
+        None
+    }
+}
+
+/// Append `text` to `out` with every rule match replaced.
+fn apply_rules(rules: &[Rule], text: &str, out: &mut Vec<SpeechPart>) {
+    let mut pos = 0;
+    loop {
+        let best = rules
+            .iter()
+            .filter_map(|rule| Some((rule.find(text, pos)?, rule)))
+            .min_by_key(|(caps, _)| caps.get(0).map_or(usize::MAX, |m| m.start()));
+        let Some((caps, rule)) = best else { break };
+        let m = caps.get(0).unwrap();
+
+        push_text(out, &text[pos..m.start()]);
+        match &rule.pronunciation {
+            Pronunciation::Respell(respell) if rule.expand => {
+                let mut expanded = String::new();
+                caps.expand(respell, &mut expanded);
+                push_text(out, &expanded);
+            }
+            Pronunciation::Respell(respell) => push_text(out, respell),
+            Pronunciation::Ipa(ipa) => out.push(SpeechPart::Ipa(ipa.clone())),
+        }
+        pos = m.end();
+    }
+    push_text(out, &text[pos..]);
+}
+
+/// Append text, merging with a preceding text part.
+fn push_text(out: &mut Vec<SpeechPart>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    match out.last_mut() {
+        Some(SpeechPart::Text(prev)) => prev.push_str(text),
+        _ => out.push(SpeechPart::Text(text.to_string())),
+    }
+}
+
+// ─── Synthesizer wrapper ───────────────────────────────────────────────────
+
+/// Applies a [`Lexicon`] to every clip before handing it to `inner`.
+///
+/// Backends that don't phonemize themselves (HTTP, mock) ignore IPA parts and
+/// respellings and speak the clip's original text.
+pub(crate) struct LexiconSynth {
+    pub(crate) inner: Arc<dyn Synthesizer>,
+    pub(crate) lexicon: Lexicon,
+}
+
+impl Synthesizer for LexiconSynth {
+    fn synth<'a>(
+        &'a self,
+        text: &'a str,
+        voice: &'a str,
+        speed: f32,
+        language: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Synthesis, String>> {
+        if self.lexicon.is_empty() {
+            return self.inner.synth(text, voice, speed, language);
+        }
+        let parts = [SpeechPart::Text(text.to_string())];
+        self.synth_parts_owned(self.lexicon.apply(&parts), text, voice, speed, language)
+    }
+
+    fn synth_parts<'a>(
+        &'a self,
+        parts: &'a [SpeechPart],
+        text: &'a str,
+        voice: &'a str,
+        speed: f32,
+        language: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Synthesis, String>> {
+        self.synth_parts_owned(self.lexicon.apply(parts), text, voice, speed, language)
+    }
+
+    fn voices(&self) -> Vec<String> {
+        self.inner.voices()
+    }
+
+    fn validate_voice(&self, voice: &str) -> Result<(), String> {
+        self.inner.validate_voice(voice)
+    }
+
+    fn voice_kind(&self, voice: &str) -> VoiceKind {
+        self.inner.voice_kind(voice)
+    }
+
+    fn save_voice(&self, name: &str, blend: &str) -> Result<(), String> {
+        self.inner.save_voice(name, blend)
+    }
+
+    fn check_save_voice(&self, name: &str, blend: &str) -> Result<(), String> {
+        self.inner.check_save_voice(name, blend)
+    }
+
+    fn cache_id(&self, voice: &str) -> Option<String> {
+        self.inner.cache_id(voice)
+    }
+
+    fn cache_stats(&self) -> Option<CacheStats> {
+        self.inner.cache_stats()
+    }
+}
+
+impl LexiconSynth {
+    fn synth_parts_owned<'a>(
+        &'a self,
+        parts: Vec<SpeechPart>,
+        text: &'a str,
+        voice: &'a str,
+        speed: f32,
+        language: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Synthesis, String>> {
+        Box::pin(async move {
+            match parts.as_slice() {
+                [SpeechPart::Text(t)] if t == text => {
+                    self.inner.synth(text, voice, speed, language).await
+                }
+                _ => {
+                    self.inner
+                        .synth_parts(&parts, text, voice, speed, language)
+                        .await
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(pattern: &str, pronunciation: Pronunciation) -> LexiconEntry {
+        LexiconEntry {
+            pattern: pattern.to_string(),
+            pronunciation,
+            regex: false,
+            case_sensitive: false,
+            whole_word: true,
+        }
+    }
+
+    fn respell(pattern: &str, to: &str) -> LexiconEntry {
+        entry(pattern, Pronunciation::Respell(to.to_string()))
+    }
+
+    fn apply(lexicon: &Lexicon, text: &str) -> Vec<SpeechPart> {
+        lexicon.apply(&[SpeechPart::Text(text.to_string())])
+    }
+
+    fn text(s: &str) -> SpeechPart {
+        SpeechPart::Text(s.to_string())
+    }
+
+    #[test]
+    fn respell_and_ipa() {
+        let lexicon = Lexicon::default();
+        lexicon.insert(respell("SQL", "sequel")).unwrap();
+        lexicon
+            .insert(entry("nayru", Pronunciation::Ipa("nˈaɪɹuː".into())))
+            .unwrap();
+        assert_eq!(
+            apply(&lexicon, "Nayru speaks sql."),
+            [
+                SpeechPart::Ipa("nˈaɪɹuː".into()),
+                text(" speaks sequel."),
+            ]
+        );
+    }
+
+    #[test]
+    fn whole_word_and_case_are_configurable() {
+        let lexicon = Lexicon::default();
+        lexicon
+            .insert(LexiconEntry {
+                case_sensitive: true,
+                ..respell("US", "U S")
+            })
+            .unwrap();
+        assert_eq!(apply(&lexicon, "us and US, USB"), [text("us and U S, USB")]);
+
+        lexicon
+            .insert(LexiconEntry {
+                whole_word: false,
+                ..respell("k8s", "kubernetes")
+            })
+            .unwrap();
+        assert_eq!(apply(&lexicon, "myk8scluster"), [text("mykubernetescluster")]);
+    }
+
+    #[test]
+    fn punctuated_patterns_match_whole_words() {
+        let lexicon = Lexicon::default();
+        lexicon.insert(respell("C++", "C plus plus")).unwrap();
+        assert_eq!(apply(&lexicon, "I like C++."), [text("I like C plus plus.")]);
+        assert_eq!(apply(&lexicon, "BC++"), [text("BC++")]);
+    }
+
+    #[test]
+    fn regex_captures_expand() {
+        let lexicon = Lexicon::default();
+        lexicon
+            .insert(LexiconEntry {
+                regex: true,
+                ..respell(r"v(\d+)", "version $1")
+            })
+            .unwrap();
+        assert_eq!(apply(&lexicon, "v2 or v10x"), [text("version 2 or v10x")]);
+        assert!(lexicon
+            .insert(LexiconEntry {
+                regex: true,
+                ..respell("(", "x")
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn insert_replaces_and_remove_deletes() {
+        let lexicon = Lexicon::default();
+        lexicon.insert(respell("a", "one")).unwrap();
+        lexicon.insert(respell("a", "two")).unwrap();
+        assert_eq!(lexicon.entries(), [respell("a", "two")]);
+        assert!(lexicon.remove("a").unwrap());
+        assert!(!lexicon.remove("a").unwrap());
+        assert!(lexicon.entries().is_empty());
+    }
+
+    #[test]
+    fn failed_save_leaves_lexicon_unchanged() {
+        let dir = std::env::temp_dir().join(format!("nayru-lexicon-ro-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let lexicon = Lexicon::default();
+        lexicon.load(dir.join(FILE_NAME)).unwrap();
+        lexicon.insert(respell("SQL", "sequel")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        // A file where the directory was: every save fails.
+        std::fs::write(&dir, "").unwrap();
+
+        assert!(lexicon.insert(respell("GUI", "gooey")).is_err());
+        assert!(lexicon.insert(respell("SQL", "squirrel")).is_err());
+        assert!(lexicon.remove("SQL").is_err());
+        assert_eq!(lexicon.entries(), [respell("SQL", "sequel")]);
+        assert_eq!(apply(&lexicon, "GUI in SQL"), [text("GUI in sequel")]);
+
+        std::fs::remove_file(&dir).unwrap();
+    }
+
+    #[test]
+    fn entry_json_format() {
+        let entry: LexiconEntry =
+            serde_json::from_str(r#"{"pattern": "SQL", "respell": "sequel"}"#).unwrap();
+        assert_eq!(entry, respell("SQL", "sequel"));
+        let json = serde_json::to_value(entry).unwrap();
+        assert_eq!(json["respell"], "sequel");
+    }
+
+    #[test]
+    fn file_round_trip_and_reload() {
+        let dir = std::env::temp_dir().join(format!("nayru-lexicon-{}", std::process::id()));
+        let path = dir.join(FILE_NAME);
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let lexicon = Lexicon::default();
+        assert_eq!(lexicon.load(&path).unwrap(), 0);
+        lexicon.insert(respell("SQL", "sequel")).unwrap();
+        assert!(!lexicon.reload_if_changed().unwrap());
+
+        let other = Lexicon::default();
+        assert_eq!(other.load(&path).unwrap(), 1);
+
+        // An external edit (mtime forced forward so coarse clocks still see it).
+        std::fs::write(&path, r#"[{"pattern": "GUI", "respell": "gooey"}]"#).unwrap();
+        let later = SystemTime::now() + Duration::from_secs(5);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert!(lexicon.reload_if_changed().unwrap());
+        assert_eq!(lexicon.entries(), [respell("GUI", "gooey")]);
+
+        std::fs::write(&path, "not json").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later + Duration::from_secs(5))
+            .unwrap();
+        assert!(lexicon.reload_if_changed().is_err());
+        assert_eq!(lexicon.entries(), [respell("GUI", "gooey")]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
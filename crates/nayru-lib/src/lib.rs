@@ -3,14 +3,28 @@
 //! TTS playback, STT capture, model download, service lifecycle, and HTTP API.
 //! Depends on nayru-core for pure types and text processing.
 
+pub mod audio_device;
+pub mod audio_sink;
+pub mod audio_stream;
 pub mod capture;
 pub mod download;
+pub mod encode;
+pub mod history;
+pub mod http_synth;
 pub mod kokoro;
+pub mod lexicon;
+pub mod loudness;
 pub mod manager;
+pub mod mock_synth;
+pub mod phonemizer;
 pub mod server;
 pub mod streaming_source;
+pub mod stretch;
 pub mod stt;
+pub mod synth;
+pub mod synth_cache;
 pub mod tts;
+pub mod voice_pack;
 
 // Re-export nayru-core for convenience
 pub use nayru_core;
@@ -0,0 +1,228 @@
+//! Clip loudness: measurement, normalization, and a peak limiter.
+//!
+//! Kokoro's level varies with the voice and with sentence length, so a long
+//! read jumps between loud and quiet sentences. Each clip is measured as
+//! integrated loudness (ITU-R BS.1770: K-weighting, 400 ms blocks, absolute
+//! and relative gates) and scaled to a target in LUFS. Gain can push peaks
+//! past full scale — normalization upward, or a request's `gain` — so
+//! [`limit`] pulls them under a ceiling with short ramps, instead of leaving
+//! them to be hard-clipped on conversion to 16-bit.
+
+/// Most normalization will boost a clip, in dB. Keeps a near-silent clip
+/// (a breath, a one-word aside) from being raised to full speech level.
+const MAX_BOOST_DB: f32 = 12.0;
+
+/// Peak ceiling of the limiter: -1 dBFS.
+const CEILING: f32 = 0.891;
+
+/// How long the limiter takes to pull gain down ahead of a peak.
+const ATTACK_SECS: f32 = 0.005;
+
+/// How long the limiter takes to recover from full scale to unity.
+const RELEASE_SECS: f32 = 0.08;
+
+/// Measurement block length and hop (75% overlap), in seconds.
+const BLOCK_SECS: f32 = 0.4;
+const HOP_SECS: f32 = 0.1;
+
+/// Blocks quieter than this are ignored outright.
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+
+/// Blocks more than this far below the ungated loudness are ignored.
+const RELATIVE_GATE_LU: f64 = 10.0;
+
+/// Integrated loudness of mono audio, in LUFS. `None` for silence.
+pub fn integrated_loudness(samples: &[f32], sample_rate: u32) -> Option<f32> {
+    let weighted = k_weight(samples, sample_rate);
+    let block = ((BLOCK_SECS * sample_rate as f32) as usize).max(1);
+    let hop = ((HOP_SECS * sample_rate as f32) as usize).max(1);
+
+    // Mean square of each block. A clip shorter than one block is one block.
+    let powers: Vec<f64> = if weighted.len() <= block {
+        vec![mean_square(&weighted)]
+    } else {
+        (0..=(weighted.len() - block) / hop)
+            .map(|i| mean_square(&weighted[i * hop..i * hop + block]))
+            .collect()
+    };
+
+    let gated = |threshold: f64| -> Option<f64> {
+        let kept: Vec<f64> = powers
+            .iter()
+            .copied()
+            .filter(|&p| lufs(p) > threshold)
+            .collect();
+        (!kept.is_empty()).then(|| kept.iter().sum::<f64>() / kept.len() as f64)
+    };
+    let ungated = gated(ABSOLUTE_GATE_LUFS)?;
+    let integrated = gated(lufs(ungated) - RELATIVE_GATE_LU)?;
+    Some(lufs(integrated) as f32)
+}
+
+/// Scale `samples` so their integrated loudness is `target` LUFS. Silence
+/// is left alone and boosts are capped at [`MAX_BOOST_DB`]. Doesn't limit;
+/// run [`limit`] afterwards.
+pub fn normalize(samples: &mut [f32], sample_rate: u32, target: f32) {
+    let Some(loudness) = integrated_loudness(samples, sample_rate) else {
+        return;
+    };
+    let gain = db_to_gain((target - loudness).min(MAX_BOOST_DB));
+    for sample in samples {
+        *sample *= gain;
+    }
+}
+
+/// Bring every peak under the ceiling (-1 dBFS). Gain ramps down over
+/// [`ATTACK_SECS`] before a peak and back up over [`RELEASE_SECS`] after it,
+/// so the rest of the clip is untouched and there are no clicks.
+pub fn limit(samples: &mut [f32], sample_rate: u32) {
+    if !samples.iter().any(|s| s.abs() > CEILING) {
+        return;
+    }
+    // Gain each sample needs on its own, then spread each dip backwards
+    // (attack) and forwards (release). Both passes only lower the gain, so
+    // every sample still ends up at or under the ceiling.
+    let mut gain: Vec<f32> = samples
+        .iter()
+        .map(|s| (CEILING / s.abs()).min(1.0))
+        .collect();
+    let attack = 1.0 / (ATTACK_SECS * sample_rate as f32);
+    let release = 1.0 / (RELEASE_SECS * sample_rate as f32);
+    for i in (0..gain.len().saturating_sub(1)).rev() {
+        gain[i] = gain[i].min(gain[i + 1] + attack);
+    }
+    for i in 1..gain.len() {
+        gain[i] = gain[i].min(gain[i - 1] + release);
+    }
+    for (sample, gain) in samples.iter_mut().zip(gain) {
+        *sample *= gain;
+    }
+}
+
+fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+fn lufs(mean_square: f64) -> f64 {
+    -0.691 + 10.0 * mean_square.max(f64::MIN_POSITIVE).log10()
+}
+
+fn mean_square(samples: &[f64]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    samples.iter().map(|s| s * s).sum::<f64>() / samples.len() as f64
+}
+
+/// BS.1770 K-weighting: a high shelf (the head's acoustic effect) then a
+/// high-pass, with coefficients derived for any sample rate.
+fn k_weight(samples: &[f32], sample_rate: u32) -> Vec<f64> {
+    let fs = sample_rate as f64;
+
+    let k = (std::f64::consts::PI * 1_681.974_450_955_533 / fs).tan();
+    let q = 0.707_175_236_955_419_6;
+    let vh = 10f64.powf(3.999_843_853_973_347 / 20.0);
+    let vb = vh.powf(0.499_666_774_154_541_6);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    };
+
+    let k = (std::f64::consts::PI * 38.135_470_876_024_44 / fs).tan();
+    let q = 0.500_327_037_323_877_3;
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    };
+
+    let mut out: Vec<f64> = samples.iter().map(|&s| s as f64).collect();
+    shelf.run(&mut out);
+    high_pass.run(&mut out);
+    out
+}
+
+/// Direct form I biquad; `a` omits the leading 1.
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+}
+
+impl Biquad {
+    fn run(&self, samples: &mut [f64]) {
+        let (mut x1, mut x2, mut y1, mut y2) = (0.0, 0.0, 0.0, 0.0);
+        for sample in samples {
+            let x = *sample;
+            let y = self.b[0] * x + self.b[1] * x1 + self.b[2] * x2 - self.a[0] * y1 - self.a[1] * y2;
+            (x2, x1, y2, y1) = (x1, x, y1, y);
+            *sample = y;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(freq: f32, amplitude: f32, secs: f32, rate: u32) -> Vec<f32> {
+        (0..(secs * rate as f32) as usize)
+            .map(|i| amplitude * (std::f32::consts::TAU * freq * i as f32 / rate as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn full_scale_1k_sine_is_minus_3_lufs() {
+        // The reference level in BS.1770.
+        for rate in [48_000, 24_000] {
+            let loudness = integrated_loudness(&sine(997.0, 1.0, 2.0, rate), rate).unwrap();
+            assert!((loudness + 3.01).abs() < 0.1, "{rate} Hz: {loudness}");
+        }
+        assert_eq!(integrated_loudness(&[0.0; 24_000], 24_000), None);
+    }
+
+    #[test]
+    fn gating_ignores_silence_between_words() {
+        let mut clip = sine(997.0, 0.1, 1.0, 24_000);
+        let speech_only = integrated_loudness(&clip, 24_000).unwrap();
+        clip.extend(vec![0.0; 48_000]);
+        let with_pause = integrated_loudness(&clip, 24_000).unwrap();
+        assert!((speech_only - with_pause).abs() < 1.0);
+    }
+
+    #[test]
+    fn normalize_reaches_target_with_capped_boost() {
+        let mut loud = sine(997.0, 0.8, 1.0, 24_000);
+        normalize(&mut loud, 24_000, -18.0);
+        let loudness = integrated_loudness(&loud, 24_000).unwrap();
+        assert!((loudness + 18.0).abs() < 0.1);
+
+        // -60 LUFS would need +42 dB; it only gets MAX_BOOST_DB.
+        let mut faint = sine(997.0, 0.001, 1.0, 24_000);
+        let before = integrated_loudness(&faint, 24_000).unwrap();
+        normalize(&mut faint, 24_000, -18.0);
+        let after = integrated_loudness(&faint, 24_000).unwrap();
+        assert!((after - before - MAX_BOOST_DB).abs() < 0.1);
+    }
+
+    #[test]
+    fn limiter_caps_peaks_and_leaves_the_rest() {
+        let mut samples = vec![0.5; 24_000];
+        samples[12_000] = 2.0;
+        samples[12_001] = -1.5;
+        limit(&mut samples, 24_000);
+        assert!(samples.iter().all(|s| s.abs() <= CEILING));
+        assert_eq!(samples[0], 0.5);
+        assert_eq!(samples[23_999], 0.5);
+        // Ramps, not a step: the sample before the peak is only a bit lower.
+        assert!(samples[11_999] < 0.5 && samples[11_999] > 0.2);
+
+        let mut quiet = vec![0.5, -0.5];
+        limit(&mut quiet, 24_000);
+        assert_eq!(quiet, vec![0.5, -0.5]);
+    }
+}
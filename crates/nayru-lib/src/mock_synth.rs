@@ -0,0 +1,176 @@
+//! Deterministic tone synthesizer for tests and model-less machines.
+//!
+//! Each word becomes a short sine tone whose length follows the word's
+//! length and the speed, separated by silence. The pitch is derived from the
+//! voice name, so different voices are audibly (and numerically) distinct.
+//! The same input always produces the same samples.
+
+use std::f32::consts::TAU;
+use std::time::Instant;
+
+use futures_util::future::BoxFuture;
+
+use nayru_core::timing::WordTiming;
+
+use crate::synth::{Synthesis, Synthesizer};
+
+/// Output sample rate (same as Kokoro).
+const SAMPLE_RATE: u32 = 24_000;
+
+/// Tone length per character of a word, at speed 1.0.
+const SECS_PER_CHAR: f32 = 0.06;
+
+/// Shortest tone for any word, at speed 1.0.
+const MIN_WORD_SECS: f32 = 0.1;
+
+/// Silence between words, at speed 1.0.
+const GAP_SECS: f32 = 0.05;
+
+const AMPLITUDE: f32 = 0.2;
+
+/// Tone generator implementing [`Synthesizer`].
+#[derive(Debug, Clone)]
+pub struct MockSynth {
+    voices: Vec<String>,
+}
+
+impl Default for MockSynth {
+    /// Offers the engine's default voice plus a couple of others.
+    fn default() -> Self {
+        Self::new(["af_heart", "am_adam", "bf_emma"])
+    }
+}
+
+impl MockSynth {
+    /// A mock that accepts exactly `voices`.
+    pub fn new<I, S>(voices: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut voices: Vec<String> = voices.into_iter().map(Into::into).collect();
+        voices.sort();
+        voices.dedup();
+        Self { voices }
+    }
+
+    /// Render `text` as tones. Pure and synchronous.
+    pub fn render(&self, text: &str, voice: &str, speed: f32) -> Synthesis {
+        let started = Instant::now();
+        let rate = SAMPLE_RATE as f32;
+        let freq = voice_frequency(voice);
+        let gap = (GAP_SECS / speed * rate) as usize;
+
+        let mut samples = Vec::new();
+        let mut words = Vec::new();
+        for (i, word) in text.split_whitespace().enumerate() {
+            if i > 0 {
+                samples.resize(samples.len() + gap, 0.0);
+            }
+            let chars = word.chars().filter(|c| c.is_alphanumeric()).count();
+            let secs = (chars as f32 * SECS_PER_CHAR).max(MIN_WORD_SECS) / speed;
+            let len = (secs * rate) as usize;
+            let start = samples.len();
+            samples.extend((0..len).map(|n| AMPLITUDE * (TAU * freq * n as f32 / rate).sin()));
+            words.push(WordTiming {
+                word: word.to_string(),
+                start: start as f32 / rate,
+                end: samples.len() as f32 / rate,
+            });
+        }
+
+        Synthesis {
+            samples,
+            sample_rate: SAMPLE_RATE,
+            took: started.elapsed(),
+            words,
+        }
+    }
+}
+
+impl Synthesizer for MockSynth {
+    fn synth<'a>(
+        &'a self,
+        text: &'a str,
+        voice: &'a str,
+        speed: f32,
+        _language: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Synthesis, String>> {
+        Box::pin(async move {
+            self.validate_voice(voice)?;
+            Ok(self.render(text, voice, speed))
+        })
+    }
+
+    fn voices(&self) -> Vec<String> {
+        self.voices.clone()
+    }
+
+    /// Output is a pure function of the input, so it caches like a model.
+    fn cache_id(&self, _voice: &str) -> Option<String> {
+        Some("mock".to_string())
+    }
+}
+
+/// A pitch between 200 and 600 Hz picked from the voice name (FNV-1a).
+fn voice_frequency(voice: &str) -> f32 {
+    let hash = voice
+        .bytes()
+        .fold(0x811c_9dc5_u32, |h, b| (h ^ b as u32).wrapping_mul(0x0100_0193));
+    200.0 + (hash % 400) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(synthesis: &Synthesis) -> f32 {
+        synthesis.duration().as_secs_f32()
+    }
+
+    #[test]
+    fn deterministic() {
+        let mock = MockSynth::default();
+        let a = mock.render("Hello there.", "af_heart", 1.0);
+        let b = mock.render("Hello there.", "af_heart", 1.0);
+        assert_eq!(a.samples, b.samples);
+        assert_eq!(a.sample_rate, SAMPLE_RATE);
+    }
+
+    #[test]
+    fn voices_differ_in_pitch() {
+        let mock = MockSynth::default();
+        let a = mock.render("Hello.", "af_heart", 1.0);
+        let b = mock.render("Hello.", "am_adam", 1.0);
+        assert_eq!(a.samples.len(), b.samples.len());
+        assert_ne!(a.samples, b.samples);
+    }
+
+    #[test]
+    fn speed_shortens_audio() {
+        let mock = MockSynth::default();
+        let normal = secs(&mock.render("one two three", "af_heart", 1.0));
+        let fast = secs(&mock.render("one two three", "af_heart", 2.0));
+        assert!((normal / fast - 2.0).abs() < 0.01, "{normal} vs {fast}");
+    }
+
+    #[test]
+    fn word_timings_cover_words() {
+        let synthesis = MockSynth::default().render("a longer word", "af_heart", 1.0);
+        let words: Vec<&str> = synthesis.words.iter().map(|w| w.word.as_str()).collect();
+        assert_eq!(words, ["a", "longer", "word"]);
+        assert!((synthesis.words[0].end - MIN_WORD_SECS).abs() < 1e-3);
+        assert!((synthesis.words[2].end - secs(&synthesis)).abs() < 1e-3);
+        assert!(synthesis.words[1].start > synthesis.words[0].end);
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_voice() {
+        let mock = MockSynth::new(["only"]);
+        let err = Synthesizer::synth(&mock, "hi", "other", 1.0, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err, "unknown voice 'other'; valid voices: only");
+        assert!(Synthesizer::synth(&mock, "hi", "only", 1.0, None).await.is_ok());
+    }
+}
@@ -0,0 +1,458 @@
+//! Text → IPA phonemes for Kokoro.
+//!
+//! Kokoro reads IPA, which espeak-ng produces. Spawning `espeak-ng --ipa`
+//! for every sentence costs tens of milliseconds of time-to-first-audio, so:
+//!
+//! - [`EspeakWorker`] keeps one espeak-ng process per language alive and
+//!   feeds it one line per request. espeak-ng reads stdin line by line when
+//!   given no text argument and flushes after each line; a sentinel line
+//!   after every request marks where its output ends.
+//! - [`CachedPhonemizer`] puts an LRU cache keyed by (text, language) in
+//!   front of any [`Phonemizer`] — reader apps re-speak the same sentences.
+//! - [`EspeakProcess`] is the spawn-per-call path, kept as the worker's
+//!   fallback and as the benchmark baseline (`cargo bench -p nayru-lib`).
+
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+const ESPEAK: &str = "espeak-ng";
+
+/// Written after every request; its phonemes mark the end of the response.
+/// Nonsense so no real clause phonemizes the same way.
+const SENTINEL: &str = "qzxqzx";
+
+/// How long a worker may take to answer before it's considered hung.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default number of phonemized sentences [`CachedPhonemizer`] keeps.
+pub const DEFAULT_CACHE_CAPACITY: usize = 1024;
+
+/// Converts text to space-separated IPA words.
+pub trait Phonemizer: Send + Sync {
+    /// Phonemize `text` with espeak-ng voice `language` (e.g. `en-us`).
+    fn phonemize<'a>(
+        &'a self,
+        text: &'a str,
+        language: &'a str,
+    ) -> BoxFuture<'a, Result<String, String>>;
+}
+
+/// Check that espeak-ng can run, so a missing install fails at startup
+/// with instructions rather than on the first sentence.
+pub async fn check_espeak() -> Result<(), String> {
+    let output = Command::new(ESPEAK)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| {
+            format!(
+                "espeak-ng is required for phonemization but couldn't be run ({e}); \
+                 install it (e.g. `apt install espeak-ng` or `brew install espeak-ng`) \
+                 and make sure it's on PATH"
+            )
+        })?;
+    if !output.status.success() {
+        return Err(format!(
+            "espeak-ng --version failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    debug!(
+        "phonemizer: {}",
+        String::from_utf8_lossy(&output.stdout).trim()
+    );
+    Ok(())
+}
+
+/// Join espeak-ng output lines (one per clause) into one line of IPA words.
+fn normalize_ipa(raw: &str) -> Result<String, String> {
+    let ipa = raw.split_whitespace().collect::<Vec<_>>().join(" ");
+    if ipa.is_empty() {
+        return Err("espeak-ng returned empty output".to_string());
+    }
+    Ok(ipa)
+}
+
+// ─── Spawn per call ────────────────────────────────────────────────────────
+
+/// Runs `espeak-ng --ipa` once per request.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EspeakProcess;
+
+impl EspeakProcess {
+    async fn run(text: &str, language: &str) -> Result<String, String> {
+        let output = Command::new(ESPEAK)
+            .args(["--ipa", "-q", "-v", language, text])
+            .output()
+            .await
+            .map_err(|e| format!("espeak-ng failed to execute: {e}"))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            warn!("espeak-ng stderr: {}", stderr);
+        }
+
+        normalize_ipa(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+impl Phonemizer for EspeakProcess {
+    fn phonemize<'a>(
+        &'a self,
+        text: &'a str,
+        language: &'a str,
+    ) -> BoxFuture<'a, Result<String, String>> {
+        Box::pin(Self::run(text, language))
+    }
+}
+
+// ─── Persistent worker ─────────────────────────────────────────────────────
+
+/// Keeps a long-lived espeak-ng process per language.
+///
+/// Workers start on first use. One that errors or stops answering is killed
+/// and respawned on the next request; the failed request falls back to
+/// [`EspeakProcess`]. So is one whose request was cancelled before its
+/// reply was read, which would otherwise answer the next request with it.
+pub struct EspeakWorker {
+    workers: std::sync::Mutex<HashMap<String, Arc<Mutex<Option<Worker>>>>>,
+    /// Starts the worker for a language.
+    command: fn(&str) -> Command,
+}
+
+impl Default for EspeakWorker {
+    fn default() -> Self {
+        Self {
+            workers: Default::default(),
+            command: espeak_command,
+        }
+    }
+}
+
+impl EspeakWorker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn slot(&self, language: &str) -> Arc<Mutex<Option<Worker>>> {
+        self.workers
+            .lock()
+            .unwrap()
+            .entry(language.to_string())
+            .or_default()
+            .clone()
+    }
+
+    async fn run(&self, text: &str, language: &str) -> Result<String, String> {
+        let slot = self.slot(language);
+        let mut worker = slot.lock().await;
+        if worker.as_ref().is_some_and(|w| w.busy) {
+            debug!("phonemizer: {language} worker has an unread reply; respawning");
+            *worker = None;
+        }
+
+        let result = match worker.as_mut() {
+            Some(w) => w.request(text).await,
+            None => match Worker::spawn((self.command)(language)).await {
+                Ok(w) => worker.insert(w).request(text).await,
+                Err(e) => Err(e),
+            },
+        };
+
+        match result {
+            Ok(ipa) => normalize_ipa(&ipa),
+            Err(e) => {
+                warn!("phonemizer: espeak-ng worker for {language} failed ({e}); spawning per call");
+                *worker = None;
+                drop(worker);
+                EspeakProcess::run(text, language).await
+            }
+        }
+    }
+}
+
+impl Phonemizer for EspeakWorker {
+    fn phonemize<'a>(
+        &'a self,
+        text: &'a str,
+        language: &'a str,
+    ) -> BoxFuture<'a, Result<String, String>> {
+        Box::pin(self.run(text, language))
+    }
+}
+
+fn espeak_command(language: &str) -> Command {
+    let mut command = Command::new(ESPEAK);
+    command.args(["--ipa", "-q", "-v", language]);
+    command
+}
+
+/// A running line-oriented phonemizer process.
+struct Worker {
+    _child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    /// What the process prints for [`SENTINEL`].
+    sentinel: String,
+    /// A request was written and its sentinel not yet read back.
+    busy: bool,
+}
+
+impl Worker {
+    /// Start `command` and learn how it phonemizes the sentinel.
+    async fn spawn(mut command: Command) -> Result<Self, String> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("espeak-ng failed to start: {e}"))?;
+        let stdin = child.stdin.take().ok_or("no stdin")?;
+        let stdout = BufReader::new(child.stdout.take().ok_or("no stdout")?);
+
+        let mut worker = Self {
+            _child: child,
+            stdin,
+            stdout,
+            sentinel: String::new(),
+            busy: false,
+        };
+        worker.write_line(SENTINEL).await?;
+        worker.sentinel = tokio::time::timeout(RESPONSE_TIMEOUT, worker.next_line())
+            .await
+            .map_err(|_| "timed out starting espeak-ng".to_string())??;
+        debug!("phonemizer: worker ready (sentinel {:?})", worker.sentinel);
+        Ok(worker)
+    }
+
+    /// Phonemize one request: everything printed before the sentinel.
+    async fn request(&mut self, text: &str) -> Result<String, String> {
+        let line = text.replace(['\n', '\r'], " ");
+        // Cleared only once the whole reply is read, so a caller dropped
+        // in between leaves the worker marked.
+        self.busy = true;
+        self.write_line(&line).await?;
+        self.write_line(SENTINEL).await?;
+
+        tokio::time::timeout(RESPONSE_TIMEOUT, async {
+            let mut out = Vec::new();
+            loop {
+                let line = self.next_line().await?;
+                if line == self.sentinel {
+                    self.busy = false;
+                    return Ok(out.join(" "));
+                }
+                out.push(line);
+            }
+        })
+        .await
+        .map_err(|_| "timed out waiting for espeak-ng".to_string())?
+    }
+
+    async fn write_line(&mut self, line: &str) -> Result<(), String> {
+        self.stdin
+            .write_all(format!("{line}\n").as_bytes())
+            .await
+            .and(self.stdin.flush().await)
+            .map_err(|e| format!("espeak-ng write: {e}"))
+    }
+
+    /// Next non-blank output line, trimmed.
+    async fn next_line(&mut self) -> Result<String, String> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let n = self
+                .stdout
+                .read_line(&mut line)
+                .await
+                .map_err(|e| format!("espeak-ng read: {e}"))?;
+            if n == 0 {
+                return Err("espeak-ng exited".to_string());
+            }
+            if !line.trim().is_empty() {
+                return Ok(line.trim().to_string());
+            }
+        }
+    }
+}
+
+// ─── Cache ─────────────────────────────────────────────────────────────────
+
+/// LRU cache of phonemized text in front of another [`Phonemizer`].
+pub struct CachedPhonemizer<P> {
+    inner: P,
+    cache: std::sync::Mutex<LruCache>,
+}
+
+impl<P: Phonemizer> CachedPhonemizer<P> {
+    /// Cache up to `capacity` results from `inner`. `0` disables caching.
+    pub fn new(inner: P, capacity: usize) -> Self {
+        Self {
+            inner,
+            cache: std::sync::Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    async fn run(&self, text: &str, language: &str) -> Result<String, String> {
+        let key = (language.to_string(), text.to_string());
+        if let Some(ipa) = self.cache.lock().unwrap().get(&key) {
+            return Ok(ipa);
+        }
+        let ipa = self.inner.phonemize(text, language).await?;
+        self.cache.lock().unwrap().insert(key, ipa.clone());
+        Ok(ipa)
+    }
+}
+
+impl<P: Phonemizer> Phonemizer for CachedPhonemizer<P> {
+    fn phonemize<'a>(
+        &'a self,
+        text: &'a str,
+        language: &'a str,
+    ) -> BoxFuture<'a, Result<String, String>> {
+        Box::pin(self.run(text, language))
+    }
+}
+
+type CacheKey = (String, String);
+
+/// Least-recently-used map. Eviction scans for the oldest entry, which is
+/// cheap next to a phonemizer call at the sizes used here.
+struct LruCache {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<CacheKey, (String, u64)>,
+}
+
+impl LruCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    fn get(&mut self, key: &CacheKey) -> Option<String> {
+        self.tick += 1;
+        let (value, used) = self.entries.get_mut(key)?;
+        *used = self.tick;
+        Some(value.clone())
+    }
+
+    fn insert(&mut self, key: CacheKey, value: String) {
+        if self.capacity == 0 {
+            return;
+        }
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.tick += 1;
+        self.entries.insert(key, (value, self.tick));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn key(s: &str) -> CacheKey {
+        ("en-us".to_string(), s.to_string())
+    }
+
+    #[test]
+    fn lru_evicts_least_recently_used() {
+        let mut cache = LruCache::new(2);
+        cache.insert(key("a"), "A".into());
+        cache.insert(key("b"), "B".into());
+        assert_eq!(cache.get(&key("a")).as_deref(), Some("A"));
+        cache.insert(key("c"), "C".into());
+        assert_eq!(cache.get(&key("b")), None);
+        assert_eq!(cache.get(&key("a")).as_deref(), Some("A"));
+        assert_eq!(cache.get(&key("c")).as_deref(), Some("C"));
+    }
+
+    #[test]
+    fn lru_zero_capacity_stores_nothing() {
+        let mut cache = LruCache::new(0);
+        cache.insert(key("a"), "A".into());
+        assert_eq!(cache.get(&key("a")), None);
+    }
+
+    /// Upper-cases its input and counts calls.
+    #[derive(Default)]
+    struct Counting(AtomicUsize);
+
+    impl Phonemizer for Counting {
+        fn phonemize<'a>(
+            &'a self,
+            text: &'a str,
+            _language: &'a str,
+        ) -> BoxFuture<'a, Result<String, String>> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move { Ok(text.to_uppercase()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn cache_is_keyed_by_text_and_language() {
+        let cached = CachedPhonemizer::new(Counting::default(), 8);
+        assert_eq!(cached.phonemize("hi", "en-us").await.unwrap(), "HI");
+        assert_eq!(cached.phonemize("hi", "en-us").await.unwrap(), "HI");
+        assert_eq!(cached.inner.0.load(Ordering::SeqCst), 1);
+        cached.phonemize("hi", "en-gb").await.unwrap();
+        assert_eq!(cached.inner.0.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn worker_protocol_reads_until_sentinel() {
+        // `cat` "phonemizes" each line to itself, so the sentinel comes back verbatim.
+        let mut worker = Worker::spawn(Command::new("cat")).await.unwrap();
+        assert_eq!(worker.sentinel, SENTINEL);
+        assert_eq!(worker.request("hello world").await.unwrap(), "hello world");
+        assert_eq!(worker.request("two\nlines").await.unwrap(), "two lines");
+        assert_eq!(worker.request("").await.unwrap(), "");
+    }
+
+    #[tokio::test]
+    async fn cancelled_request_does_not_answer_the_next() {
+        // Echoes each line after 300 ms, so a request can be cut off mid-reply.
+        let phonemizer = EspeakWorker {
+            command: |_| {
+                let mut command = Command::new("sh");
+                command.args(["-c", "while read -r l; do sleep 0.3; echo \"$l\"; done"]);
+                command
+            },
+            ..EspeakWorker::new()
+        };
+        // Past the spawn's sentinel round trip, before the request's reply.
+        let cut = tokio::time::timeout(Duration::from_millis(450), phonemizer.run("first", "en-us")).await;
+        assert!(cut.is_err());
+        assert_eq!(phonemizer.run("second", "en-us").await.unwrap(), "second");
+    }
+
+    #[test]
+    fn normalize_joins_clauses() {
+        assert_eq!(normalize_ipa(" həlˈoʊ\nwˈɜːld \n").unwrap(), "həlˈoʊ wˈɜːld");
+        assert!(normalize_ipa("\n").is_err());
+    }
+}
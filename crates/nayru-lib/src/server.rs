@@ -2,15 +2,71 @@
 //!
 //! Runs on port 2003 by default. CORS-permissive so raia-app can call from
 //! localhost:3000.
+//!
+//! `/events` pushes engine status and lifecycle events as Server-Sent Events;
+//! `/events/ws` sends the same JSON events as WebSocket text frames.
+//!
+//! `/v1/audio/speech` mirrors OpenAI's speech endpoint so OpenAI SDK clients
+//! can point at nayru directly. It returns audio bytes instead of playing them,
+//! as `wav`, `pcm`, `flac`, `opus` (Ogg), or `mp3` per `response_format`
+//! (`mp3` by default, like OpenAI, when the `mp3` feature is built).
+//!
+//! `/lexicon` lists (`GET`), adds or replaces (`POST`), and removes
+//! (`DELETE ?pattern=`) pronunciation lexicon entries; changes are saved to
+//! the lexicon file.
+//!
+//! `GET /audio/stream` streams the speech being played as chunked HTTP
+//! (`?format=pcm` or `opus`, in Ogg); `/audio/ws` sends it as WebSocket
+//! binary messages (PCM chunks or bare Opus packets), after a JSON `format`
+//! message and with a JSON `flush` message whenever speech is stopped. Both
+//! need the server's output to include remote listeners.
+//!
+//! `GET /voices` lists voices with metadata (`?preview=1` adds a short WAV
+//! sample of each); `POST /voices` saves a voice blend
+//! (`af_heart:0.6+bf_emma:0.4`) as a named custom voice.
+//!
+//! `GET /devices` lists audio output devices; `POST /devices` moves playback
+//! to one by name (or back to the system default) without dropping the queue.
+//!
+//! `POST /volume` sets the master volume and `POST /rate` the playback rate
+//! (time-stretched, so it applies to queued speech too); `POST /duck` lowers
+//! playback while something else talks (optionally lifting after
+//! `timeout_ms`) and `DELETE /duck` brings it back up. `/speak` with
+//! `"priority": true` plays over the queue at once, ducking it meanwhile.
+//!
+//! `POST /replay` plays the current (or last) sentence again, `POST /previous`
+//! goes back a sentence, and `POST /rewind` goes back `seconds` of played
+//! audio; the queue carries on afterwards. They answer 400 until something
+//! has played.
 
-use axum::extract::State;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderName, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
 use axum::routing::{get, post};
 use axum::{Json, Router};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use futures_util::{Stream, StreamExt};
+use tokio::sync::broadcast;
 use tower_http::cors::CorsLayer;
+use tracing::warn;
+
+use nayru_core::text_prep::{parse_ssml, SsmlSegment};
+use nayru_core::types::{AudioDevice, SpeakOptions, TtsStatus, VoiceInfo};
 
-use nayru_core::types::TtsStatus;
+use nayru_core::wav::write_wav;
 
-use crate::tts::TtsEngine;
+use crate::audio_stream::{EpochFilter, LiveEncoder, StreamFrame, STREAM_SAMPLE_RATE};
+use crate::encode::{encode, AudioFormat, AudioTags};
+use crate::lexicon::{Lexicon, LexiconEntry};
+use crate::tts::{TtsEngine, DEFAULT_DUCK_LEVEL};
 
 /// Build the axum router with a shared [`TtsEngine`].
 pub fn router(engine: TtsEngine) -> Router {
@@ -20,9 +76,26 @@ pub fn router(engine: TtsEngine) -> Router {
         .route("/skip", post(skip))
         .route("/pause", post(pause))
         .route("/resume", post(resume))
+        .route("/replay", post(replay))
+        .route("/previous", post(previous))
+        .route("/rewind", post(rewind))
         .route("/status", get(status))
+        .route("/events", get(events_sse))
+        .route("/events/ws", get(events_ws))
         .route("/stream/chunk", post(stream_chunk))
         .route("/stream/end", post(stream_end))
+        .route("/v1/audio/speech", post(audio_speech))
+        .route("/audio/stream", get(audio_stream))
+        .route("/audio/ws", get(audio_ws))
+        .route("/voices", get(list_voices).post(save_voice))
+        .route("/devices", get(list_devices).post(set_device))
+        .route("/volume", post(set_volume))
+        .route("/rate", post(set_rate))
+        .route("/duck", post(duck).delete(unduck))
+        .route(
+            "/lexicon",
+            get(lexicon_list).post(lexicon_add).delete(lexicon_remove),
+        )
         .layer(CorsLayer::permissive())
         .with_state(engine)
 }
@@ -31,12 +104,26 @@ pub fn router(engine: TtsEngine) -> Router {
 struct SpeakRequest {
     text: String,
     #[serde(default)]
-    voice: Option<String>,
+    format: TextFormat,
+    #[serde(flatten)]
+    options: SpeakOptions,
+}
+
+/// How `/speak` interprets `text`.
+#[derive(serde::Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+enum TextFormat {
+    /// Plain text or markdown, cleaned before speaking.
+    #[default]
+    Text,
+    /// SSML markup.
+    Ssml,
 }
 
 #[derive(serde::Serialize)]
 struct SpeakResponse {
     ok: bool,
+    utterance_id: u64,
     queued_chunks: usize,
 }
 
@@ -45,16 +132,49 @@ struct OkResponse {
     ok: bool,
 }
 
+#[derive(serde::Serialize)]
+struct SpeakError {
+    ok: bool,
+    error: String,
+    /// Every voice, when the request named one the engine doesn't have.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    voices: Option<Vec<String>>,
+}
+
 async fn speak(
     State(engine): State<TtsEngine>,
     Json(req): Json<SpeakRequest>,
-) -> Json<SpeakResponse> {
-    let _ = req.voice; // TODO: per-request voice override
-    let n = engine.speak(&req.text);
-    Json(SpeakResponse {
-        ok: true,
-        queued_chunks: n,
-    })
+) -> Result<Json<SpeakResponse>, (StatusCode, Json<SpeakError>)> {
+    let mut named = Vec::from_iter(req.options.voice.clone());
+    let queued = match req.format {
+        TextFormat::Text => engine.speak(&req.text, req.options),
+        TextFormat::Ssml => engine.speak_ssml(&req.text, req.options),
+    };
+    match queued {
+        Ok(queued) => Ok(Json(SpeakResponse {
+            ok: true,
+            utterance_id: queued.utterance_id,
+            queued_chunks: queued.chunks,
+        })),
+        Err(error) => {
+            if let TextFormat::Ssml = req.format {
+                let segments = parse_ssml(&req.text).unwrap_or_default();
+                named.extend(segments.into_iter().filter_map(|segment| match segment {
+                    SsmlSegment::Text { style, .. } | SsmlSegment::Phoneme { style, .. } => style.voice,
+                    SsmlSegment::Break(_) => None,
+                }));
+            }
+            let unknown_voice = named.iter().any(|v| engine.validate_voice(v).is_err());
+            Err((
+                StatusCode::BAD_REQUEST,
+                Json(SpeakError {
+                    ok: false,
+                    error,
+                    voices: unknown_voice.then(|| engine.voices()),
+                }),
+            ))
+        }
+    }
 }
 
 async fn stop(State(engine): State<TtsEngine>) -> Json<OkResponse> {
@@ -77,10 +197,245 @@ async fn resume(State(engine): State<TtsEngine>) -> Json<OkResponse> {
     Json(OkResponse { ok: true })
 }
 
+async fn replay(
+    State(engine): State<TtsEngine>,
+) -> Result<Json<OkResponse>, (StatusCode, Json<ErrorResponse>)> {
+    engine
+        .replay_last()
+        .await
+        .map(|()| Json(OkResponse { ok: true }))
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, e))
+}
+
+async fn previous(
+    State(engine): State<TtsEngine>,
+) -> Result<Json<OkResponse>, (StatusCode, Json<ErrorResponse>)> {
+    engine
+        .previous()
+        .await
+        .map(|()| Json(OkResponse { ok: true }))
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, e))
+}
+
+#[derive(serde::Deserialize)]
+struct RewindRequest {
+    seconds: f32,
+}
+
+async fn rewind(
+    State(engine): State<TtsEngine>,
+    Json(req): Json<RewindRequest>,
+) -> Result<Json<OkResponse>, (StatusCode, Json<ErrorResponse>)> {
+    engine
+        .rewind(req.seconds)
+        .await
+        .map(|()| Json(OkResponse { ok: true }))
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, e))
+}
+
 async fn status(State(engine): State<TtsEngine>) -> Json<TtsStatus> {
     Json(engine.status())
 }
 
+async fn events_sse(
+    State(engine): State<TtsEngine>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream = engine.events().map(|event| {
+        Ok(Event::default()
+            .json_data(&event)
+            .unwrap_or_else(|_| Event::default().comment("serialize error")))
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+async fn events_ws(State(engine): State<TtsEngine>, ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(move |socket| forward_events(socket, engine))
+}
+
+/// Send every engine event to the socket until either side closes.
+/// Incoming messages are ignored (reading them keeps ping/pong working).
+async fn forward_events(mut socket: WebSocket, engine: TtsEngine) {
+    let events = engine.events();
+    tokio::pin!(events);
+    loop {
+        tokio::select! {
+            event = events.next() => {
+                let Some(event) = event else { break };
+                let Ok(json) = serde_json::to_string(&event) else { continue };
+                if socket.send(Message::Text(json.into())).await.is_err() {
+                    break;
+                }
+            }
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+// ─── Remote audio ──────────────────────────────────────────────────────────
+
+#[derive(serde::Deserialize)]
+struct AudioStreamQuery {
+    #[serde(default = "default_stream_format")]
+    format: String,
+}
+
+fn default_stream_format() -> String {
+    "pcm".into()
+}
+
+type AudioListener = (AudioFormat, broadcast::Receiver<StreamFrame>, LiveEncoder);
+
+/// Check the request and set up a listener: the tap subscription and encoder.
+fn open_audio_stream(
+    engine: &TtsEngine,
+    format: &str,
+    ogg: bool,
+) -> Result<AudioListener, (StatusCode, Json<ErrorResponse>)> {
+    let format = AudioFormat::parse(format).map_err(|e| error_response(StatusCode::BAD_REQUEST, e))?;
+    let encoder = LiveEncoder::new(format, ogg).map_err(|e| error_response(StatusCode::BAD_REQUEST, e))?;
+    let rx = engine.subscribe_audio().ok_or_else(|| {
+        error_response(
+            StatusCode::CONFLICT,
+            "remote audio is off; start the server with --output remote or both".into(),
+        )
+    })?;
+    Ok((format, rx, encoder))
+}
+
+/// Next frame for this listener, skipping stale audio. `None` once the
+/// engine is gone. Flushes come back as `Err(epoch)`.
+async fn next_audio(
+    rx: &mut broadcast::Receiver<StreamFrame>,
+    filter: &mut EpochFilter,
+) -> Option<Result<Arc<[i16]>, u64>> {
+    loop {
+        match rx.recv().await {
+            Ok(frame) => {
+                if let Some(samples) = filter.accept(&frame) {
+                    return Some(Ok(samples));
+                }
+                if let StreamFrame::Flush { epoch } = frame {
+                    return Some(Err(epoch));
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                warn!("audio stream: listener lagged, skipped {n} frames");
+            }
+            Err(broadcast::error::RecvError::Closed) => return None,
+        }
+    }
+}
+
+async fn audio_stream(
+    State(engine): State<TtsEngine>,
+    Query(query): Query<AudioStreamQuery>,
+) -> Response {
+    let (format, rx, encoder) = match open_audio_stream(&engine, &query.format, true) {
+        Ok(opened) => opened,
+        Err(e) => return e.into_response(),
+    };
+    let body = futures_util::stream::unfold(
+        (rx, encoder, EpochFilter::default()),
+        |(mut rx, mut encoder, mut filter)| async move {
+            loop {
+                // Raw PCM and Ogg have no way to say "drop what you buffered",
+                // so flushes only reset the stale-audio filter here.
+                let Ok(samples) = next_audio(&mut rx, &mut filter).await? else {
+                    continue;
+                };
+                match encoder.push(&samples) {
+                    Ok(chunks) if chunks.is_empty() => continue,
+                    Ok(chunks) => {
+                        let bytes = chunks.concat();
+                        return Some((Ok::<_, Infallible>(bytes), (rx, encoder, filter)));
+                    }
+                    Err(e) => {
+                        warn!("audio stream: {e}");
+                        return None;
+                    }
+                }
+            }
+        },
+    );
+    (
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CACHE_CONTROL, "no-cache".to_string()),
+            (HeaderName::from_static("x-audio-format"), format.name().to_string()),
+            (
+                HeaderName::from_static("x-audio-sample-rate"),
+                STREAM_SAMPLE_RATE.to_string(),
+            ),
+            (HeaderName::from_static("x-audio-channels"), "1".to_string()),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response()
+}
+
+async fn audio_ws(
+    State(engine): State<TtsEngine>,
+    Query(query): Query<AudioStreamQuery>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    match open_audio_stream(&engine, &query.format, false) {
+        Ok((format, rx, encoder)) => {
+            ws.on_upgrade(move |socket| forward_audio(socket, format, rx, encoder))
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Send the format, then audio as binary messages and flushes as JSON,
+/// until either side closes.
+async fn forward_audio(
+    mut socket: WebSocket,
+    format: AudioFormat,
+    mut rx: broadcast::Receiver<StreamFrame>,
+    mut encoder: LiveEncoder,
+) {
+    let header = serde_json::json!({
+        "type": "format",
+        "format": format.name(),
+        "sample_rate": STREAM_SAMPLE_RATE,
+        "channels": 1,
+    });
+    if socket.send(Message::Text(header.to_string().into())).await.is_err() {
+        return;
+    }
+    let mut filter = EpochFilter::default();
+    loop {
+        let messages = tokio::select! {
+            frame = next_audio(&mut rx, &mut filter) => match frame {
+                None => break,
+                Some(Ok(samples)) => match encoder.push(&samples) {
+                    Ok(chunks) => chunks.into_iter().map(|c| Message::Binary(c.into())).collect(),
+                    Err(e) => {
+                        warn!("audio stream: {e}");
+                        break;
+                    }
+                },
+                Some(Err(epoch)) => {
+                    let flush = serde_json::json!({ "type": "flush", "epoch": epoch });
+                    vec![Message::Text(flush.to_string().into())]
+                }
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+        };
+        for message in messages {
+            if socket.send(message).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
 #[derive(serde::Deserialize)]
 struct StreamChunkRequest {
     text: String,
@@ -98,3 +453,269 @@ async fn stream_end(State(engine): State<TtsEngine>) -> Json<OkResponse> {
     engine.stream_end();
     Json(OkResponse { ok: true })
 }
+
+// ─── Pronunciation lexicon ─────────────────────────────────────────────────
+
+#[derive(serde::Serialize)]
+struct ErrorResponse {
+    ok: bool,
+    error: String,
+}
+
+fn error_response(status: StatusCode, error: String) -> (StatusCode, Json<ErrorResponse>) {
+    (status, Json(ErrorResponse { ok: false, error }))
+}
+
+async fn lexicon_list(State(engine): State<TtsEngine>) -> Json<Vec<LexiconEntry>> {
+    Json(engine.lexicon().entries())
+}
+
+async fn lexicon_add(
+    State(engine): State<TtsEngine>,
+    Json(entry): Json<LexiconEntry>,
+) -> Result<Json<OkResponse>, (StatusCode, Json<ErrorResponse>)> {
+    Lexicon::check(&entry).map_err(|e| error_response(StatusCode::BAD_REQUEST, e))?;
+    engine
+        .lexicon()
+        .insert(entry)
+        .map(|()| Json(OkResponse { ok: true }))
+        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+#[derive(serde::Deserialize)]
+struct LexiconRemoveQuery {
+    pattern: String,
+}
+
+#[derive(serde::Serialize)]
+struct LexiconRemoveResponse {
+    ok: bool,
+    removed: bool,
+}
+
+async fn lexicon_remove(
+    State(engine): State<TtsEngine>,
+    Query(query): Query<LexiconRemoveQuery>,
+) -> Result<Json<LexiconRemoveResponse>, (StatusCode, Json<ErrorResponse>)> {
+    engine
+        .lexicon()
+        .remove(&query.pattern)
+        .map(|removed| Json(LexiconRemoveResponse { ok: true, removed }))
+        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+// ─── Voices ────────────────────────────────────────────────────────────────
+
+#[derive(serde::Deserialize)]
+struct VoicesQuery {
+    /// `1` or `true` to include a synthesized sample of each voice.
+    #[serde(default)]
+    preview: Option<String>,
+}
+
+async fn list_voices(
+    State(engine): State<TtsEngine>,
+    Query(query): Query<VoicesQuery>,
+) -> Json<Vec<VoiceInfo>> {
+    let mut voices = engine.voice_infos();
+    if matches!(query.preview.as_deref(), Some("1" | "true")) {
+        for voice in &mut voices {
+            match engine.preview_voice(voice).await {
+                Ok(audio) => {
+                    let wav = write_wav(&audio.pcm, audio.sample_rate);
+                    voice.preview = Some(format!("data:audio/wav;base64,{}", BASE64.encode(wav)));
+                }
+                Err(e) => tracing::warn!("preview of {} failed: {e}", voice.name),
+            }
+        }
+    }
+    Json(voices)
+}
+
+#[derive(serde::Deserialize)]
+struct SaveVoiceRequest {
+    name: String,
+    blend: String,
+}
+
+async fn save_voice(
+    State(engine): State<TtsEngine>,
+    Json(req): Json<SaveVoiceRequest>,
+) -> Result<Json<OkResponse>, (StatusCode, Json<ErrorResponse>)> {
+    engine
+        .check_save_voice(&req.name, &req.blend)
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, e))?;
+    // Writing the voice file blocks.
+    tokio::task::spawn_blocking(move || engine.save_voice(&req.name, &req.blend))
+        .await
+        .unwrap_or_else(|e| Err(format!("voice save panicked: {e}")))
+        .map(|()| Json(OkResponse { ok: true }))
+        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+// ─── Output devices ────────────────────────────────────────────────────────
+
+async fn list_devices(
+    State(engine): State<TtsEngine>,
+) -> Result<Json<Vec<AudioDevice>>, (StatusCode, Json<ErrorResponse>)> {
+    // Enumerating devices can block on the audio backend.
+    tokio::task::spawn_blocking(move || engine.output_devices())
+        .await
+        .unwrap_or_else(|e| Err(format!("device listing panicked: {e}")))
+        .map(Json)
+        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+#[derive(serde::Deserialize)]
+struct SetDeviceRequest {
+    /// `None` = the system default.
+    #[serde(default)]
+    name: Option<String>,
+}
+
+async fn set_device(
+    State(engine): State<TtsEngine>,
+    Json(req): Json<SetDeviceRequest>,
+) -> Result<Json<OkResponse>, (StatusCode, Json<ErrorResponse>)> {
+    tokio::task::spawn_blocking(move || engine.set_output_device(req.name.as_deref()))
+        .await
+        .unwrap_or_else(|e| Err(format!("device switch panicked: {e}")))
+        .map(|()| Json(OkResponse { ok: true }))
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, e))
+}
+
+#[derive(serde::Deserialize)]
+struct VolumeRequest {
+    volume: f32,
+}
+
+async fn set_volume(
+    State(engine): State<TtsEngine>,
+    Json(req): Json<VolumeRequest>,
+) -> Result<Json<OkResponse>, (StatusCode, Json<ErrorResponse>)> {
+    engine
+        .set_volume(req.volume)
+        .map(|()| Json(OkResponse { ok: true }))
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, e))
+}
+
+#[derive(serde::Deserialize)]
+struct RateRequest {
+    rate: f32,
+}
+
+async fn set_rate(
+    State(engine): State<TtsEngine>,
+    Json(req): Json<RateRequest>,
+) -> Result<Json<OkResponse>, (StatusCode, Json<ErrorResponse>)> {
+    engine
+        .set_rate(req.rate)
+        .map(|()| Json(OkResponse { ok: true }))
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, e))
+}
+
+#[derive(serde::Deserialize)]
+struct DuckRequest {
+    #[serde(default = "default_duck_level")]
+    level: f32,
+    /// Lift the duck after this long if nobody calls `DELETE /duck`.
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+}
+
+fn default_duck_level() -> f32 {
+    DEFAULT_DUCK_LEVEL
+}
+
+async fn duck(
+    State(engine): State<TtsEngine>,
+    Json(req): Json<DuckRequest>,
+) -> Result<Json<OkResponse>, (StatusCode, Json<ErrorResponse>)> {
+    engine
+        .duck(req.level, req.timeout_ms.map(Duration::from_millis))
+        .map(|()| Json(OkResponse { ok: true }))
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, e))
+}
+
+async fn unduck(State(engine): State<TtsEngine>) -> Json<OkResponse> {
+    engine.unduck();
+    Json(OkResponse { ok: true })
+}
+
+// ─── OpenAI-compatible speech ──────────────────────────────────────────────
+
+/// OpenAI speech request. `model` is accepted but ignored (unknown fields are
+/// skipped) — nayru always uses the loaded Kokoro model.
+#[derive(serde::Deserialize)]
+struct SpeechRequest {
+    input: String,
+    #[serde(default)]
+    voice: Option<String>,
+    #[serde(default)]
+    speed: Option<f32>,
+    #[serde(default = "default_response_format")]
+    response_format: String,
+    /// Tags for formats that carry them (not part of OpenAI's API).
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    artist: Option<String>,
+}
+
+/// `mp3`, as with OpenAI, or `wav` when built without the `mp3` feature.
+fn default_response_format() -> String {
+    if cfg!(feature = "mp3") { "mp3" } else { "wav" }.into()
+}
+
+/// OpenAI-style error body: `{"error": {"message": ..., "type": ...}}`.
+fn openai_error(status: StatusCode, message: String) -> Response {
+    let kind = if status.is_client_error() {
+        "invalid_request_error"
+    } else {
+        "server_error"
+    };
+    (
+        status,
+        Json(serde_json::json!({ "error": { "message": message, "type": kind } })),
+    )
+        .into_response()
+}
+
+async fn audio_speech(
+    State(engine): State<TtsEngine>,
+    Json(req): Json<SpeechRequest>,
+) -> Response {
+    let format = match AudioFormat::parse(&req.response_format) {
+        Ok(f) => f,
+        Err(e) => return openai_error(StatusCode::BAD_REQUEST, e),
+    };
+
+    let options = SpeakOptions {
+        voice: req.voice,
+        speed: req.speed,
+        ..SpeakOptions::default()
+    };
+    if let Err(e) = engine.validate(&options) {
+        return openai_error(StatusCode::BAD_REQUEST, e);
+    }
+
+    let audio = match engine.render(&req.input, &options).await {
+        Ok(audio) => audio,
+        Err(e) => return openai_error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    };
+    let tags = AudioTags {
+        title: req.title,
+        artist: req.artist,
+        voice: Some(options.voice.unwrap_or_else(|| engine.status().voice)),
+    };
+    // Encoding a long render is CPU-bound; keep it off the async workers.
+    let encoded = tokio::task::spawn_blocking(move || {
+        encode(format, &audio.pcm, audio.sample_rate, &tags)
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("encoder panicked: {e}")));
+    match encoded {
+        Ok(bytes) => ([(header::CONTENT_TYPE, format.content_type())], bytes).into_response(),
+        Err(e) => openai_error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
@@ -7,6 +7,10 @@
 //! Once playing, `next()` uses a 10ms recv timeout — if data doesn't arrive
 //! in time it yields a silence sample to keep rodio alive. When `Done` is
 //! received or the sender is dropped, iteration ends.
+//!
+//! Optional `on_start` / `on_finish` hooks fire from the audio thread when the
+//! sink pulls the first sample and when the source ends or is dropped early
+//! (skip/stop). The engine uses them to report playback progress.
 
 use std::collections::VecDeque;
 use std::sync::mpsc::Receiver;
@@ -29,6 +33,9 @@ pub struct StreamingSource {
     channels: u16,
     sample_rate: u32,
     finished: bool,
+    started: bool,
+    on_start: Option<Box<dyn FnOnce() + Send>>,
+    on_finish: Option<Box<dyn FnOnce(bool) + Send>>,
 }
 
 impl StreamingSource {
@@ -44,9 +51,26 @@ impl StreamingSource {
             channels,
             sample_rate,
             finished: false,
+            started: false,
+            on_start: None,
+            on_finish: None,
         }
     }
 
+    /// Call `f` when the sink pulls the first sample.
+    pub fn on_start(mut self, f: impl FnOnce() + Send + 'static) -> Self {
+        self.on_start = Some(Box::new(f));
+        self
+    }
+
+    /// Call `f` once playback ends. The argument is `true` if the source was
+    /// dropped mid-playback (skip/stop) rather than played to the end.
+    /// Never fires for a source that was dropped before it started.
+    pub fn on_finish(mut self, f: impl FnOnce(bool) + Send + 'static) -> Self {
+        self.on_finish = Some(Box::new(f));
+        self
+    }
+
     /// Try to fill the buffer from the channel.
     fn fill_buffer(&mut self) {
         // Drain all immediately available chunks
@@ -78,6 +102,27 @@ impl Iterator for StreamingSource {
     type Item = i16;
 
     fn next(&mut self) -> Option<i16> {
+        let sample = self.next_sample();
+        match sample {
+            Some(_) if !self.started => {
+                self.started = true;
+                if let Some(f) = self.on_start.take() {
+                    f();
+                }
+            }
+            Some(_) => {}
+            None => {
+                if let Some(f) = self.on_finish.take() {
+                    f(false);
+                }
+            }
+        }
+        sample
+    }
+}
+
+impl StreamingSource {
+    fn next_sample(&mut self) -> Option<i16> {
         if let Some(sample) = self.buffer.pop_front() {
             return Some(sample);
         }
@@ -99,6 +144,17 @@ impl Iterator for StreamingSource {
     }
 }
 
+impl Drop for StreamingSource {
+    fn drop(&mut self) {
+        if !self.started {
+            return;
+        }
+        if let Some(f) = self.on_finish.take() {
+            f(true);
+        }
+    }
+}
+
 impl Source for StreamingSource {
     fn current_frame_len(&self) -> Option<usize> {
         if self.finished && self.buffer.is_empty() {
@@ -126,7 +182,7 @@ impl Source for StreamingSource {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::sync::mpsc;
+    use std::sync::{mpsc, Arc, Mutex};
 
     #[test]
     fn streams_data_then_finishes() {
@@ -153,6 +209,50 @@ mod tests {
         assert_eq!(samples, vec![42]);
     }
 
+    #[test]
+    fn hooks_fire_on_start_and_end() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let (tx, rx) = mpsc::channel();
+        let (e1, e2) = (events.clone(), events.clone());
+        let mut source = StreamingSource::new(rx, 1, 24000)
+            .on_start(move || e1.lock().unwrap().push("start".to_string()))
+            .on_finish(move |interrupted| e2.lock().unwrap().push(format!("finish:{interrupted}")));
+
+        tx.send(PcmChunk::Data(vec![1, 2])).unwrap();
+        tx.send(PcmChunk::Done).unwrap();
+        let _: Vec<i16> = source.by_ref().collect();
+        drop(source);
+
+        assert_eq!(*events.lock().unwrap(), vec!["start", "finish:false"]);
+    }
+
+    #[test]
+    fn drop_mid_playback_reports_interrupted() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let (tx, rx) = mpsc::channel();
+        let e = events.clone();
+        let mut source = StreamingSource::new(rx, 1, 24000)
+            .on_finish(move |interrupted| e.lock().unwrap().push(interrupted));
+
+        tx.send(PcmChunk::Data(vec![1, 2, 3])).unwrap();
+        assert_eq!(source.next(), Some(1));
+        drop(source);
+
+        assert_eq!(*events.lock().unwrap(), vec![true]);
+    }
+
+    #[test]
+    fn drop_before_start_is_silent() {
+        let events = Arc::new(Mutex::new(Vec::<bool>::new()));
+        let (_tx, rx) = mpsc::channel();
+        let e = events.clone();
+        let source = StreamingSource::new(rx, 1, 24000)
+            .on_finish(move |interrupted| e.lock().unwrap().push(interrupted));
+        drop(source);
+
+        assert!(events.lock().unwrap().is_empty());
+    }
+
     #[test]
     fn reports_correct_format() {
         let (_tx, rx) = mpsc::channel();
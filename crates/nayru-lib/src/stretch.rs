@@ -0,0 +1,229 @@
+//! Pitch-preserving time-stretch (WSOLA) for live playback rate changes.
+//!
+//! Kokoro's `speed` input sounds best, but only applies to speech not yet
+//! synthesized. The playback rate is applied to clips as they're pulled, so
+//! a change is heard immediately, queued clips included. It isn't folded
+//! into `speed` for clips still to be synthesized: their pace would then
+//! depend on the rate at synthesis time, so a later change would leave
+//! queued clips mismatched, and each rate would miss the synthesis cache.
+//!
+//! WSOLA (waveform-similarity overlap-add) cuts the input into Hann-windowed
+//! frames and overlap-adds them at half a frame apart. The frames are read
+//! `rate` times further apart than they're written, and each one is nudged
+//! (within [`TOLERANCE_SECS`]) to where it best lines up with the waveform
+//! the previous frame would have continued into, so pitch periods join
+//! smoothly instead of phasing. At rate 1.0 the frames land exactly where
+//! they were and the output is the input.
+
+use std::collections::VecDeque;
+
+/// Slowest playback rate.
+pub const MIN_RATE: f32 = 0.5;
+
+/// Fastest playback rate.
+pub const MAX_RATE: f32 = 3.0;
+
+/// Frame length: a few pitch periods of speech.
+const WINDOW_SECS: f32 = 0.025;
+
+/// How far a frame may move from its nominal position to line up.
+const TOLERANCE_SECS: f32 = 0.0075;
+
+/// Streaming WSOLA over mono samples at one sample rate.
+pub struct Stretcher {
+    window: usize,
+    /// Output hop: half the window.
+    hop: usize,
+    tolerance: usize,
+    /// Periodic Hann window; `hann[i] + hann[i + hop] == 1`.
+    hann: Vec<f32>,
+    /// Buffered input; `input[0]` is input sample number `base`.
+    input: Vec<f32>,
+    base: usize,
+    /// Input samples that came from the source; past this is zero padding.
+    input_len: Option<usize>,
+    /// Nominal input position of the next frame.
+    pos: f64,
+    /// Input position of the previous frame.
+    prev: Option<usize>,
+    /// Second half of the previous frame, to overlap the next one.
+    tail: Vec<f32>,
+    out: VecDeque<i16>,
+    done: bool,
+}
+
+impl Stretcher {
+    pub fn new(sample_rate: u32) -> Self {
+        // Even, so the window splits into two equal hops.
+        let window = ((WINDOW_SECS * sample_rate as f32) as usize / 2 * 2).max(2);
+        let hop = window / 2;
+        let hann = (0..window)
+            .map(|i| 0.5 - 0.5 * (std::f32::consts::TAU * i as f32 / window as f32).cos())
+            .collect();
+        Self {
+            window,
+            hop,
+            tolerance: (TOLERANCE_SECS * sample_rate as f32) as usize,
+            hann,
+            input: Vec::new(),
+            base: 0,
+            input_len: None,
+            pos: 0.0,
+            prev: None,
+            tail: Vec::new(),
+            out: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// The next output sample at `rate`, pulling from `input` as needed.
+    /// `None` once `input` has ended and everything read from it is out.
+    pub fn next(&mut self, rate: f32, input: &mut impl Iterator<Item = i16>) -> Option<i16> {
+        while self.out.is_empty() && !self.done {
+            self.step(rate as f64, input);
+        }
+        self.out.pop_front()
+    }
+
+    /// Write one hop of output.
+    fn step(&mut self, rate: f64, input: &mut impl Iterator<Item = i16>) {
+        let nominal = self.pos.round() as usize;
+        let (lo, hi) = match self.prev {
+            Some(_) => (
+                nominal.saturating_sub(self.tolerance).max(self.base),
+                nominal + self.tolerance,
+            ),
+            None => (nominal, nominal),
+        };
+        // Where the previous frame's waveform carries on to.
+        let natural = self.prev.map(|prev| prev + self.hop);
+        self.fill(hi.max(natural.unwrap_or(0)) + self.window, input);
+
+        if self.input_len.is_some_and(|len| nominal >= len) {
+            // Fade out the last frame and stop.
+            self.out.extend(self.tail.drain(..).map(to_i16));
+            self.done = true;
+            return;
+        }
+
+        let start = match natural {
+            Some(natural) => self.best_match(lo, hi, nominal, natural),
+            None => nominal,
+        };
+        let frame = &self.input[start - self.base..start - self.base + self.window];
+        if self.tail.is_empty() {
+            // First frame: nothing to overlap, so let its first half through
+            // at full level rather than fading in.
+            self.tail = (0..self.hop).map(|i| frame[i] * self.hann[i + self.hop]).collect();
+        }
+        for i in 0..self.hop {
+            self.out.push_back(to_i16(self.tail[i] + frame[i] * self.hann[i]));
+            self.tail[i] = frame[i + self.hop] * self.hann[i + self.hop];
+        }
+
+        self.prev = Some(start);
+        self.pos += self.hop as f64 * rate;
+
+        // Nothing before the next search range or continuation is needed.
+        let keep = ((self.pos.round() as usize).saturating_sub(self.tolerance))
+            .min(start + self.hop)
+            .max(self.base);
+        self.input.drain(..keep - self.base);
+        self.base = keep;
+    }
+
+    /// Buffer input up to (not including) sample `end`, zero-padding once
+    /// the source runs out.
+    fn fill(&mut self, end: usize, input: &mut impl Iterator<Item = i16>) {
+        while self.base + self.input.len() < end {
+            match self.input_len {
+                Some(_) => self.input.push(0.0),
+                None => match input.next() {
+                    Some(sample) => self.input.push(sample as f32),
+                    None => self.input_len = Some(self.base + self.input.len()),
+                },
+            }
+        }
+    }
+
+    /// Frame start in `lo..=hi` whose waveform best matches the one starting
+    /// at `natural`, by normalized cross-correlation. Ties go to `nominal`.
+    fn best_match(&self, lo: usize, hi: usize, nominal: usize, natural: usize) -> usize {
+        let at = |start: usize| &self.input[start - self.base..start - self.base + self.window];
+        let target = at(natural);
+        // Every other sample is plenty for speech, and halves the work.
+        let score = |start: usize| -> f32 {
+            let (dot, energy) = at(start)
+                .iter()
+                .zip(target)
+                .step_by(2)
+                .fold((0.0, 0.0), |(dot, energy), (a, b)| (dot + a * b, energy + a * a));
+            if energy > 0.0 { dot / energy.sqrt() } else { 0.0 }
+        };
+        let nominal = nominal.clamp(lo, hi);
+        let mut best = (nominal, score(nominal));
+        for start in (lo..=hi).step_by(2) {
+            let s = score(start);
+            if s > best.1 {
+                best = (start, s);
+            }
+        }
+        best.0
+    }
+}
+
+fn to_i16(sample: f32) -> i16 {
+    sample.round().clamp(i16::MIN as f32, i16::MAX as f32) as i16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(freq: f32, secs: f32, rate: u32) -> Vec<i16> {
+        (0..(secs * rate as f32) as usize)
+            .map(|i| (8_000.0 * (std::f32::consts::TAU * freq * i as f32 / rate as f32).sin()) as i16)
+            .collect()
+    }
+
+    fn stretch(samples: &[i16], rate: f32) -> Vec<i16> {
+        let mut stretcher = Stretcher::new(24_000);
+        let mut input = samples.iter().copied();
+        std::iter::from_fn(|| stretcher.next(rate, &mut input)).collect()
+    }
+
+    fn crossings(samples: &[i16]) -> usize {
+        samples.windows(2).filter(|w| (w[0] < 0) != (w[1] < 0)).count()
+    }
+
+    #[test]
+    fn unit_rate_passes_audio_through() {
+        let input = sine(220.0, 0.5, 24_000);
+        let output = stretch(&input, 1.0);
+        // The end is padded out to a whole hop.
+        assert!(output.len() >= input.len() && output.len() < input.len() + 600);
+        for (a, b) in input.iter().zip(&output) {
+            assert!((a - b).abs() <= 1, "{a} vs {b}");
+        }
+    }
+
+    #[test]
+    fn length_follows_rate_and_pitch_stays() {
+        let input = sine(300.0, 2.0, 24_000);
+        let input_rate = crossings(&input) as f32 / input.len() as f32;
+        for rate in [MIN_RATE, 1.5, 2.0, MAX_RATE] {
+            let output = stretch(&input, rate);
+            let expected = input.len() as f32 / rate;
+            assert!((output.len() as f32 - expected).abs() < 1_000.0, "{rate}: {}", output.len());
+            // Same frequency: zero crossings per sample match the input.
+            let body = &output[600..output.len() - 600];
+            let output_rate = crossings(body) as f32 / body.len() as f32;
+            assert!((output_rate / input_rate - 1.0).abs() < 0.03, "{rate}: {output_rate}");
+        }
+    }
+
+    #[test]
+    fn empty_input_ends_at_once() {
+        assert!(stretch(&[], 2.0).is_empty());
+    }
+}
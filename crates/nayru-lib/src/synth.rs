@@ -0,0 +1,136 @@
+//! Pluggable synthesis backends.
+//!
+//! [`TtsEngine`](crate::tts::TtsEngine) drives any [`Synthesizer`]:
+//!
+//! - [`KokoroSynth`](crate::kokoro::KokoroSynth) — in-process Kokoro ONNX.
+//! - [`HttpSynth`](crate::http_synth::HttpSynth) — an OpenAI-compatible
+//!   `/v1/audio/speech` server (Kokoros, Kokoro-FastAPI, another nayru).
+//! - [`MockSynth`](crate::mock_synth::MockSynth) — deterministic tones, for
+//!   tests and machines without the model.
+
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+
+use nayru_core::timing::WordTiming;
+use nayru_core::types::{CacheStats, VoiceKind};
+
+/// One synthesized clip.
+#[derive(Debug, Clone)]
+pub struct Synthesis {
+    /// Mono samples in [-1.0, 1.0].
+    pub samples: Vec<f32>,
+    /// Sample rate of `samples`, in Hz.
+    pub sample_rate: u32,
+    /// Inference time, excluding phonemization.
+    pub took: Duration,
+    /// When each whitespace-separated word of the input is spoken.
+    pub words: Vec<WordTiming>,
+}
+
+impl Synthesis {
+    /// Length of the clip.
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.samples.len() as f64 / self.sample_rate as f64)
+    }
+}
+
+/// A piece of one clip's input.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpeechPart {
+    /// Text, phonemized by the backend.
+    Text(String),
+    /// IPA passed straight to the model (SSML `<phoneme>`).
+    Ipa(String),
+}
+
+/// A text-to-speech backend.
+///
+/// Methods return boxed futures so the engine can hold any backend as
+/// `Arc<dyn Synthesizer>`.
+pub trait Synthesizer: Send + Sync {
+    /// Synthesize `text` in `voice` at `speed` (1.0 = normal).
+    ///
+    /// `language` is an espeak-ng language code; backends that don't
+    /// phonemize themselves may ignore it.
+    fn synth<'a>(
+        &'a self,
+        text: &'a str,
+        voice: &'a str,
+        speed: f32,
+        language: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Synthesis, String>>;
+
+    /// Synthesize a clip made of text and IPA parts. `text` is what the clip
+    /// reads as.
+    ///
+    /// The default ignores the IPA and speaks `text`, for backends that only
+    /// accept plain text.
+    fn synth_parts<'a>(
+        &'a self,
+        parts: &'a [SpeechPart],
+        text: &'a str,
+        voice: &'a str,
+        speed: f32,
+        language: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Synthesis, String>> {
+        let _ = parts;
+        self.synth(text, voice, speed, language)
+    }
+
+    /// Names of all voices this backend can speak with, sorted.
+    fn voices(&self) -> Vec<String>;
+
+    /// Check that `voice` is one of [`voices`](Self::voices).
+    fn validate_voice(&self, voice: &str) -> Result<(), String> {
+        let voices = self.voices();
+        if voices.iter().any(|v| v == voice) {
+            return Ok(());
+        }
+        Err(unknown_voice(voice, &voices))
+    }
+
+    /// Where `voice` comes from. The default treats every voice as built in.
+    fn voice_kind(&self, voice: &str) -> VoiceKind {
+        let _ = voice;
+        VoiceKind::Builtin
+    }
+
+    /// Save a voice or weighted blend (`af_heart:0.6+bf_emma:0.4`) as a
+    /// custom voice called `name`, usable wherever a voice name is.
+    fn save_voice(&self, name: &str, blend: &str) -> Result<(), String> {
+        let _ = (name, blend);
+        Err("this backend doesn't support custom voices".to_string())
+    }
+
+    /// Check that [`save_voice`](Self::save_voice) would accept `name` and
+    /// `blend`, so callers can tell bad requests from filesystem errors.
+    fn check_save_voice(&self, name: &str, blend: &str) -> Result<(), String> {
+        let _ = (name, blend);
+        Err("this backend doesn't support custom voices".to_string())
+    }
+
+    /// Identifies the model and `voice` behind this backend for synthesis
+    /// cache keys, so swapping models or saving over a voice doesn't replay
+    /// stale audio. `None` (the default) means output can't be cached.
+    fn cache_id(&self, voice: &str) -> Option<String> {
+        let _ = voice;
+        None
+    }
+
+    /// Cache counters, for backends wrapped in a
+    /// [`CachedSynth`](crate::synth_cache::CachedSynth).
+    fn cache_stats(&self) -> Option<CacheStats> {
+        None
+    }
+}
+
+/// Error for a voice name the backend doesn't have, listing the valid ones.
+pub(crate) fn unknown_voice(voice: &str, valid: &[impl AsRef<str>]) -> String {
+    let names: Vec<&str> = valid.iter().map(AsRef::as_ref).collect();
+    format!(
+        "unknown voice '{}'; valid voices: {}",
+        voice,
+        names.join(", ")
+    )
+}
@@ -0,0 +1,583 @@
+//! Content-addressed synthesis cache on disk.
+//!
+//! Replaying the same notification or paragraph shouldn't re-run espeak-ng
+//! and ONNX inference. [`CachedSynth`] wraps a backend and keys each clip by
+//! a SHA-256 of its normalized input, voice, speed, language, and the
+//! backend's [`cache_id`](Synthesizer::cache_id) (which names the model
+//! files and the voice's contents). Clips are stored as deflated 16-bit PCM
+//! plus word timings, one file per key, and the least recently used are
+//! evicted past a size cap.
+//!
+//! Backends without a `cache_id` (e.g. a remote server whose model may
+//! change under us) are passed through uncached.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use futures_util::future::BoxFuture;
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+
+use nayru_core::timing::WordTiming;
+use nayru_core::types::{CacheStats, VoiceKind};
+
+use crate::synth::{SpeechPart, Synthesis, Synthesizer};
+
+/// Default size cap: 256 MiB, roughly 6 hours of deflated speech.
+pub const DEFAULT_MAX_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Cache file extension.
+const EXTENSION: &str = "clip";
+
+/// Where the cache lives by default (e.g. `~/.local/share/nayru/synth-cache`).
+pub fn default_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("nayru").join("synth-cache"))
+}
+
+/// Identifies a file's contents cheaply: name, size, and modification time.
+/// Used in [`Synthesizer::cache_id`] so replacing a model misses the cache.
+pub fn file_fingerprint(path: &Path) -> String {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let Ok(meta) = std::fs::metadata(path) else {
+        return name.into_owned();
+    };
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs());
+    format!("{name}:{}:{modified}", meta.len())
+}
+
+/// Cache key for one clip. Whitespace in text parts is collapsed so
+/// reflowed paragraphs still hit.
+pub fn cache_key(
+    cache_id: &str,
+    parts: &[SpeechPart],
+    text: &str,
+    voice: &str,
+    speed: f32,
+    language: Option<&str>,
+) -> String {
+    let normalize = |s: &str| s.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut hasher = Sha256::new();
+    for field in [cache_id, voice, &format!("{speed:.3}"), language.unwrap_or("")] {
+        hasher.update(field.as_bytes());
+        hasher.update([0]);
+    }
+    hasher.update(normalize(text).as_bytes());
+    for part in parts {
+        let (tag, body) = match part {
+            SpeechPart::Text(t) => (b't', normalize(t)),
+            SpeechPart::Ipa(ipa) => (b'i', ipa.clone()),
+        };
+        hasher.update([0, tag]);
+        hasher.update(body.as_bytes());
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// What a cache file holds, before compression.
+#[derive(bincode::Encode, bincode::Decode)]
+struct Entry {
+    sample_rate: u32,
+    pcm: Vec<i16>,
+    words: Vec<(String, f32, f32)>,
+}
+
+impl Entry {
+    fn from_synthesis(synthesis: &Synthesis) -> Self {
+        Self {
+            sample_rate: synthesis.sample_rate,
+            pcm: synthesis
+                .samples
+                .iter()
+                .map(|&s| (s * 32767.0).round().clamp(-32768.0, 32767.0) as i16)
+                .collect(),
+            words: synthesis
+                .words
+                .iter()
+                .map(|w| (w.word.clone(), w.start, w.end))
+                .collect(),
+        }
+    }
+
+    fn into_synthesis(self, took: std::time::Duration) -> Synthesis {
+        Synthesis {
+            samples: self.pcm.iter().map(|&s| s as f32 / 32767.0).collect(),
+            sample_rate: self.sample_rate,
+            took,
+            words: self
+                .words
+                .into_iter()
+                .map(|(word, start, end)| WordTiming { word, start, end })
+                .collect(),
+        }
+    }
+
+    fn encode(&self) -> Result<Vec<u8>, String> {
+        let raw = bincode::encode_to_vec(self, bincode::config::standard())
+            .map_err(|e| format!("encode clip: {e}"))?;
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(&raw).map_err(|e| e.to_string())?;
+        encoder.finish().map_err(|e| e.to_string())
+    }
+
+    fn decode(data: &[u8]) -> Result<Self, String> {
+        let mut raw = Vec::new();
+        DeflateDecoder::new(data)
+            .read_to_end(&mut raw)
+            .map_err(|e| format!("inflate clip: {e}"))?;
+        let (entry, _) = bincode::decode_from_slice(&raw, bincode::config::standard())
+            .map_err(|e| format!("decode clip: {e}"))?;
+        Ok(entry)
+    }
+}
+
+struct Slot {
+    bytes: u64,
+    /// Higher = used more recently.
+    used: u64,
+}
+
+#[derive(Default)]
+struct Index {
+    slots: HashMap<String, Slot>,
+    bytes: u64,
+    clock: u64,
+}
+
+impl Index {
+    fn touch(&mut self, key: &str) -> bool {
+        self.clock += 1;
+        match self.slots.get_mut(key) {
+            Some(slot) => {
+                slot.used = self.clock;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn insert(&mut self, key: String, bytes: u64) {
+        self.clock += 1;
+        let slot = Slot {
+            bytes,
+            used: self.clock,
+        };
+        if let Some(old) = self.slots.insert(key, slot) {
+            self.bytes -= old.bytes;
+        }
+        self.bytes += bytes;
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(slot) = self.slots.remove(key) {
+            self.bytes -= slot.bytes;
+        }
+    }
+
+    /// Least recently used keys to drop to get under `max_bytes`.
+    fn evictions(&self, max_bytes: u64) -> Vec<String> {
+        if self.bytes <= max_bytes {
+            return Vec::new();
+        }
+        let mut by_age: Vec<(&String, &Slot)> = self.slots.iter().collect();
+        by_age.sort_by_key(|(_, slot)| slot.used);
+        let mut excess = self.bytes - max_bytes;
+        let mut keys = Vec::new();
+        for (key, slot) in by_age {
+            if excess == 0 {
+                break;
+            }
+            excess = excess.saturating_sub(slot.bytes);
+            keys.push(key.clone());
+        }
+        keys
+    }
+}
+
+/// Clip files in a directory, with LRU eviction past a size cap.
+///
+/// Recency survives restarts through file modification times, which are
+/// bumped on every hit.
+pub struct SynthCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    index: Mutex<Index>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl SynthCache {
+    /// Open (creating if needed) the cache in `dir`.
+    pub fn open(dir: &Path, max_bytes: u64) -> Result<Self, String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("create {}: {e}", dir.display()))?;
+        let mut files: Vec<(String, u64, SystemTime)> = std::fs::read_dir(dir)
+            .map_err(|e| format!("read {}: {e}", dir.display()))?
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension().is_none_or(|ext| ext != EXTENSION) {
+                    return None;
+                }
+                let key = path.file_stem()?.to_str()?.to_string();
+                let meta = entry.metadata().ok()?;
+                Some((key, meta.len(), meta.modified().unwrap_or(UNIX_EPOCH)))
+            })
+            .collect();
+        files.sort_by_key(|(_, _, modified)| *modified);
+
+        let mut index = Index::default();
+        for (key, bytes, _) in files {
+            index.insert(key, bytes);
+        }
+        debug!(
+            "synth cache: {} clips, {} bytes in {}",
+            index.slots.len(),
+            index.bytes,
+            dir.display()
+        );
+
+        let cache = Self {
+            dir: dir.to_path_buf(),
+            max_bytes,
+            index: Mutex::new(index),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        };
+        cache.evict();
+        Ok(cache)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.{EXTENSION}"))
+    }
+
+    /// The clip stored under `key`, counting a hit or miss.
+    pub fn get(&self, key: &str) -> Option<Synthesis> {
+        let started = Instant::now();
+        let found = self.index.lock().unwrap().touch(key);
+        let entry = found.then(|| self.read(key)).flatten();
+        match entry {
+            Some(entry) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(entry.into_synthesis(started.elapsed()))
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    fn read(&self, key: &str) -> Option<Entry> {
+        let path = self.path(key);
+        let result = std::fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|data| Entry::decode(&data));
+        match result {
+            Ok(entry) => {
+                // Persist recency for the next startup; failure only costs order.
+                let _ = std::fs::File::options()
+                    .write(true)
+                    .open(&path)
+                    .and_then(|f| f.set_modified(SystemTime::now()));
+                Some(entry)
+            }
+            Err(e) => {
+                // Cleared behind our back, or corrupt: forget it.
+                debug!("synth cache: dropping {key}: {e}");
+                self.index.lock().unwrap().remove(key);
+                let _ = std::fs::remove_file(&path);
+                None
+            }
+        }
+    }
+
+    /// Store a clip under `key`, evicting old clips past the size cap.
+    pub fn put(&self, key: &str, synthesis: &Synthesis) -> Result<(), String> {
+        let data = Entry::from_synthesis(synthesis).encode()?;
+        let path = self.path(key);
+        // Write then rename so a concurrent reader never sees half a file.
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, &data).map_err(|e| format!("write {}: {e}", tmp.display()))?;
+        std::fs::rename(&tmp, &path).map_err(|e| format!("rename {}: {e}", path.display()))?;
+        self.index
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), data.len() as u64);
+        self.evict();
+        Ok(())
+    }
+
+    fn evict(&self) {
+        let mut index = self.index.lock().unwrap();
+        for key in index.evictions(self.max_bytes) {
+            let _ = std::fs::remove_file(self.path(&key));
+            index.remove(&key);
+        }
+    }
+
+    /// Delete every clip. Returns how many were removed.
+    pub fn clear(&self) -> Result<usize, String> {
+        let mut index = self.index.lock().unwrap();
+        let removed = clear_dir(&self.dir)?;
+        *index = Index::default();
+        Ok(removed)
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let index = self.index.lock().unwrap();
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: index.slots.len(),
+            bytes: index.bytes,
+        }
+    }
+}
+
+/// Delete every clip file in `dir` without opening a cache over it. A
+/// missing directory has nothing to clear.
+pub fn clear_dir(dir: &Path) -> Result<usize, String> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(format!("read {}: {e}", dir.display())),
+    };
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == EXTENSION) {
+            std::fs::remove_file(&path).map_err(|e| format!("remove {}: {e}", path.display()))?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// A [`Synthesizer`] that answers from a [`SynthCache`] when it can.
+pub struct CachedSynth {
+    inner: Arc<dyn Synthesizer>,
+    cache: Arc<SynthCache>,
+}
+
+impl CachedSynth {
+    pub fn new(inner: Arc<dyn Synthesizer>, cache: Arc<SynthCache>) -> Self {
+        Self { inner, cache }
+    }
+
+    async fn cached<'a>(
+        &'a self,
+        parts: &'a [SpeechPart],
+        text: &'a str,
+        voice: &'a str,
+        speed: f32,
+        language: Option<&'a str>,
+    ) -> Result<Synthesis, String> {
+        let Some(cache_id) = self.inner.cache_id(voice) else {
+            return self.inner.synth_parts(parts, text, voice, speed, language).await;
+        };
+        let key = cache_key(&cache_id, parts, text, voice, speed, language);
+        let (cache, lookup) = (self.cache.clone(), key.clone());
+        let found = tokio::task::spawn_blocking(move || cache.get(&lookup))
+            .await
+            .ok()
+            .flatten();
+        if let Some(synthesis) = found {
+            debug!("synth cache: hit {key}");
+            return Ok(synthesis);
+        }
+
+        let synthesis = self
+            .inner
+            .synth_parts(parts, text, voice, speed, language)
+            .await?;
+        if !synthesis.samples.is_empty() {
+            let (cache, stored) = (self.cache.clone(), synthesis.clone());
+            tokio::task::spawn_blocking(move || {
+                if let Err(e) = cache.put(&key, &stored) {
+                    warn!("synth cache: {e}");
+                }
+            });
+        }
+        Ok(synthesis)
+    }
+}
+
+impl Synthesizer for CachedSynth {
+    fn synth<'a>(
+        &'a self,
+        text: &'a str,
+        voice: &'a str,
+        speed: f32,
+        language: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Synthesis, String>> {
+        Box::pin(async move {
+            let parts = [SpeechPart::Text(text.to_string())];
+            self.cached(&parts, text, voice, speed, language).await
+        })
+    }
+
+    fn synth_parts<'a>(
+        &'a self,
+        parts: &'a [SpeechPart],
+        text: &'a str,
+        voice: &'a str,
+        speed: f32,
+        language: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Synthesis, String>> {
+        Box::pin(self.cached(parts, text, voice, speed, language))
+    }
+
+    fn voices(&self) -> Vec<String> {
+        self.inner.voices()
+    }
+
+    fn validate_voice(&self, voice: &str) -> Result<(), String> {
+        self.inner.validate_voice(voice)
+    }
+
+    fn voice_kind(&self, voice: &str) -> VoiceKind {
+        self.inner.voice_kind(voice)
+    }
+
+    fn save_voice(&self, name: &str, blend: &str) -> Result<(), String> {
+        self.inner.save_voice(name, blend)
+    }
+
+    fn check_save_voice(&self, name: &str, blend: &str) -> Result<(), String> {
+        self.inner.check_save_voice(name, blend)
+    }
+
+    fn cache_id(&self, voice: &str) -> Option<String> {
+        self.inner.cache_id(voice)
+    }
+
+    fn cache_stats(&self) -> Option<CacheStats> {
+        Some(self.cache.stats())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_synth::MockSynth;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("nayru-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn text(s: &str) -> Vec<SpeechPart> {
+        vec![SpeechPart::Text(s.into())]
+    }
+
+    #[test]
+    fn keys_normalize_whitespace_and_separate_inputs() {
+        let key = |t: &str, voice: &str, speed: f32| {
+            cache_key("m", &text(t), t, voice, speed, Some("en-us"))
+        };
+        assert_eq!(key("Hello  there.", "af_heart", 1.0), key("Hello\nthere.", "af_heart", 1.0));
+        assert_ne!(key("Hello there.", "af_heart", 1.0), key("Hello there.", "bf_emma", 1.0));
+        assert_ne!(key("Hello there.", "af_heart", 1.0), key("Hello there.", "af_heart", 1.1));
+        assert_ne!(
+            cache_key("m", &text("hi"), "hi", "v", 1.0, None),
+            cache_key("other-model", &text("hi"), "hi", "v", 1.0, None)
+        );
+        assert_eq!(key("x", "v", 1.0).len(), 64);
+    }
+
+    #[test]
+    fn round_trip_and_counts() {
+        let tmp = TempDir::new("cache-roundtrip");
+        let cache = SynthCache::open(&tmp.0, DEFAULT_MAX_BYTES).unwrap();
+        let clip = MockSynth::default().render("Hello there.", "af_heart", 1.0);
+
+        assert!(cache.get("k").is_none());
+        cache.put("k", &clip).unwrap();
+        let hit = cache.get("k").unwrap();
+        assert_eq!(hit.sample_rate, clip.sample_rate);
+        assert_eq!(hit.samples.len(), clip.samples.len());
+        assert!(hit.samples.iter().zip(&clip.samples).all(|(a, b)| (a - b).abs() < 1e-4));
+        assert_eq!(hit.words, clip.words);
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+        assert!(stats.bytes > 0);
+
+        // Reopening finds the clip on disk.
+        let reopened = SynthCache::open(&tmp.0, DEFAULT_MAX_BYTES).unwrap();
+        assert!(reopened.get("k").is_some());
+        assert_eq!(reopened.clear().unwrap(), 1);
+        assert!(reopened.get("k").is_none());
+        assert_eq!(reopened.stats().entries, 0);
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let tmp = TempDir::new("cache-evict");
+        let mock = MockSynth::default();
+        let clip = mock.render("One two three four.", "af_heart", 1.0);
+        let size = Entry::from_synthesis(&clip).encode().unwrap().len() as u64;
+
+        let cache = SynthCache::open(&tmp.0, size * 2).unwrap();
+        cache.put("a", &clip).unwrap();
+        cache.put("b", &clip).unwrap();
+        assert!(cache.get("a").is_some());
+        cache.put("c", &clip).unwrap();
+
+        assert!(cache.get("b").is_none(), "b was least recently used");
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("c").is_some());
+        assert!(cache.stats().bytes <= size * 2);
+    }
+
+    #[tokio::test]
+    async fn cached_synth_skips_the_backend_on_hit() {
+        let tmp = TempDir::new("cache-synth");
+        let cache = Arc::new(SynthCache::open(&tmp.0, DEFAULT_MAX_BYTES).unwrap());
+        let synth = CachedSynth::new(Arc::new(MockSynth::default()), cache.clone());
+
+        let first = synth.synth("Hello there.", "af_heart", 1.0, None).await.unwrap();
+        // The store happens off-thread; wait for it to land.
+        for _ in 0..100 {
+            if cache.stats().entries == 1 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let second = synth.synth("Hello  there.", "af_heart", 1.0, None).await.unwrap();
+        assert_eq!(first.samples.len(), second.samples.len());
+
+        let stats = synth.cache_stats().unwrap();
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+    }
+}
@@ -4,7 +4,7 @@
 //!
 //! ```text
 //! speak("text") → [cmd_tx] → text_processor: split sentences
-//!     → [fetch_tx] → fetcher_0: KokoroTts::synth(), create source
+//!     → [fetch_tx] → fetcher_0: Synthesizer::synth(), create source
 //!     → [fetch_tx] → fetcher_1: (prefetch) synth concurrently
 //!     → playback thread: gapless sequential playback
 //! ```
@@ -16,30 +16,90 @@
 //!
 //! Sentences are dispatched individually (no merging) to minimize time-to-first-audio.
 //!
+//! Synthesis goes through the [`Synthesizer`] trait: in-process Kokoro in
+//! production, a remote server or [`MockSynth`](crate::mock_synth::MockSynth)
+//! elsewhere. Clips play at whatever sample rate the backend returns.
+//!
 //! Epoch-based cancellation: `stop()` bumps an [`AtomicU64`] so all in-flight
 //! work for the previous epoch is silently discarded.
 //!
+//! **Live settings:** the config sits in a `watch` channel that the processor
+//! and fetchers read per job. [`TtsEngine::update_config`] also bumps the
+//! epoch to retire clips that haven't started, and the processor re-sends
+//! their jobs (it keeps each until its clip starts) so they're heard with
+//! the new settings.
+//!
+//! **Events:** status changes go out on a `watch` channel; clip start/finish,
+//! synthesis errors, and pause/resume go out on a `broadcast` channel.
+//! [`TtsEngine::events`] merges both into one stream for `/events`.
+//!
+//! **SSML:** [`TtsEngine::speak_ssml`] turns markup into sentences that carry
+//! their own voice/speed/language, optional pre-phonemized IPA, and silence
+//! padding for `<break>`s. Pauses are padded onto neighbouring clips rather
+//! than queued as separate jobs, so they can't overtake the speech around them.
+//!
 //! **Streaming API:** For LLM streaming, use `stream_chunk()` / `stream_end()`
 //! instead of `speak()`. The text_processor accumulates chunks, extracts complete
 //! sentences as they arrive, and dispatches them through the same fetch pipeline —
 //! one continuous epoch, gapless playback.
+//!
+//! **Remote listeners:** with [`AudioOutput::Remote`] or [`AudioOutput::Both`],
+//! every clip is also published on an [`AudioTap`] as it plays (see
+//! [`audio_stream`](crate::audio_stream)).
+//!
+//! **History:** fetchers hand the playback thread each clip's PCM, and the
+//! thread keeps clips that have played in a [`History`] (the last five
+//! minutes or so). [`TtsEngine::replay_last`], [`TtsEngine::previous`], and
+//! [`TtsEngine::rewind`] queue clips from it again, ahead of the rest.
+//!
+//! **Priority speech:** an utterance with [`SpeakOptions::priority`] skips
+//! the job pipeline: a task of its own synthesizes it sentence by sentence,
+//! and the playback thread mixes each clip over the queue through an
+//! [`Overlay`], ducking the queue until the last one ends.
+//!
+//! **Shutdown:** [`TtsEngine::shutdown`] ends the processor (which drops the
+//! job channel, so the fetchers run out of work and stop) and then the
+//! playback thread, after it has either played out the queue or faded out.
+//!
+//! **Sinks:** the playback thread keeps the queue of clips (for skip and
+//! stop) and hands shared [`Clip`] handles to an [`AudioSink`]: an output
+//! device, a null sink paced by the wall clock (`Remote`, `Null`, or no
+//! device present), or a tee that also records to WAV. See
+//! [`audio_sink`](crate::audio_sink). Pause/skip/stop and progress events
+//! work the same on all of them.
 
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::collections::VecDeque;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use rodio::{OutputStream, Sink};
-use tokio::sync::{mpsc, watch};
+use futures_util::Stream;
+use tokio::sync::{broadcast, mpsc, watch};
 use tracing::{debug, error, warn};
 
-use crate::kokoro::KokoroSynth;
-
-use nayru_core::text_prep::{clean_text_for_tts, split_sentences, split_text, DEFAULT_MAX_CHUNK_LEN};
-use nayru_core::types::{TtsConfig, TtsState, TtsStatus};
-
+use crate::audio_stream::{AudioTap, StreamFrame};
+use crate::history::{History, PlayedClip, HISTORY_LIMIT};
+use crate::lexicon::Lexicon;
+use crate::loudness;
+use crate::synth::{SpeechPart, Synthesizer};
+
+use nayru_core::text_prep::{
+    parse_ssml, spell_numbers, split_sentence_chunks, split_sentences, split_spoken, split_text,
+    strip_markdown, SentenceEnd, SsmlSegment, SsmlStyle,
+};
+use nayru_core::timing::WordTiming;
+use nayru_core::lang::sample_sentence;
+use nayru_core::types::{
+    AudioDevice, AudioOutput, PlaybackPosition, QueuedUtterance, SpeakOptions, TtsConfig, TtsEvent, TtsState, TtsStatus,
+    VoiceInfo, VoiceKind,
+};
+
+use crate::audio_device::output_devices;
+use crate::audio_sink::{self, AudioSink, Clip, OutputLevel, Overlay, PlaybackRate};
 use crate::streaming_source::{PcmChunk, StreamingSource};
+use crate::stretch::{MAX_RATE, MIN_RATE};
 
-/// Kokoro PCM output: 24 kHz mono.
-const PCM_SAMPLE_RATE: u32 = 24_000;
+/// Synthesizers produce mono audio.
 const PCM_CHANNELS: u16 = 1;
 
 /// Number of concurrent fetcher tasks.
@@ -49,35 +109,293 @@ const FETCHER_COUNT: usize = 2;
 /// Capacity of the fetch job channel.
 const FETCH_QUEUE_CAPACITY: usize = 32;
 
+/// How often the playback thread checks whether the sink has drained.
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Largest per-utterance gain; louder just clips.
+const MAX_GAIN: f32 = 4.0;
+
+/// Level [`TtsEngine::duck`] lowers playback to by default.
+pub const DEFAULT_DUCK_LEVEL: f32 = 0.25;
+
+/// Capacity of the event broadcast channel. Slow subscribers that fall further
+/// behind than this skip ahead (status is resent so they resync).
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
 /// Cloneable handle to the TTS engine. All methods are non-blocking.
 #[derive(Clone)]
 pub struct TtsEngine {
     cmd_tx: mpsc::UnboundedSender<Cmd>,
     play_cmd_tx: std::sync::mpsc::Sender<PlayCmd>,
     status_rx: watch::Receiver<TtsStatus>,
+    events_tx: broadcast::Sender<TtsEvent>,
     epoch: Arc<AtomicU64>,
+    next_utterance_id: Arc<AtomicU64>,
+    synth: Arc<dyn Synthesizer>,
+    lexicon: Lexicon,
+    tap: AudioTap,
+    /// Current settings; fetchers and the text processor read them per job.
+    config: Arc<watch::Sender<TtsConfig>>,
+    workers: Arc<tokio::sync::Mutex<Workers>>,
+    /// Where priority utterances are synthesized, so `speak` works from any
+    /// thread.
+    runtime: tokio::runtime::Handle,
+}
+
+/// How [`TtsEngine::shutdown`] treats speech that hasn't played yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownMode {
+    /// Fade out the clip playing and drop the rest.
+    Immediate,
+    /// Synthesize and play everything queued first (resuming if paused).
+    Drain,
+}
+
+/// The engine's tasks and thread, joined by [`TtsEngine::shutdown`]. Each is
+/// cleared once it has ended, so a shutdown that's interrupted can be
+/// picked up by another.
+struct Workers {
+    processor: Option<tokio::task::JoinHandle<()>>,
+    fetchers: Vec<tokio::task::JoinHandle<()>>,
+    playback: Option<std::thread::JoinHandle<()>>,
+}
+
+/// Audio produced by [`TtsEngine::render`].
+#[derive(Debug, Clone)]
+pub struct RenderedAudio {
+    /// 16-bit mono PCM.
+    pub pcm: Vec<i16>,
+    pub sample_rate: u32,
 }
 
 // ─── Internal types ────────────────────────────────────────────────────────
 
 enum Cmd {
-    Speak(String),
+    Speak {
+        utterance_id: u64,
+        sentences: Vec<Sentence>,
+    },
     StreamChunk(String),
     StreamEnd,
     Stop,
+    /// End the processor, dropping the job channel.
+    Shutdown,
+    /// [`TtsEngine::update_config`] changed the settings; `resynthesize`
+    /// redoes queued speech that hasn't started playing.
+    ConfigChanged { resynthesize: bool },
+}
+
+/// One sentence of a queued utterance.
+struct Sentence {
+    /// What events report; synthesized as-is unless `parts` is set.
+    text: String,
+    /// Mixed text and IPA (SSML `<phoneme>`), synthesized as one clip.
+    parts: Option<Vec<SpeechPart>>,
+    /// Voice/speed/language; `None` fields use the fetcher defaults.
+    options: SpeakOptions,
+    /// Silence before and after the sentence: the configured
+    /// [`Pauses`](nayru_core::types::Pauses), or SSML `<break>`s.
+    pause_before: Duration,
+    pause_after: Duration,
+}
+
+impl Sentence {
+    /// A plain sentence of markdown-free `text`, with its numbers spelled,
+    /// its language fixed per [`TtsConfig::language_for`], and the pause
+    /// for how it `end`s after it.
+    fn plain(text: &str, end: SentenceEnd, options: &SpeakOptions, config: &TtsConfig) -> Self {
+        let language = config.language_for(options, text);
+        Self {
+            text: spell_numbers(text, &language),
+            parts: None,
+            options: SpeakOptions {
+                language: Some(language),
+                ..options.clone()
+            },
+            pause_before: Duration::ZERO,
+            pause_after: config.pauses_for(options).after(end),
+        }
+    }
+
+    fn is_speakable(&self) -> bool {
+        self.parts.is_some() || is_speakable(&self.text)
+    }
+
+    /// Split into fetch jobs. Long plain sentences become several chunks;
+    /// the pauses go on the first and last.
+    fn into_jobs(self, epoch: u64, position: PlaybackPosition, max_len: usize) -> Vec<FetchJob> {
+        let chunks = match self.parts {
+            Some(parts) => vec![(self.text, Some(parts))],
+            None => split_sentence_chunks(&self.text, max_len)
+                .into_iter()
+                .map(|chunk| (chunk, None))
+                .collect(),
+        };
+        let last = chunks.len().saturating_sub(1);
+        chunks
+            .into_iter()
+            .enumerate()
+            .map(|(i, (text, parts))| FetchJob {
+                seq: 0,
+                text,
+                parts,
+                epoch,
+                options: self.options.clone(),
+                position,
+                pause_before: if i == 0 { self.pause_before } else { Duration::ZERO },
+                pause_after: if i == last { self.pause_after } else { Duration::ZERO },
+            })
+            .collect()
+    }
 }
 
+#[derive(Clone)]
 struct FetchJob {
+    /// Dispatch order, set by [`Dispatcher::send`].
+    seq: u64,
     text: String,
+    /// Pre-phonemized pieces; `None` = synthesize `text`.
+    parts: Option<Vec<SpeechPart>>,
     epoch: u64,
+    /// Per-utterance voice/speed/language; `None` fields use the fetcher defaults.
+    options: SpeakOptions,
+    /// Which sentence of which utterance this chunk came from.
+    position: PlaybackPosition,
+    pause_before: Duration,
+    pause_after: Duration,
+}
+
+impl FetchJob {
+    /// A plain-text job for the streaming API.
+    fn streamed(text: String, epoch: u64, position: PlaybackPosition, pause_after: Duration) -> Self {
+        Self {
+            seq: 0,
+            text,
+            parts: None,
+            epoch,
+            options: SpeakOptions::default(),
+            position,
+            pause_before: Duration::ZERO,
+            pause_after,
+        }
+    }
 }
 
 enum PlayCmd {
-    PlayStream(StreamingSource),
+    Play {
+        clip: Arc<PlayedClip>,
+        seq: u64,
+        epoch: u64,
+    },
+    /// Play a clip of a priority utterance over the queue.
+    Overlay { clip: Arc<PlayedClip>, epoch: u64 },
+    /// Cancel clips that haven't started and drop any that arrive from
+    /// before `epoch`. Replies with the seq of the first job still to play.
+    Requeue {
+        epoch: u64,
+        reply: tokio::sync::oneshot::Sender<u64>,
+    },
     Skip,
     Stop,
     Pause,
     Resume,
+    /// Play clips from the history again, ahead of the queue. `epoch` is the
+    /// current one, for the remote audio tap.
+    Replay {
+        replay: Replay,
+        epoch: u64,
+        reply: tokio::sync::oneshot::Sender<Result<(), String>>,
+    },
+    /// Move playback to this device (`None` = the system default).
+    SetDevice(Option<String>),
+    SetVolume(f32),
+    SetRate(f32),
+    /// Lower playback to `level` until [`PlayCmd::Unduck`] or `until`.
+    Duck { level: f32, until: Option<Instant> },
+    Unduck,
+    /// End the thread: once the queue has played out with `drain`, else
+    /// after fading out.
+    Shutdown { drain: bool },
+}
+
+/// Where [`PlayCmd::Replay`] goes back to.
+enum Replay {
+    Last,
+    Previous,
+    Rewind(Duration),
+}
+
+/// Worth sending to Kokoro: at least two chars and something pronounceable.
+fn is_speakable(text: &str) -> bool {
+    text.len() >= 2 && text.chars().any(|c| c.is_alphanumeric())
+}
+
+/// Synthesize a document to one PCM buffer: strip markdown, split into
+/// sentences, and synthesize them in order, with the configured pauses
+/// between them. No engine or audio device needed.
+///
+/// `on_sentence(done, total)` is called after each sentence, for progress
+/// reporting. `options` aren't validated — see [`TtsEngine::validate`].
+pub async fn render_text(
+    synth: &dyn Synthesizer,
+    config: &TtsConfig,
+    text: &str,
+    options: &SpeakOptions,
+    mut on_sentence: impl FnMut(usize, usize),
+) -> Result<RenderedAudio, String> {
+    let voice = options.voice.as_deref().unwrap_or(&config.voice);
+    let speed = options.speed.unwrap_or(config.speed);
+    let pauses = config.pauses_for(options);
+
+    let sentences: Vec<(String, SentenceEnd)> = split_spoken(text)
+        .into_iter()
+        .filter(|(s, _)| s.chars().any(|c| c.is_alphanumeric()))
+        .collect();
+    let mut pcm = Vec::new();
+    let mut sample_rate = None;
+    for (i, (sentence, _)) in sentences.iter().enumerate() {
+        if let Some((_, end)) = i.checked_sub(1).map(|prev| &sentences[prev]) {
+            let rate = sample_rate.unwrap_or(crate::kokoro::SAMPLE_RATE);
+            let gap = (pauses.after(*end).as_secs_f64() * rate as f64) as usize;
+            pcm.resize(pcm.len() + gap, 0);
+        }
+        for chunk in split_sentence_chunks(sentence, config.max_chunk_len) {
+            if !chunk.chars().any(|c| c.is_alphanumeric()) {
+                continue;
+            }
+            let language = config.language_for(options, &chunk);
+            let chunk = spell_numbers(&chunk, &language);
+            let mut synthesis = synth.synth(&chunk, voice, speed, Some(&language)).await?;
+            if *sample_rate.get_or_insert(synthesis.sample_rate) != synthesis.sample_rate {
+                return Err("synthesizer changed sample rate mid-render".to_string());
+            }
+            if let Some(threshold) = config.trim_threshold {
+                trim_clip(&mut synthesis.samples, synthesis.sample_rate, &mut [], threshold);
+            }
+            level_clip(&mut synthesis.samples, synthesis.sample_rate, config, options.gain);
+            pcm.extend(f32_to_i16(&synthesis.samples));
+        }
+        on_sentence(i + 1, sentences.len());
+    }
+    Ok(RenderedAudio {
+        pcm,
+        sample_rate: sample_rate.unwrap_or(crate::kokoro::SAMPLE_RATE),
+    })
+}
+
+/// Bring a synthesized clip to its playback level: normalized to the
+/// configured loudness, scaled by the utterance's gain, and limited so
+/// [`f32_to_i16`] never has to clip.
+fn level_clip(samples: &mut [f32], sample_rate: u32, config: &TtsConfig, gain: Option<f32>) {
+    if let Some(target) = config.loudness_target {
+        loudness::normalize(samples, sample_rate, target);
+    }
+    if let Some(gain) = gain.filter(|g| *g != 1.0) {
+        for sample in samples.iter_mut() {
+            *sample *= gain;
+        }
+    }
+    loudness::limit(samples, sample_rate);
 }
 
 /// Convert f32 samples [-1.0, 1.0] to i16 PCM.
@@ -88,80 +406,644 @@ fn f32_to_i16(samples: &[f32]) -> Vec<i16> {
         .collect()
 }
 
+/// Keep at most this much of a clip's edge silence when trimming, so soft
+/// onsets and trailing consonants under the threshold survive.
+const TRIM_MARGIN: Duration = Duration::from_millis(20);
+
+/// Cut a clip's leading and trailing audio quieter than `threshold_db`
+/// (dBFS), down to [`TRIM_MARGIN`], shifting its word timings to match.
+/// A clip that's silent throughout is left alone.
+fn trim_clip(samples: &mut Vec<f32>, sample_rate: u32, words: &mut [WordTiming], threshold_db: f32) {
+    let threshold = 10f32.powf(threshold_db / 20.0);
+    let loud = |s: &f32| s.abs() > threshold;
+    let (Some(first), Some(last)) = (samples.iter().position(loud), samples.iter().rposition(loud))
+    else {
+        return;
+    };
+    let margin = (TRIM_MARGIN.as_secs_f32() * sample_rate as f32) as usize;
+    let start = first.saturating_sub(margin);
+    samples.truncate((last + 1 + margin).min(samples.len()));
+    samples.drain(..start);
+
+    let offset = start as f32 / sample_rate as f32;
+    let duration = samples.len() as f32 / sample_rate as f32;
+    for word in words {
+        word.start = (word.start - offset).clamp(0.0, duration);
+        word.end = (word.end - offset).clamp(0.0, duration);
+    }
+}
+
+/// Surround a clip with silence, shifting its word timings to match.
+fn pad_clip(
+    samples: Vec<f32>,
+    sample_rate: u32,
+    words: &mut [WordTiming],
+    before: Duration,
+    after: Duration,
+) -> Vec<f32> {
+    if before.is_zero() && after.is_zero() {
+        return samples;
+    }
+    let silence = |d: Duration| (d.as_secs_f32() * sample_rate as f32) as usize;
+    let (lead, trail) = (silence(before), silence(after));
+    let mut padded = Vec::with_capacity(lead + samples.len() + trail);
+    padded.resize(lead, 0.0);
+    padded.extend(samples);
+    padded.resize(padded.len() + trail, 0.0);
+
+    let offset = lead as f32 / sample_rate as f32;
+    for word in words {
+        word.start += offset;
+        word.end += offset;
+    }
+    padded
+}
+
+/// Builds [`Sentence`]s from parsed SSML.
+///
+/// Text is split at sentence boundaries and `<phoneme>` IPA joins the
+/// sentence around it. A `<break>` or a change of voice, rate, or language
+/// ends the current sentence.
+struct SsmlSentences {
+    sentences: Vec<Sentence>,
+    parts: Vec<SpeechPart>,
+    shown: Vec<String>,
+    options: SpeakOptions,
+    /// Breaks before the first sentence.
+    lead: Duration,
+    /// Pause after each sentence unless a break follows it.
+    pause: Duration,
+    /// A break has followed the last sentence.
+    broken: bool,
+}
+
+impl SsmlSentences {
+    fn build(segments: &[SsmlSegment], base: &SpeakOptions, config: &TtsConfig) -> Vec<Sentence> {
+        let mut b = Self {
+            sentences: Vec::new(),
+            parts: Vec::new(),
+            shown: Vec::new(),
+            options: base.clone(),
+            lead: Duration::ZERO,
+            pause: config.pauses_for(base).after(SentenceEnd::Sentence),
+            broken: false,
+        };
+        for segment in segments {
+            match segment {
+                SsmlSegment::Text { text, style } => {
+                    let options = style_options(style, base, config.speed);
+                    let language = config.language_for(&options, text);
+                    b.restyle(options);
+                    let cleaned = strip_markdown(text);
+                    for (i, sentence) in split_sentences(&cleaned).into_iter().enumerate() {
+                        if i > 0 {
+                            b.flush();
+                        }
+                        let sentence = spell_numbers(&sentence, &language);
+                        b.parts.push(SpeechPart::Text(sentence.clone()));
+                        b.shown.push(sentence);
+                    }
+                    if cleaned.ends_with(['.', '!', '?']) {
+                        b.flush();
+                    }
+                }
+                SsmlSegment::Phoneme { ipa, text, style } => {
+                    b.restyle(style_options(style, base, config.speed));
+                    b.parts.push(SpeechPart::Ipa(ipa.clone()));
+                    b.shown.push(text.clone());
+                }
+                SsmlSegment::Break(pause) => {
+                    b.flush();
+                    match b.sentences.last_mut() {
+                        // A break replaces the usual pause after a sentence.
+                        Some(last) if !b.broken => last.pause_after = *pause,
+                        Some(last) => last.pause_after += *pause,
+                        None => b.lead += *pause,
+                    }
+                    b.broken = true;
+                }
+            }
+        }
+        b.flush();
+        // Nothing to pause for after the last sentence, unless a break asks.
+        if let Some(last) = b.sentences.last_mut().filter(|_| !b.broken) {
+            last.pause_after = Duration::ZERO;
+        }
+        b.sentences
+    }
+
+    fn restyle(&mut self, options: SpeakOptions) {
+        if options != self.options {
+            self.flush();
+            self.options = options;
+        }
+    }
+
+    fn flush(&mut self) {
+        let parts = std::mem::take(&mut self.parts);
+        let shown = std::mem::take(&mut self.shown);
+        let text = shown
+            .iter()
+            .filter(|s| !s.is_empty())
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let has_ipa = parts.iter().any(|p| matches!(p, SpeechPart::Ipa(_)));
+        if !has_ipa && !is_speakable(&text) {
+            return;
+        }
+        // No pause where a style change splits a sentence.
+        let pause_after = if text.ends_with(['.', '!', '?']) {
+            self.pause
+        } else {
+            Duration::ZERO
+        };
+        self.sentences.push(Sentence {
+            text,
+            parts: has_ipa.then_some(parts),
+            options: self.options.clone(),
+            pause_before: std::mem::take(&mut self.lead),
+            pause_after,
+        });
+        self.broken = false;
+    }
+}
+
+/// Per-sentence options for an SSML style: its voice and language override
+/// the request's, and its rate scales the request's speed.
+fn style_options(style: &SsmlStyle, base: &SpeakOptions, default_speed: f32) -> SpeakOptions {
+    SpeakOptions {
+        voice: style.voice.clone().or_else(|| base.voice.clone()),
+        speed: if style.rate == 1.0 {
+            base.speed
+        } else {
+            Some(base.speed.unwrap_or(default_speed) * style.rate)
+        },
+        language: style.language.clone().or_else(|| base.language.clone()),
+        gain: base.gain,
+        pauses: base.pauses,
+        priority: base.priority,
+    }
+}
+
 // ─── Engine construction ───────────────────────────────────────────────────
 
 impl TtsEngine {
     /// Spawn the TTS pipeline. Returns a cloneable handle.
     ///
-    /// `kokoro` must be a pre-loaded KokoroSynth instance (model + voices loaded).
-    pub fn new(config: TtsConfig, kokoro: Arc<KokoroSynth>) -> Self {
+    /// `synth` must be ready to use (e.g. a [`KokoroSynth`](crate::kokoro::KokoroSynth)
+    /// with its model and voices loaded).
+    pub fn new(config: TtsConfig, synth: Arc<dyn Synthesizer>) -> Self {
+        let (output, device) = (config.output, config.output_device.clone());
+        Self::with_sink(config, synth, move || audio_sink::for_output(output, device))
+    }
+
+    /// Like [`new`](Self::new), but playing through the sink `make_sink`
+    /// builds (on the playback thread) instead of the one `config.output`
+    /// picks — e.g. a [`NullSink`](audio_sink::NullSink) for tests, or a
+    /// [`Tee`](audio_sink::Tee) to record the session.
+    pub fn with_sink(
+        config: TtsConfig,
+        synth: Arc<dyn Synthesizer>,
+        make_sink: impl FnOnce() -> Box<dyn AudioSink> + Send + 'static,
+    ) -> Self {
+        let lexicon = Lexicon::default();
+        let synth = lexicon.wrap(synth);
         let epoch = Arc::new(AtomicU64::new(0));
+        let next_utterance_id = Arc::new(AtomicU64::new(1));
         let (cmd_tx, cmd_rx) = mpsc::unbounded_channel();
         let (status_tx, status_rx) = watch::channel(TtsStatus {
             state: TtsState::Idle,
             queue_length: 0,
             voice: config.voice.clone(),
+            current: None,
+            cache: synth.cache_stats(),
+            device: None,
+            volume: config.volume,
+            ducked: false,
+            rate: config.rate.clamp(MIN_RATE, MAX_RATE),
         });
+        let (events_tx, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        let tap = AudioTap::default();
+        let (config_tx, config_rx) = watch::channel(config.clone());
+        // Seq of the first job whose clip hasn't started playing.
+        let played = Arc::new(AtomicU64::new(0));
 
         // Job channel
         let (fetch_tx, fetch_rx) = mpsc::channel::<FetchJob>(FETCH_QUEUE_CAPACITY);
 
         // Playback OS thread (rodio OutputStream is !Send)
+        let level = OutputLevel::new(config.volume.clamp(0.0, 1.0));
+        let rate = PlaybackRate::new(config.rate.clamp(MIN_RATE, MAX_RATE));
         let (play_cmd_tx, play_cmd_rx) = std::sync::mpsc::channel::<PlayCmd>();
-        let play_status_tx = status_tx.clone();
-        std::thread::Builder::new()
+        let hooks = ClipHooks {
+            status_tx: status_tx.clone(),
+            events_tx: events_tx.clone(),
+            tap: config.output.is_remote().then(|| tap.clone()),
+            overlay: Overlay::default(),
+        };
+        let play_played = played.clone();
+        let playback = std::thread::Builder::new()
             .name("nayru-playback".into())
             .spawn(move || {
-                playback_thread(play_cmd_rx, play_status_tx);
+                let sink = make_sink();
+                playback_thread(play_cmd_rx, hooks, sink, level, rate, play_played)
             })
             .expect("failed to spawn playback thread");
 
         // Spawn FETCHER_COUNT fetcher tasks sharing the job channel
         let fetch_rx = Arc::new(tokio::sync::Mutex::new(fetch_rx));
+        let mut fetchers = Vec::with_capacity(FETCHER_COUNT);
         for i in 0..FETCHER_COUNT {
             let fetch_rx = fetch_rx.clone();
             let epoch = epoch.clone();
             let play_cmd_tx = play_cmd_tx.clone();
             let status_tx = status_tx.clone();
-            let kokoro = kokoro.clone();
-            let voice = config.voice.clone();
-            let speed = config.speed;
-            tokio::spawn(async move {
-                fetcher_task(i, fetch_rx, play_cmd_tx, epoch, status_tx, kokoro, &voice, speed)
-                    .await;
-            });
+            let events_tx = events_tx.clone();
+            let synth = synth.clone();
+            let config = config_rx.clone();
+            fetchers.push(tokio::spawn(async move {
+                fetcher_task(
+                    i,
+                    fetch_rx,
+                    play_cmd_tx,
+                    epoch,
+                    status_tx,
+                    events_tx,
+                    synth,
+                    config,
+                )
+                .await;
+            }));
         }
 
         // Text processor — splits, merges, and dispatches jobs
         let proc_epoch = epoch.clone();
-        tokio::spawn(async move {
-            text_processor_task(cmd_rx, fetch_tx, proc_epoch, status_tx, config).await;
+        let proc_next_id = next_utterance_id.clone();
+        let dispatcher = Dispatcher::new(fetch_tx, played);
+        let proc_play_cmd_tx = play_cmd_tx.clone();
+        let processor = tokio::spawn(async move {
+            text_processor_task(
+                cmd_rx,
+                dispatcher,
+                proc_play_cmd_tx,
+                proc_epoch,
+                proc_next_id,
+                status_tx,
+                config_rx,
+            )
+            .await;
         });
 
         Self {
             cmd_tx,
             play_cmd_tx,
             status_rx,
+            events_tx,
             epoch,
+            next_utterance_id,
+            synth,
+            lexicon,
+            tap,
+            config: Arc::new(config_tx),
+            workers: Arc::new(tokio::sync::Mutex::new(Workers {
+                processor: Some(processor),
+                fetchers,
+                playback: Some(playback),
+            })),
+            runtime: tokio::runtime::Handle::current(),
+        }
+    }
+
+    /// The engine's current settings.
+    pub fn config(&self) -> TtsConfig {
+        self.config.borrow().clone()
+    }
+
+    /// Change settings on the running engine, without dropping the queue.
+    ///
+    /// `update` edits a copy of the current [`TtsConfig`]; voice, speed,
+    /// language, chunk length, loudness, trimming, and pauses take effect
+    /// from the next sentence. Speech already synthesized but not yet
+    /// playing is synthesized again, so the change is heard right away
+    /// rather than after the queue drains. `output`, `output_device`,
+    /// `volume`, and `rate` are left alone — see
+    /// [`set_output_device`](Self::set_output_device),
+    /// [`set_volume`](Self::set_volume), and [`set_rate`](Self::set_rate).
+    ///
+    /// Fails, changing nothing, on an unknown voice, a speed that isn't a
+    /// positive number, a zero chunk length, or a pause over
+    /// [`MAX_PAUSE_MS`](nayru_core::types::MAX_PAUSE_MS).
+    pub fn update_config(&self, update: impl FnOnce(&mut TtsConfig)) -> Result<(), String> {
+        let current = self.config();
+        let mut config = current.clone();
+        update(&mut config);
+        config.output = current.output;
+        config.output_device = current.output_device.clone();
+        config.volume = current.volume;
+        config.rate = current.rate;
+
+        self.synth.validate_voice(&config.voice)?;
+        if !(config.speed.is_finite() && config.speed > 0.0) {
+            return Err(format!("invalid speed {}; must be a positive number", config.speed));
+        }
+        if config.max_chunk_len == 0 {
+            return Err("max_chunk_len must be at least 1".to_string());
+        }
+        config.pauses.validate()?;
+        if config == current {
+            return Ok(());
+        }
+
+        // Chunk length only affects how later text is split.
+        let resynthesize = TtsConfig {
+            max_chunk_len: current.max_chunk_len,
+            ..config.clone()
+        } != current;
+        self.config.send_replace(config);
+        let _ = self.cmd_tx.send(Cmd::ConfigChanged { resynthesize });
+        Ok(())
+    }
+
+    /// Queue text for speech. Returns the utterance ID and estimated chunk count.
+    ///
+    /// The text is split into sentences; [`TtsStatus::current`] and playback
+    /// events report progress as `(utterance_id, sentence_index)`. Each
+    /// sentence is followed by the pause for what ends it — a paragraph, a
+    /// list item, or just a full stop (see [`Pauses`](nayru_core::types::Pauses)).
+    ///
+    /// `options` override the engine's default voice, speed, and language for
+    /// this utterance only. Fails if the voice isn't in voices.bin or the
+    /// speed isn't a positive number.
+    pub fn speak(&self, text: &str, options: SpeakOptions) -> Result<QueuedUtterance, String> {
+        self.validate(&options)?;
+
+        let cleaned = strip_markdown(text);
+        if !is_speakable(&cleaned) {
+            return Ok(self.queue(Vec::new(), false));
+        }
+        let sentences = split_spoken(text)
+            .iter()
+            .map(|(s, end)| Sentence::plain(s, *end, &options, &self.config.borrow()))
+            .collect();
+        Ok(self.queue(sentences, options.priority))
+    }
+
+    /// Queue SSML for speech.
+    ///
+    /// `<break>`s become silence, `<phoneme>` IPA skips espeak-ng, and
+    /// `<voice>`, `xml:lang`, `<prosody rate>`, and `<emphasis>` apply to the
+    /// sentences they wrap. `options` are the defaults the markup overrides.
+    /// Fails on malformed markup or an unknown voice anywhere in it.
+    pub fn speak_ssml(&self, ssml: &str, options: SpeakOptions) -> Result<QueuedUtterance, String> {
+        self.validate(&options)?;
+        let segments = parse_ssml(ssml)?;
+        let sentences = SsmlSentences::build(&segments, &options, &self.config.borrow());
+        for sentence in &sentences {
+            self.validate(&sentence.options)?;
         }
+        Ok(self.queue(sentences, options.priority))
+    }
+
+    /// Queue pre-split sentences for speech.
+    ///
+    /// Each sentence is cleaned separately and keeps its position in
+    /// `sentences` as its `sentence_index`, so callers that render their own
+    /// sentence list (like the reader app) get an exact mapping back.
+    pub fn speak_sentences(
+        &self,
+        sentences: &[String],
+        options: SpeakOptions,
+    ) -> Result<QueuedUtterance, String> {
+        self.validate(&options)?;
+        let last = sentences.len().saturating_sub(1);
+        let cleaned = sentences
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                let config = self.config.borrow();
+                let end = if i == last { SentenceEnd::End } else { SentenceEnd::Sentence };
+                Sentence::plain(&strip_markdown(s), end, &options, &config)
+            })
+            .collect();
+        Ok(self.queue(cleaned, options.priority))
+    }
+
+    /// Send sentences to the text processor, or with `priority` to a task
+    /// of their own that plays them over the queue.
+    fn queue(&self, sentences: Vec<Sentence>, priority: bool) -> QueuedUtterance {
+        let utterance_id = self.next_utterance_id.fetch_add(1, Ordering::SeqCst);
+        let chunks = sentences
+            .iter()
+            .filter(|s| s.is_speakable())
+            .map(|s| match s.parts {
+                Some(_) => 1,
+                None => split_text(&s.text, self.config.borrow().max_chunk_len).len(),
+            })
+            .sum();
+        if chunks > 0 && priority {
+            self.speak_over(utterance_id, sentences);
+        } else if chunks > 0 {
+            let _ = self.cmd_tx.send(Cmd::Speak {
+                utterance_id,
+                sentences,
+            });
+        }
+        QueuedUtterance {
+            utterance_id,
+            chunks,
+        }
+    }
+
+    /// Synthesize a priority utterance one clip at a time, each played over
+    /// the queue as soon as it's ready. `stop()` ends it.
+    fn speak_over(&self, utterance_id: u64, sentences: Vec<Sentence>) {
+        let synth = self.synth.clone();
+        let config = self.config();
+        let play_cmd_tx = self.play_cmd_tx.clone();
+        let events_tx = self.events_tx.clone();
+        let epoch = self.epoch.clone();
+        let started = epoch.load(Ordering::SeqCst);
+        self.runtime.spawn(async move {
+            for (sentence_index, sentence) in sentences.into_iter().enumerate() {
+                if !sentence.is_speakable() {
+                    continue;
+                }
+                let position = PlaybackPosition {
+                    utterance_id,
+                    sentence_index,
+                };
+                for job in sentence.into_jobs(started, position, config.max_chunk_len) {
+                    let result = synth_clip(synth.as_ref(), &config, &job).await;
+                    if epoch.load(Ordering::SeqCst) != started {
+                        debug!("priority: stopped, discarding");
+                        return;
+                    }
+                    match result {
+                        Ok(Some(clip)) => {
+                            let play = PlayCmd::Overlay {
+                                clip: Arc::new(clip),
+                                epoch: started,
+                            };
+                            if play_cmd_tx.send(play).is_err() {
+                                return;
+                            }
+                        }
+                        Ok(None) => warn!("priority: synthesizer returned empty audio"),
+                        Err(e) => {
+                            error!("priority: synthesis failed: {e}");
+                            let _ = events_tx.send(TtsEvent::SynthesisError {
+                                position,
+                                text: job.text,
+                                error: e,
+                            });
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    /// Synthesize text to mono PCM without touching the audio device.
+    ///
+    /// Runs the same clean → split → synth path as [`speak`](Self::speak),
+    /// sequentially, and concatenates the sentences (see [`render_text`]).
+    /// Independent of the playback queue — `stop()` does not cancel a render.
+    pub async fn render(
+        &self,
+        text: &str,
+        options: &SpeakOptions,
+    ) -> Result<RenderedAudio, String> {
+        self.validate(options)?;
+        let config = self.config();
+        render_text(
+            self.synth.as_ref(),
+            &config,
+            text,
+            options,
+            |_, _| {},
+        )
+        .await
+    }
+
+    /// Pronunciation lexicon applied to every clip. Empty and in-memory
+    /// until [`Lexicon::load`] points it at a file.
+    pub fn lexicon(&self) -> &Lexicon {
+        &self.lexicon
+    }
+
+    /// Names of all voices the engine can speak with, sorted.
+    pub fn voices(&self) -> Vec<String> {
+        self.synth.voices()
     }
 
-    /// Queue text for speech. Returns the estimated number of chunks.
-    pub fn speak(&self, text: &str) -> usize {
-        let cleaned = clean_text_for_tts(text);
-        if cleaned.len() < 2 || !cleaned.chars().any(|c| c.is_alphanumeric()) {
-            return 0;
+    /// Fails, listing the valid names, if the engine can't speak with `voice`.
+    pub fn validate_voice(&self, voice: &str) -> Result<(), String> {
+        self.synth.validate_voice(voice)
+    }
+
+    /// Every voice with its metadata, sorted by name. The configured default
+    /// voice is included when it's a blend.
+    pub fn voice_infos(&self) -> Vec<VoiceInfo> {
+        let mut infos: Vec<VoiceInfo> = self
+            .synth
+            .voices()
+            .iter()
+            .map(|name| VoiceInfo::new(name, self.synth.voice_kind(name)))
+            .collect();
+        let default = self.config.borrow().voice.clone();
+        if self.synth.voice_kind(&default) == VoiceKind::Blend
+            && self.synth.validate_voice(&default).is_ok()
+        {
+            infos.push(VoiceInfo::new(&default, VoiceKind::Blend));
+            infos.sort_by(|a, b| a.name.cmp(&b.name));
         }
-        let n = split_text(&cleaned, DEFAULT_MAX_CHUNK_LEN).len();
-        let _ = self.cmd_tx.send(Cmd::Speak(cleaned));
-        n
+        infos
+    }
+
+    /// A short sample of `voice` introducing itself in its own language.
+    pub async fn preview_voice(&self, voice: &VoiceInfo) -> Result<RenderedAudio, String> {
+        let options = SpeakOptions {
+            voice: Some(voice.name.clone()),
+            ..Default::default()
+        };
+        let language = self.config.borrow().language_for(&options, "");
+        let text = sample_sentence(&language, &voice.display_name);
+        self.render(&text, &options).await
+    }
+
+    /// Save a voice or blend (`af_heart:0.6+bf_emma:0.4`) as custom voice
+    /// `name`. Only backends with local voice packs support this.
+    pub fn save_voice(&self, name: &str, blend: &str) -> Result<(), String> {
+        self.synth.save_voice(name, blend)
+    }
+
+    /// Check that [`save_voice`](Self::save_voice) would accept `name` and
+    /// `blend`, without writing anything.
+    pub fn check_save_voice(&self, name: &str, blend: &str) -> Result<(), String> {
+        self.synth.check_save_voice(name, blend)
+    }
+
+    /// Check per-utterance options against the loaded voices.
+    pub fn validate(&self, options: &SpeakOptions) -> Result<(), String> {
+        if let Some(voice) = &options.voice {
+            self.synth.validate_voice(voice)?;
+        }
+        if let Some(speed) = options.speed.filter(|s| !(s.is_finite() && *s > 0.0)) {
+            return Err(format!("invalid speed {speed}; must be a positive number"));
+        }
+        if let Some(gain) = options.gain.filter(|g| !(0.0..=MAX_GAIN).contains(g)) {
+            return Err(format!("invalid gain {gain}; must be between 0 and {MAX_GAIN}"));
+        }
+        if let Some(pauses) = &options.pauses {
+            pauses.validate()?;
+        }
+        Ok(())
     }
 
     /// Stop all speech immediately.
     pub fn stop(&self) {
-        self.epoch.fetch_add(1, Ordering::SeqCst);
+        let epoch = self.epoch.fetch_add(1, Ordering::SeqCst) + 1;
         let _ = self.cmd_tx.send(Cmd::Stop);
         let _ = self.play_cmd_tx.send(PlayCmd::Stop);
+        self.tap.flush(epoch);
+    }
+
+    /// Shut the engine down: end the text processor, the fetchers, and the
+    /// playback thread, and wait for them. Every clone of the engine is
+    /// affected; commands sent afterwards are ignored.
+    ///
+    /// [`ShutdownMode::Drain`] can take as long as the queue does. Calling
+    /// with [`ShutdownMode::Immediate`] while a drain is under way (say, on a
+    /// second Ctrl-C) cuts it short.
+    pub async fn shutdown(&self, mode: ShutdownMode) {
+        match mode {
+            ShutdownMode::Immediate => {
+                let epoch = self.epoch.fetch_add(1, Ordering::SeqCst) + 1;
+                let _ = self.play_cmd_tx.send(PlayCmd::Shutdown { drain: false });
+                self.tap.flush(epoch);
+            }
+            // A stream still open is spoken up to where it got.
+            ShutdownMode::Drain => {
+                let _ = self.cmd_tx.send(Cmd::StreamEnd);
+            }
+        }
+        let _ = self.cmd_tx.send(Cmd::Shutdown);
+
+        let mut workers = self.workers.lock().await;
+        if let Some(processor) = &mut workers.processor {
+            let _ = processor.await;
+            workers.processor = None;
+        }
+        while let Some(fetcher) = workers.fetchers.last_mut() {
+            let _ = fetcher.await;
+            workers.fetchers.pop();
+        }
+        // Every clip has been handed over, so draining can start.
+        let _ = self.play_cmd_tx.send(PlayCmd::Shutdown { drain: true });
+        if let Some(playback) = workers.playback.take() {
+            let _ = tokio::task::spawn_blocking(move || playback.join()).await;
+        }
     }
 
     /// Skip the currently playing clip.
@@ -169,7 +1051,8 @@ impl TtsEngine {
         let _ = self.play_cmd_tx.send(PlayCmd::Skip);
     }
 
-    /// Pause playback.
+    /// Pause playback. With nothing playing, the next speech waits for
+    /// [`resume`](Self::resume).
     pub fn pause(&self) {
         let _ = self.play_cmd_tx.send(PlayCmd::Pause);
     }
@@ -179,9 +1062,130 @@ impl TtsEngine {
         let _ = self.play_cmd_tx.send(PlayCmd::Resume);
     }
 
-    /// Get current status.
+    /// Play the current sentence again from its start, or with nothing
+    /// playing, the last sentence played. The queue carries on after it.
+    pub async fn replay_last(&self) -> Result<(), String> {
+        self.replay(Replay::Last).await
+    }
+
+    /// Go back to the start of the sentence before the one playing, or with
+    /// nothing playing, replay the last one.
+    pub async fn previous(&self) -> Result<(), String> {
+        self.replay(Replay::Previous).await
+    }
+
+    /// Go back `seconds` of played audio, across sentences if need be.
+    /// Speech is kept for [`HISTORY_LIMIT`]; going back further starts from
+    /// the oldest kept.
+    pub async fn rewind(&self, seconds: f32) -> Result<(), String> {
+        if !(seconds.is_finite() && seconds > 0.0) {
+            return Err(format!("invalid rewind {seconds}; must be a positive number of seconds"));
+        }
+        let by = Duration::from_secs_f32(seconds.min(HISTORY_LIMIT.as_secs_f32()));
+        self.replay(Replay::Rewind(by)).await
+    }
+
+    async fn replay(&self, replay: Replay) -> Result<(), String> {
+        let (reply, reply_rx) = tokio::sync::oneshot::channel();
+        let cmd = PlayCmd::Replay {
+            replay,
+            epoch: self.epoch.load(Ordering::SeqCst),
+            reply,
+        };
+        self.play_cmd_tx
+            .send(cmd)
+            .map_err(|_| "playback has stopped".to_string())?;
+        reply_rx.await.map_err(|_| "playback has stopped".to_string())?
+    }
+
+    /// Set the master volume (0.0–1.0). Ramps smoothly, mid-clip included.
+    pub fn set_volume(&self, volume: f32) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&volume) {
+            return Err(format!("invalid volume {volume}; must be between 0 and 1"));
+        }
+        let _ = self.play_cmd_tx.send(PlayCmd::SetVolume(volume));
+        Ok(())
+    }
+
+    /// Set the playback rate (0.5–3.0), time-stretching without changing
+    /// pitch. Unlike the synthesis `speed`, it applies at once, to the clip
+    /// that's playing and everything queued.
+    pub fn set_rate(&self, rate: f32) -> Result<(), String> {
+        if !(MIN_RATE..=MAX_RATE).contains(&rate) {
+            return Err(format!(
+                "invalid rate {rate}; must be between {MIN_RATE} and {MAX_RATE}"
+            ));
+        }
+        let _ = self.play_cmd_tx.send(PlayCmd::SetRate(rate));
+        Ok(())
+    }
+
+    /// Lower playback to `level` (a fraction of the master volume) while
+    /// someone else speaks — another nayru with more urgent speech, a call.
+    /// Ducking again replaces the level and timeout. With `timeout`, the duck
+    /// lifts by itself, so a caller that dies can't leave nayru muted.
+    pub fn duck(&self, level: f32, timeout: Option<Duration>) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&level) {
+            return Err(format!("invalid duck level {level}; must be between 0 and 1"));
+        }
+        let until = timeout.map(|t| Instant::now() + t);
+        let _ = self.play_cmd_tx.send(PlayCmd::Duck { level, until });
+        Ok(())
+    }
+
+    /// Bring playback back up after [`duck`](Self::duck).
+    pub fn unduck(&self) {
+        let _ = self.play_cmd_tx.send(PlayCmd::Unduck);
+    }
+
+    /// Get current status. Cache counters are read fresh, since
+    /// [`render`](Self::render) uses the cache without touching playback status.
     pub fn status(&self) -> TtsStatus {
-        self.status_rx.borrow().clone()
+        let mut status = self.status_rx.borrow().clone();
+        status.cache = self.synth.cache_stats();
+        status
+    }
+
+    /// Where speech plays.
+    pub fn audio_output(&self) -> AudioOutput {
+        self.config.borrow().output
+    }
+
+    /// Subscribe to the audio played for remote listeners. `None` if the
+    /// engine only plays locally.
+    pub fn subscribe_audio(&self) -> Option<broadcast::Receiver<StreamFrame>> {
+        self.config.borrow().output.is_remote().then(|| self.tap.subscribe())
+    }
+
+    /// Audio output devices on this machine, marking the one playing speech.
+    pub fn output_devices(&self) -> Result<Vec<AudioDevice>, String> {
+        let active = self.status_rx.borrow().device.clone();
+        let mut devices = output_devices()?;
+        for device in &mut devices {
+            device.active = active.as_ref() == Some(&device.name);
+        }
+        Ok(devices)
+    }
+
+    /// Move playback to another device (`None` = the system default) without
+    /// losing queued speech; the current sentence carries on where it was.
+    /// The choice sticks: if the device goes away, playback falls back to the
+    /// default and returns when it comes back.
+    pub fn set_output_device(&self, name: Option<&str>) -> Result<(), String> {
+        if !self.config.borrow().output.is_local() {
+            return Err("speech only goes to remote listeners; there's no audio device to switch".into());
+        }
+        if let Some(name) = name {
+            let names: Vec<String> = output_devices()?.into_iter().map(|d| d.name).collect();
+            if !names.iter().any(|n| n == name) {
+                return Err(format!(
+                    "unknown output device '{name}'; available: {}",
+                    names.join(", ")
+                ));
+            }
+        }
+        let _ = self.play_cmd_tx.send(PlayCmd::SetDevice(name.map(String::from)));
+        Ok(())
     }
 
     /// Subscribe to status changes.
@@ -189,6 +1193,46 @@ impl TtsEngine {
         self.status_rx.clone()
     }
 
+    /// Subscribe to lifecycle events (clip start/finish, errors, pause/resume).
+    ///
+    /// Status changes are not included — see [`events`](Self::events).
+    pub fn subscribe_events(&self) -> broadcast::Receiver<TtsEvent> {
+        self.events_tx.subscribe()
+    }
+
+    /// All engine events as a stream: the current status first, then every
+    /// status change and lifecycle event as it happens.
+    ///
+    /// Ends when the engine is dropped.
+    pub fn events(&self) -> impl Stream<Item = TtsEvent> + Send + use<> {
+        let status_rx = self.subscribe_status();
+        let events_rx = self.subscribe_events();
+        futures_util::stream::unfold(
+            (status_rx, events_rx, true),
+            |(mut status_rx, mut events_rx, first)| async move {
+                if first {
+                    let status = status_rx.borrow_and_update().clone();
+                    return Some((TtsEvent::Status(status), (status_rx, events_rx, false)));
+                }
+                let event = tokio::select! {
+                    changed = status_rx.changed() => {
+                        changed.ok()?;
+                        TtsEvent::Status(status_rx.borrow_and_update().clone())
+                    }
+                    event = events_rx.recv() => match event {
+                        Ok(event) => event,
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            warn!("events: subscriber lagged, skipped {n}");
+                            TtsEvent::Status(status_rx.borrow_and_update().clone())
+                        }
+                        Err(broadcast::error::RecvError::Closed) => return None,
+                    },
+                };
+                Some((event, (status_rx, events_rx, false)))
+            },
+        )
+    }
+
     /// Feed a text chunk from an LLM stream.
     pub fn stream_chunk(&self, text: &str) {
         if !text.is_empty() {
@@ -204,29 +1248,93 @@ impl TtsEngine {
 
 // ─── Text processor ──────────────────────────────────────────────────────
 
+/// An in-progress LLM stream. All of its sentences share one utterance ID.
+struct StreamState {
+    epoch: u64,
+    utterance_id: u64,
+    next_index: usize,
+}
+
+impl StreamState {
+    /// Tag the next dispatched sentence.
+    fn next_position(&mut self) -> PlaybackPosition {
+        let position = PlaybackPosition {
+            utterance_id: self.utterance_id,
+            sentence_index: self.next_index,
+        };
+        self.next_index += 1;
+        position
+    }
+}
+
+/// Sends jobs to the fetchers, numbering them and keeping a copy of each
+/// until its clip starts playing, so a config change can synthesize the
+/// unplayed ones again.
+struct Dispatcher {
+    fetch_tx: mpsc::Sender<FetchJob>,
+    next_seq: u64,
+    history: VecDeque<FetchJob>,
+    /// Seq of the first job whose clip hasn't started, kept by the playback
+    /// thread.
+    played: Arc<AtomicU64>,
+}
+
+impl Dispatcher {
+    fn new(fetch_tx: mpsc::Sender<FetchJob>, played: Arc<AtomicU64>) -> Self {
+        Self {
+            fetch_tx,
+            next_seq: 0,
+            history: VecDeque::new(),
+            played,
+        }
+    }
+
+    /// Returns false once the fetchers are gone.
+    async fn send(&mut self, mut job: FetchJob) -> bool {
+        job.seq = self.next_seq;
+        self.next_seq += 1;
+        let played = self.played.load(Ordering::SeqCst);
+        while self.history.front().is_some_and(|j| j.seq < played) {
+            self.history.pop_front();
+        }
+        self.history.push_back(job.clone());
+        self.fetch_tx.send(job).await.is_ok()
+    }
+}
+
 async fn text_processor_task(
     mut cmd_rx: mpsc::UnboundedReceiver<Cmd>,
-    fetch_tx: mpsc::Sender<FetchJob>,
+    mut dispatcher: Dispatcher,
+    play_cmd_tx: std::sync::mpsc::Sender<PlayCmd>,
     epoch: Arc<AtomicU64>,
+    next_utterance_id: Arc<AtomicU64>,
     status_tx: watch::Sender<TtsStatus>,
-    config: TtsConfig,
+    config_rx: watch::Receiver<TtsConfig>,
 ) {
     let mut stream_buffer = String::new();
-    let mut stream_epoch: Option<u64> = None;
+    let mut stream: Option<StreamState> = None;
 
     while let Some(cmd) = cmd_rx.recv().await {
+        let config = config_rx.borrow().clone();
         match cmd {
-            Cmd::Speak(text) => {
+            Cmd::Speak {
+                utterance_id,
+                sentences,
+            } => {
                 let current_epoch = epoch.load(Ordering::SeqCst);
 
-                let sentences = split_sentences(&text);
-                let mut batched: Vec<String> = Vec::new();
-                for sentence in sentences {
-                    if sentence.len() <= config.max_chunk_len {
-                        batched.push(sentence);
-                    } else {
-                        batched.extend(split_text(&sentence, config.max_chunk_len));
+                // Each sentence keeps its index even if it's split into
+                // several chunks or skipped for being empty.
+                let mut batched: Vec<FetchJob> = Vec::new();
+                for (sentence_index, sentence) in sentences.into_iter().enumerate() {
+                    if !sentence.is_speakable() {
+                        continue;
                     }
+                    let position = PlaybackPosition {
+                        utterance_id,
+                        sentence_index,
+                    };
+                    batched.extend(sentence.into_jobs(current_epoch, position, config.max_chunk_len));
                 }
 
                 let total = batched.len();
@@ -238,30 +1346,28 @@ async fn text_processor_task(
                 });
 
                 debug!(
-                    "processor: dispatching {} jobs (epoch {})",
-                    total, current_epoch
+                    "processor: dispatching {} jobs for utterance {} (epoch {})",
+                    total, utterance_id, current_epoch
                 );
 
-                for text in batched {
-                    debug!("processor: queuing job ({} chars)", text.len());
-                    if fetch_tx
-                        .send(FetchJob {
-                            text,
-                            epoch: current_epoch,
-                        })
-                        .await
-                        .is_err()
-                    {
+                for job in batched {
+                    debug!("processor: queuing job ({} chars)", job.text.len());
+                    if !dispatcher.send(job).await {
                         break;
                     }
                 }
             }
 
             Cmd::StreamChunk(chunk) => {
-                if stream_epoch.is_none() {
+                if stream.is_none() {
                     let e = epoch.load(Ordering::SeqCst);
-                    stream_epoch = Some(e);
-                    debug!("stream started (epoch {})", e);
+                    let utterance_id = next_utterance_id.fetch_add(1, Ordering::SeqCst);
+                    stream = Some(StreamState {
+                        epoch: e,
+                        utterance_id,
+                        next_index: 0,
+                    });
+                    debug!("stream started (utterance {utterance_id}, epoch {e})");
                     update_status(&status_tx, |s| {
                         if s.state == TtsState::Idle {
                             s.state = TtsState::Converting;
@@ -269,10 +1375,10 @@ async fn text_processor_task(
                     });
                 }
 
-                let current_epoch = stream_epoch.unwrap();
-                if epoch.load(Ordering::SeqCst) != current_epoch {
+                let state = stream.as_mut().unwrap();
+                if epoch.load(Ordering::SeqCst) != state.epoch {
                     stream_buffer.clear();
-                    stream_epoch = None;
+                    stream = None;
                     continue;
                 }
 
@@ -280,8 +1386,8 @@ async fn text_processor_task(
 
                 dispatch_stream_sentences(
                     &mut stream_buffer,
-                    current_epoch,
-                    &fetch_tx,
+                    state,
+                    &mut dispatcher,
                     &epoch,
                     &status_tx,
                     &config,
@@ -291,39 +1397,37 @@ async fn text_processor_task(
 
             Cmd::StreamEnd => {
                 debug!("stream end — buffer={} chars", stream_buffer.len());
-                if let Some(current_epoch) = stream_epoch.take() {
-                    if epoch.load(Ordering::SeqCst) == current_epoch {
-                        let remaining = stream_buffer.trim().to_string();
-                        if remaining.len() >= 2
-                            && remaining.chars().any(|c| c.is_alphanumeric())
-                        {
-                            let chunks = if remaining.len() <= config.max_chunk_len {
-                                vec![remaining]
-                            } else {
-                                split_text(&remaining, config.max_chunk_len)
-                            };
-
-                            let count = chunks.len();
-                            update_status(&status_tx, |s| {
-                                s.queue_length += count;
-                            });
-
-                            debug!("stream: flushing {} final chunk(s)", count);
-
-                            for text in chunks {
-                                if epoch.load(Ordering::SeqCst) != current_epoch {
-                                    break;
-                                }
-                                if fetch_tx
-                                    .send(FetchJob {
-                                        text,
-                                        epoch: current_epoch,
-                                    })
-                                    .await
-                                    .is_err()
-                                {
-                                    break;
-                                }
+                if let Some(mut state) = stream.take()
+                    && epoch.load(Ordering::SeqCst) == state.epoch
+                {
+                    let remaining = stream_buffer.trim().to_string();
+                    if remaining.len() >= 2
+                        && remaining.chars().any(|c| c.is_alphanumeric())
+                    {
+                        let chunks = if remaining.len() <= config.max_chunk_len {
+                            vec![remaining]
+                        } else {
+                            split_text(&remaining, config.max_chunk_len)
+                        };
+
+                        let count = chunks.len();
+                        update_status(&status_tx, |s| {
+                            s.queue_length += count;
+                        });
+
+                        debug!("stream: flushing {} final chunk(s)", count);
+
+                        // The stream ends here; no pause after it.
+                        let position = state.next_position();
+                        for text in chunks {
+                            if epoch.load(Ordering::SeqCst) != state.epoch {
+                                break;
+                            }
+                            if !dispatcher
+                                .send(FetchJob::streamed(text, state.epoch, position, Duration::ZERO))
+                                .await
+                            {
+                                break;
                             }
                         }
                     }
@@ -333,20 +1437,69 @@ async fn text_processor_task(
 
             Cmd::Stop => {
                 stream_buffer.clear();
-                stream_epoch = None;
+                stream = None;
+                dispatcher.history.clear();
                 update_status(&status_tx, |s| {
                     s.queue_length = 0;
                     s.state = TtsState::Idle;
+                    s.current = None;
                 });
             }
+
+            Cmd::Shutdown => break,
+
+            Cmd::ConfigChanged { resynthesize } => {
+                update_status(&status_tx, |s| s.voice = config.voice.clone());
+                if !resynthesize {
+                    continue;
+                }
+                // A new epoch retires the queued clips and in-flight jobs,
+                // like stop() but without touching the clip that's playing.
+                // If stop() got there first there's nothing left to redo.
+                let old = epoch.load(Ordering::SeqCst);
+                if epoch
+                    .compare_exchange(old, old + 1, Ordering::SeqCst, Ordering::SeqCst)
+                    .is_err()
+                {
+                    continue;
+                }
+                let (reply_tx, reply_rx) = tokio::sync::oneshot::channel();
+                let requeue = PlayCmd::Requeue {
+                    epoch: old + 1,
+                    reply: reply_tx,
+                };
+                if play_cmd_tx.send(requeue).is_err() {
+                    break;
+                }
+                let Ok(first) = reply_rx.await else {
+                    break;
+                };
+
+                let jobs: Vec<FetchJob> = std::mem::take(&mut dispatcher.history)
+                    .into_iter()
+                    .filter(|job| job.seq >= first && job.epoch == old)
+                    .collect();
+                let count = jobs.len();
+                update_status(&status_tx, |s| s.queue_length = count);
+                if let Some(state) = stream.as_mut() {
+                    state.epoch = old + 1;
+                }
+                debug!("processor: config changed, resynthesizing {count} jobs");
+                for job in jobs {
+                    let job = FetchJob { epoch: old + 1, ..job };
+                    if !dispatcher.send(job).await {
+                        break;
+                    }
+                }
+            }
         }
     }
 }
 
 async fn dispatch_stream_sentences(
     buffer: &mut String,
-    current_epoch: u64,
-    fetch_tx: &mpsc::Sender<FetchJob>,
+    state: &mut StreamState,
+    dispatcher: &mut Dispatcher,
     epoch: &Arc<AtomicU64>,
     status_tx: &watch::Sender<TtsStatus>,
     config: &TtsConfig,
@@ -367,11 +1520,13 @@ async fn dispatch_stream_sentences(
                     s.queue_length += 1;
                 });
                 debug!("stream: force-split dispatch ({} chars)", chunk.len());
-                let _ = fetch_tx
-                    .send(FetchJob {
-                        text: chunk,
-                        epoch: current_epoch,
-                    })
+                dispatcher
+                    .send(FetchJob::streamed(
+                        chunk,
+                        state.epoch,
+                        state.next_position(),
+                        Duration::ZERO,
+                    ))
                     .await;
             }
         }
@@ -381,12 +1536,22 @@ async fn dispatch_stream_sentences(
     let last = sentences.last().unwrap().clone();
     let complete = &sentences[..sentences.len() - 1];
 
-    let mut to_dispatch: Vec<String> = Vec::new();
+    // The pause goes after a sentence's last chunk.
+    let pause = config.pauses.after(SentenceEnd::Sentence);
+    let mut to_dispatch: Vec<(PlaybackPosition, String, Duration)> = Vec::new();
     for sentence in complete {
+        let position = state.next_position();
         if sentence.len() <= config.max_chunk_len {
-            to_dispatch.push(sentence.clone());
+            to_dispatch.push((position, sentence.clone(), pause));
         } else {
-            to_dispatch.extend(split_text(sentence, config.max_chunk_len));
+            to_dispatch.extend(
+                split_text(sentence, config.max_chunk_len)
+                    .into_iter()
+                    .map(|chunk| (position, chunk, Duration::ZERO)),
+            );
+            if let Some(last) = to_dispatch.last_mut() {
+                last.2 = pause;
+            }
         }
     }
 
@@ -396,21 +1561,17 @@ async fn dispatch_stream_sentences(
             s.queue_length += count;
         });
 
-        for text in &to_dispatch {
+        for (_, text, _) in &to_dispatch {
             debug!("stream: dispatch sentence ({} chars)", text.len());
         }
 
-        for text in to_dispatch {
-            if epoch.load(Ordering::SeqCst) != current_epoch {
+        for (position, text, pause) in to_dispatch {
+            if epoch.load(Ordering::SeqCst) != state.epoch {
                 break;
             }
-            if fetch_tx
-                .send(FetchJob {
-                    text,
-                    epoch: current_epoch,
-                })
+            if !dispatcher
+                .send(FetchJob::streamed(text, state.epoch, position, pause))
                 .await
-                .is_err()
             {
                 break;
             }
@@ -428,12 +1589,10 @@ async fn fetcher_task(
     play_cmd_tx: std::sync::mpsc::Sender<PlayCmd>,
     epoch: Arc<AtomicU64>,
     status_tx: watch::Sender<TtsStatus>,
-    kokoro: Arc<KokoroSynth>,
-    voice_name: &str,
-    speed: f32,
+    events_tx: broadcast::Sender<TtsEvent>,
+    synth: Arc<dyn Synthesizer>,
+    config: watch::Receiver<TtsConfig>,
 ) {
-    let voice_name = voice_name.to_string();
-
     loop {
         let job = {
             let mut rx = fetch_rx.lock().await;
@@ -458,116 +1617,541 @@ async fn fetcher_task(
 
         debug!("fetch[{worker_id}]: synth {} chars", job.text.len());
 
-        let result = kokoro.synth(&job.text, &voice_name, speed).await;
+        let config = config.borrow().clone();
+        let result = synth_clip(synth.as_ref(), &config, &job).await;
 
+        // Whoever changed the epoch has already reset the queue length.
         if job.epoch != epoch.load(Ordering::SeqCst) {
             debug!("fetch[{worker_id}]: stale after synth, discarding");
-            update_status(&status_tx, |s| {
-                s.queue_length = s.queue_length.saturating_sub(1);
-            });
+            update_status(&status_tx, |s| s.cache = synth.cache_stats());
             continue;
         }
 
         match result {
-            Ok((samples_f32, took)) => {
-                debug!(
-                    "fetch[{worker_id}]: synthesized {} samples in {:?}",
-                    samples_f32.len(),
-                    took
-                );
-
-                if samples_f32.is_empty() {
-                    warn!("fetch[{worker_id}]: kokoro returned empty audio");
-                    update_status(&status_tx, |s| {
-                        s.queue_length = s.queue_length.saturating_sub(1);
-                    });
-                    continue;
-                }
-
-                let samples_i16 = f32_to_i16(&samples_f32);
-                let (tx, rx) = std::sync::mpsc::channel();
-                let source = StreamingSource::new(rx, PCM_CHANNELS, PCM_SAMPLE_RATE);
-                let _ = tx.send(PcmChunk::Data(samples_i16));
-                let _ = tx.send(PcmChunk::Done);
-
-                if play_cmd_tx.send(PlayCmd::PlayStream(source)).is_err() {
+            Ok(None) => {
+                warn!("fetch[{worker_id}]: synthesizer returned empty audio");
+                update_status(&status_tx, |s| {
+                    s.queue_length = s.queue_length.saturating_sub(1);
+                });
+                continue;
+            }
+            Ok(Some(clip)) => {
+                let play = PlayCmd::Play {
+                    clip: Arc::new(clip),
+                    seq: job.seq,
+                    epoch: job.epoch,
+                };
+                if play_cmd_tx.send(play).is_err() {
                     break;
                 }
             }
             Err(e) => {
                 error!("fetch[{worker_id}]: synthesis failed: {e}");
+                let _ = events_tx.send(TtsEvent::SynthesisError {
+                    position: job.position,
+                    text: job.text,
+                    error: e,
+                });
             }
         }
 
         update_status(&status_tx, |s| {
             s.queue_length = s.queue_length.saturating_sub(1);
+            s.cache = synth.cache_stats();
         });
     }
 }
 
+/// Synthesize `job` and shape it for playback: trimmed, leveled, and padded
+/// with its pauses. `Ok(None)` if the synthesizer returned no audio.
+async fn synth_clip(
+    synth: &dyn Synthesizer,
+    config: &TtsConfig,
+    job: &FetchJob,
+) -> Result<Option<PlayedClip>, String> {
+    let voice_name = job.options.voice.as_deref().unwrap_or(&config.voice);
+    let speed = job.options.speed.unwrap_or(config.speed);
+    // Streamed text arrives without a language; plain and SSML sentences
+    // already have theirs.
+    let language = config.language_for(&job.options, &job.text);
+    let language = Some(language.as_str());
+    let mut synthesis = match &job.parts {
+        Some(parts) => {
+            synth
+                .synth_parts(parts, &job.text, voice_name, speed, language)
+                .await
+        }
+        None => synth.synth(&job.text, voice_name, speed, language).await,
+    }?;
+    debug!(
+        "synthesized {} samples in {:?}",
+        synthesis.samples.len(),
+        synthesis.took
+    );
+    if synthesis.samples.is_empty() {
+        return Ok(None);
+    }
+
+    let sample_rate = synthesis.sample_rate;
+    if let Some(threshold) = config.trim_threshold {
+        trim_clip(&mut synthesis.samples, sample_rate, &mut synthesis.words, threshold);
+    }
+    level_clip(&mut synthesis.samples, sample_rate, config, job.options.gain);
+    let samples = pad_clip(
+        synthesis.samples,
+        sample_rate,
+        &mut synthesis.words,
+        job.pause_before,
+        job.pause_after,
+    );
+    Ok(Some(PlayedClip {
+        position: job.position,
+        text: job.text.clone(),
+        words: synthesis.words,
+        samples: f32_to_i16(&samples),
+        sample_rate,
+    }))
+}
+
+/// Report clip start/finish: set [`TtsStatus::current`] when the sink starts
+/// pulling samples and emit start/finish events. Word timings ride along on
+/// the start event so clients can highlight words as the clip plays.
+///
+/// `current` is left in place on finish — the next clip overwrites it, and
+/// the playback thread clears it once the sink drains.
+fn with_progress_hooks(
+    source: StreamingSource,
+    position: PlaybackPosition,
+    text: &str,
+    words: Vec<WordTiming>,
+    status_tx: &watch::Sender<TtsStatus>,
+    events_tx: &broadcast::Sender<TtsEvent>,
+) -> StreamingSource {
+    let (start_status, start_events, start_text) =
+        (status_tx.clone(), events_tx.clone(), text.to_string());
+    let (finish_events, finish_text) = (events_tx.clone(), text.to_string());
+    source
+        .on_start(move || {
+            update_status(&start_status, |s| {
+                s.state = TtsState::Playing;
+                s.current = Some(position);
+            });
+            let _ = start_events.send(TtsEvent::UtteranceStarted {
+                position,
+                text: start_text,
+                words,
+            });
+        })
+        .on_finish(move |interrupted| {
+            let _ = finish_events.send(TtsEvent::UtteranceFinished {
+                position,
+                text: finish_text,
+                interrupted,
+            });
+        })
+}
+
+/// What the playback thread hooks onto each clip it plays.
+struct ClipHooks {
+    status_tx: watch::Sender<TtsStatus>,
+    events_tx: broadcast::Sender<TtsEvent>,
+    /// Remote listeners, if the output has any.
+    tap: Option<AudioTap>,
+    /// Priority clips, mixed over the queued ones.
+    overlay: Overlay,
+}
+
+impl ClipHooks {
+    /// A source playing `audio` from sample `start`, reporting progress.
+    fn source(&self, audio: &PlayedClip, start: usize) -> StreamingSource {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let _ = tx.send(PcmChunk::Data(audio.samples[start.min(audio.samples.len())..].to_vec()));
+        let _ = tx.send(PcmChunk::Done);
+        with_progress_hooks(
+            StreamingSource::new(rx, PCM_CHANNELS, audio.sample_rate),
+            audio.position,
+            &audio.text,
+            audio.words_from(start),
+            &self.status_tx,
+            &self.events_tx,
+        )
+    }
+
+    /// A clip playing `audio` from sample `start`, reporting progress,
+    /// mixing in the overlay, and feeding the tap.
+    fn queued(
+        &self,
+        audio: Arc<PlayedClip>,
+        start: usize,
+        epoch: u64,
+        level: &OutputLevel,
+        rate: &PlaybackRate,
+    ) -> Queued {
+        let clip = Clip::new(self.source(&audio, start), level.clone(), rate.clone());
+        clip.mix_in(self.overlay.clone());
+        self.tap(&clip, audio.sample_rate, epoch);
+        Queued {
+            clip,
+            audio,
+            start,
+            seq: None,
+            history_id: None,
+        }
+    }
+
+    /// Publish what `clip` plays to remote listeners.
+    fn tap(&self, clip: &Clip, sample_rate: u32, epoch: u64) {
+        if let Some(tap) = self.tap.clone() {
+            clip.tap_to(move |samples| tap.send_audio(epoch, sample_rate, samples));
+        }
+    }
+
+    /// A priority clip, added to the overlay. It reports progress; the
+    /// clip it's mixed into feeds the tap.
+    fn overlaid(&self, audio: Arc<PlayedClip>, level: &OutputLevel, rate: &PlaybackRate) -> Queued {
+        let clip = Clip::new(self.source(&audio, 0), level.clone(), rate.clone());
+        self.overlay.push(clip.clone());
+        Queued {
+            clip,
+            audio,
+            start: 0,
+            seq: None,
+            history_id: None,
+        }
+    }
+}
+
+/// A clip queued or playing on the playback thread. The sink holds a clone.
+struct Queued {
+    clip: Clip,
+    audio: Arc<PlayedClip>,
+    /// Sample of `audio` the clip starts at (after a rewind, mid-clip).
+    start: usize,
+    /// Seq of the job it came from; `None` for a replay.
+    seq: Option<u64>,
+    /// Its number in the history, once it has started.
+    history_id: Option<u64>,
+}
+
+/// Note the clips that have started: move `played` past their jobs and add
+/// them to the history.
+fn track_started(queue: &mut VecDeque<Queued>, history: &mut History, played: &AtomicU64) {
+    for queued in queue.iter_mut().filter(|q| q.clip.is_started()) {
+        if let Some(seq) = queued.seq {
+            played.fetch_max(seq + 1, Ordering::SeqCst);
+        }
+        if queued.history_id.is_none() {
+            queued.history_id = Some(history.push(queued.audio.clone()));
+        }
+    }
+}
+
 // ─── Playback OS thread ───────────────────────────────────────────────────
 
 fn playback_thread(
     cmd_rx: std::sync::mpsc::Receiver<PlayCmd>,
-    status_tx: watch::Sender<TtsStatus>,
+    hooks: ClipHooks,
+    mut sink: Box<dyn AudioSink>,
+    level: OutputLevel,
+    rate: PlaybackRate,
+    played: Arc<AtomicU64>,
 ) {
-    let (_stream, stream_handle) = match OutputStream::try_default() {
-        Ok(pair) => pair,
-        Err(e) => {
-            error!("playback: failed to open audio output: {e}");
-            return;
-        }
-    };
-
-    let mut sink = Sink::try_new(&stream_handle).expect("failed to create sink");
+    let status_tx = &hooks.status_tx;
+    // Clips queued or playing, in order, for skip, stop, and replay.
+    let mut queue: VecDeque<Queued> = VecDeque::new();
+    // Priority clips playing over the queue, with their epochs. Their level
+    // follows the volume but is never ducked.
+    let mut priority: VecDeque<(Queued, u64)> = VecDeque::new();
+    let priority_level = OutputLevel::new(level.volume());
+    let mut history = History::default();
+    let mut paused = false;
+    let mut draining = false;
+    let mut wait = Duration::ZERO;
+    // The duck asked for with `duck`, apart from priority speech's.
+    let mut duck = 1.0;
+    let mut duck_until: Option<Instant> = None;
+    // Clips from jobs before a requeue are dropped on arrival.
+    let mut min_epoch = 0;
 
     loop {
-        if sink.empty() {
-            update_status(&status_tx, |s| {
+        track_started(&mut queue, &mut history, &played);
+        queue.retain(|q| !q.clip.is_done());
+        priority.retain(|(q, _)| !q.clip.is_done());
+        if queue.is_empty() && !priority.is_empty() {
+            // Nothing to mix into: the priority clips become the queue.
+            hooks.overlay.take();
+            for (q, epoch) in priority.drain(..) {
+                hooks.tap(&q.clip, q.audio.sample_rate, epoch);
+                sink.append(q.clip.clone());
+                queue.push_back(q);
+            }
+        }
+        if draining && queue.is_empty() {
+            break;
+        }
+        if queue.is_empty() {
+            update_status(status_tx, |s| {
                 if s.state == TtsState::Playing {
                     s.state = TtsState::Idle;
                 }
+                s.current = None;
             });
         }
 
-        match cmd_rx.recv() {
-            Ok(PlayCmd::PlayStream(source)) => {
-                debug!("playback: source appended to sink");
-                sink.append(source);
-                update_status(&status_tx, |s| s.state = TtsState::Playing);
+        // Wake periodically so a drained sink is reported as idle promptly.
+        match cmd_rx.recv_timeout(wait.min(IDLE_POLL_INTERVAL)) {
+            Ok(PlayCmd::Play { epoch, .. }) if epoch < min_epoch => {
+                debug!("playback: dropping clip from before a requeue");
+            }
+            Ok(PlayCmd::Play { clip, seq, epoch }) => {
+                debug!("playback: clip appended to sink");
+                let queued = Queued {
+                    seq: Some(seq),
+                    ..hooks.queued(clip, 0, epoch, &level, &rate)
+                };
+                sink.append(queued.clip.clone());
+                queue.push_back(queued);
+                update_status(status_tx, |s| s.state = TtsState::Playing);
+            }
+            Ok(PlayCmd::Overlay { clip, epoch }) => {
+                debug!("playback: priority clip over the queue");
+                priority.push_back((hooks.overlaid(clip, &priority_level, &rate), epoch));
+                update_status(status_tx, |s| s.state = TtsState::Playing);
+            }
+            Ok(PlayCmd::Requeue { epoch, reply }) => {
+                min_epoch = epoch;
+                track_started(&mut queue, &mut history, &played);
+                let first = played.load(Ordering::SeqCst);
+                // Everything from `first` on is synthesized again, so it
+                // goes even if it started a moment ago.
+                queue.retain(|q| {
+                    let requeued = q.seq.is_some_and(|seq| seq >= first);
+                    if requeued {
+                        q.clip.cancel();
+                    }
+                    !requeued
+                });
+                let _ = reply.send(first);
+            }
+            Ok(PlayCmd::Replay { replay, epoch, reply }) => {
+                track_started(&mut queue, &mut history, &played);
+                // The clip playing now, and how far into it.
+                let current = queue
+                    .front()
+                    .filter(|q| !q.clip.is_done())
+                    .and_then(|q| Some((q.history_id?, q.start + q.clip.position())));
+                let segments = match replay {
+                    Replay::Last => history.replay_last(current.map(|(id, _)| id)),
+                    Replay::Previous => history.previous(current.map(|(id, _)| id)),
+                    Replay::Rewind(by) => history.rewind(by, current),
+                };
+                if segments.is_empty() {
+                    let _ = reply.send(Err("nothing has played yet".into()));
+                } else {
+                    // The current clip is in the segments; the rest of the
+                    // queue follows them.
+                    if current.is_some()
+                        && let Some(q) = queue.pop_front()
+                    {
+                        q.clip.cancel();
+                    }
+                    sink.clear();
+                    let rest = std::mem::take(&mut queue);
+                    for segment in segments {
+                        queue.push_back(Queued {
+                            history_id: Some(segment.id),
+                            ..hooks.queued(segment.clip, segment.start, epoch, &level, &rate)
+                        });
+                    }
+                    queue.extend(rest);
+                    for q in &queue {
+                        sink.append(q.clip.clone());
+                    }
+                    if paused {
+                        sink.pause();
+                    } else {
+                        sink.play();
+                    }
+                    update_status(status_tx, |s| s.state = TtsState::Playing);
+                    let _ = reply.send(Ok(()));
+                }
             }
             Ok(PlayCmd::Skip) => {
-                sink.skip_one();
-                if sink.empty() {
-                    update_status(&status_tx, |s| s.state = TtsState::Idle);
+                track_started(&mut queue, &mut history, &played);
+                // Priority speech is what's heard over the rest. The sink
+                // moves on once the clip's source is gone.
+                if let Some((q, _)) = priority.pop_front() {
+                    q.clip.cancel();
+                } else if let Some(q) = queue.pop_front() {
+                    q.clip.cancel();
+                }
+                if queue.is_empty() {
+                    update_status(status_tx, |s| {
+                        s.state = TtsState::Idle;
+                        s.current = None;
+                    });
                 }
             }
             Ok(PlayCmd::Stop) => {
-                sink.stop();
-                sink = Sink::try_new(&stream_handle).expect("failed to create sink");
-                update_status(&status_tx, |s| s.state = TtsState::Idle);
+                track_started(&mut queue, &mut history, &played);
+                for q in queue.drain(..).chain(priority.drain(..).map(|(q, _)| q)) {
+                    q.clip.cancel();
+                }
+                hooks.overlay.take();
+                sink.clear();
+                sink.play();
+                if paused {
+                    paused = false;
+                    let _ = hooks.events_tx.send(TtsEvent::Resumed);
+                }
+                update_status(status_tx, |s| {
+                    s.state = TtsState::Idle;
+                    s.current = None;
+                });
             }
-            Ok(PlayCmd::Pause) => {
+            Ok(PlayCmd::Pause) if !paused => {
                 sink.pause();
+                paused = true;
+                let _ = hooks.events_tx.send(TtsEvent::Paused);
+            }
+            Ok(PlayCmd::Resume) if paused => {
+                sink.play();
+                paused = false;
+                let _ = hooks.events_tx.send(TtsEvent::Resumed);
+            }
+            Ok(PlayCmd::Pause | PlayCmd::Resume) => {}
+            Ok(PlayCmd::SetDevice(name)) => sink.set_device(name),
+            Ok(PlayCmd::SetVolume(volume)) => {
+                level.set_volume(volume);
+                priority_level.set_volume(volume);
             }
-            Ok(PlayCmd::Resume) => {
+            Ok(PlayCmd::SetRate(new_rate)) => rate.set(new_rate),
+            Ok(PlayCmd::Duck { level, until }) => {
+                duck = level;
+                duck_until = until;
+            }
+            Ok(PlayCmd::Unduck) => {
+                duck = 1.0;
+                duck_until = None;
+            }
+            Ok(PlayCmd::Shutdown { drain: true }) => {
+                // A paused queue would never drain.
+                draining = true;
+                if paused {
+                    paused = false;
+                    let _ = hooks.events_tx.send(TtsEvent::Resumed);
+                }
                 sink.play();
             }
-            Err(_) => {
-                sink.stop();
+            Ok(PlayCmd::Shutdown { drain: false }) => {
+                let playing = queue
+                    .front()
+                    .is_some_and(|q| q.clip.is_started() && !q.clip.is_done());
+                if playing && !paused {
+                    fade_out(&mut *sink, &[&level, &priority_level]);
+                }
                 break;
             }
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+
+        if duck_until.is_some_and(|until| Instant::now() >= until) {
+            duck = 1.0;
+            duck_until = None;
         }
+        // Priority speech ducks the queue under it.
+        level.set_duck(if priority.is_empty() {
+            duck
+        } else {
+            duck.min(DEFAULT_DUCK_LEVEL)
+        });
+
+        wait = sink.tick();
+        let device = sink.device();
+        update_status(status_tx, |s| {
+            s.device = device;
+            s.volume = level.volume();
+            s.rate = rate.get();
+            s.ducked = level.duck() < 1.0;
+        });
+    }
+
+    for q in queue.drain(..).chain(priority.drain(..).map(|(q, _)| q)) {
+        q.clip.cancel();
+    }
+    sink.clear();
+    update_status(status_tx, |s| {
+        s.state = TtsState::Idle;
+        s.queue_length = 0;
+        s.current = None;
+    });
+}
+
+/// Duck to silence and keep the sink going while the level ramps down, so
+/// shutting down doesn't cut off mid-word.
+fn fade_out(sink: &mut dyn AudioSink, levels: &[&OutputLevel]) {
+    for level in levels {
+        level.set_duck(0.0);
+    }
+    let end = Instant::now() + audio_sink::LEVEL_RAMP;
+    loop {
+        let left = end.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            break;
+        }
+        std::thread::sleep(sink.tick().max(Duration::from_millis(5)).min(left));
     }
 }
 
+/// Apply `f` to the status, notifying subscribers only if it actually changed.
 fn update_status(tx: &watch::Sender<TtsStatus>, f: impl FnOnce(&mut TtsStatus)) {
-    tx.send_modify(f);
+    tx.send_if_modified(|status| {
+        let before = status.clone();
+        f(status);
+        *status != before
+    });
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::mock_synth::MockSynth;
+    use nayru_core::types::Pauses;
+
+    /// An engine on [`MockSynth`] that plays into a [`NullSink`], in real
+    /// time or as fast as it can.
+    ///
+    /// [`NullSink`]: audio_sink::NullSink
+    fn null_engine(realtime: bool) -> TtsEngine {
+        TtsEngine::with_sink(TtsConfig::default(), Arc::new(MockSynth::default()), move || {
+            Box::new(if realtime {
+                audio_sink::NullSink::realtime(Duration::ZERO)
+            } else {
+                audio_sink::NullSink::unthrottled()
+            })
+        })
+    }
+
+    async fn next_event(events: &mut broadcast::Receiver<TtsEvent>) -> TtsEvent {
+        tokio::time::timeout(Duration::from_secs(10), events.recv())
+            .await
+            .expect("playback stalled")
+            .unwrap()
+    }
+
+    /// Waits for the next `count` utterances to finish, none cut off, and
+    /// returns their texts.
+    async fn finished(events: &mut broadcast::Receiver<TtsEvent>, count: usize) -> Vec<String> {
+        let mut texts = Vec::new();
+        while texts.len() < count {
+            if let TtsEvent::UtteranceFinished { text, interrupted, .. } = next_event(events).await {
+                assert!(!interrupted, "{text} was cut off");
+                texts.push(text);
+            }
+        }
+        texts
+    }
 
     #[test]
     fn f32_to_i16_conversion() {
@@ -583,15 +2167,404 @@ mod tests {
         assert_eq!(result, vec![32767, -32768]);
     }
 
+    #[tokio::test]
+    async fn render_through_mock_synth() {
+        let mock = MockSynth::default();
+        let engine = TtsEngine::new(TtsConfig::default(), Arc::new(mock.clone()));
+
+        let audio = engine
+            .render("One two. Three.", &SpeakOptions::default())
+            .await
+            .unwrap();
+        let expected: usize = ["One two.", "Three."]
+            .iter()
+            .map(|s| mock.render(s, "af_heart", 1.0).samples.len())
+            .sum();
+        assert_eq!(audio.sample_rate, 24_000);
+        // Plus the default 250 ms pause between the sentences.
+        assert_eq!(audio.pcm.len(), expected + 6_000);
+
+        let unknown = SpeakOptions {
+            voice: Some("nope".into()),
+            ..SpeakOptions::default()
+        };
+        assert!(engine.render("Hi.", &unknown).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn speaks_headless_through_null_sink() {
+        let engine = null_engine(false);
+        let mut events = engine.subscribe_events();
+        engine.speak("One two. Three.", SpeakOptions::default()).unwrap();
+        assert_eq!(finished(&mut events, 2).await, vec!["One two.", "Three."]);
+        assert_eq!(engine.status().device, None);
+    }
+
+    #[tokio::test]
+    async fn pause_and_resume_report_only_changes() {
+        let engine = null_engine(false);
+        let mut events = engine.subscribe_events();
+        engine.pause();
+        engine.pause();
+        engine.resume();
+        engine.resume();
+        engine.pause();
+        // Stopping also lifts the pause.
+        engine.stop();
+        engine.resume();
+
+        let mut seen = Vec::new();
+        while seen.len() < 4 {
+            match next_event(&mut events).await {
+                event @ (TtsEvent::Paused | TtsEvent::Resumed) => seen.push(event),
+                _ => {}
+            }
+        }
+        use TtsEvent::{Paused, Resumed};
+        assert_eq!(seen, vec![Paused, Resumed, Paused, Resumed]);
+        engine.shutdown(ShutdownMode::Immediate).await;
+        let rest: Vec<_> = std::iter::from_fn(|| events.try_recv().ok()).collect();
+        assert!(!rest.iter().any(|e| matches!(e, TtsEvent::Paused | TtsEvent::Resumed)), "{rest:?}");
+    }
+
+    #[tokio::test]
+    async fn replay_and_rewind_play_history_again() {
+        let engine = null_engine(false);
+        assert!(engine.replay_last().await.unwrap_err().contains("nothing"));
+        assert!(engine.rewind(-1.0).await.is_err());
+
+        let mut events = engine.subscribe_events();
+        engine.speak("One two. Three.", SpeakOptions::default()).unwrap();
+        assert_eq!(finished(&mut events, 2).await, vec!["One two.", "Three."]);
+
+        engine.previous().await.unwrap();
+        assert_eq!(finished(&mut events, 1).await, vec!["Three."]);
+        engine.rewind(60.0).await.unwrap();
+        assert_eq!(finished(&mut events, 2).await, vec!["One two.", "Three."]);
+    }
+
+    #[tokio::test]
+    async fn rewinding_twice_goes_back_from_the_first_rewind() {
+        let engine = null_engine(true);
+        let mut events = engine.subscribe_events();
+        let mut started = async || loop {
+            if let TtsEvent::UtteranceStarted { words, .. } = next_event(&mut events).await {
+                return words;
+            }
+        };
+        // About 3.5 s of mock speech; "Alpha" is the first 0.3 s.
+        let text = "Alpha bravo charlie delta echo foxtrot golf hotel india.";
+        engine.speak(text, SpeakOptions::default()).unwrap();
+        assert_eq!(started().await.len(), 9);
+        tokio::time::sleep(Duration::from_millis(1200)).await;
+        engine.pause();
+
+        // Each rewind goes back from where the last one landed, not from
+        // the start of the sentence.
+        engine.rewind(0.3).await.unwrap();
+        engine.rewind(0.3).await.unwrap();
+        engine.resume();
+        let words = started().await;
+        assert!(words.len() < 9 && words[0].word != "Alpha", "{words:?}");
+        engine.stop();
+    }
+
+    #[tokio::test]
+    async fn priority_speech_plays_over_the_queue_and_ducks_it() {
+        let engine = null_engine(true);
+        let mut events = engine.subscribe_events();
+        let mut status = engine.subscribe_status();
+        let priority = SpeakOptions {
+            priority: true,
+            ..SpeakOptions::default()
+        };
+
+        // About 3.5 s of mock speech underneath.
+        let text = "Alpha bravo charlie delta echo foxtrot golf hotel india.";
+        engine.speak(text, SpeakOptions::default()).unwrap();
+        while !matches!(next_event(&mut events).await, TtsEvent::UtteranceStarted { .. }) {}
+        let urgent = engine.speak("Hey there.", priority.clone()).unwrap();
+        let ducked = tokio::time::timeout(Duration::from_secs(5), status.wait_for(|s| s.ducked)).await;
+        assert!(ducked.expect("queue not ducked").is_ok());
+
+        // It plays to the end while the queue keeps going underneath.
+        loop {
+            match next_event(&mut events).await {
+                TtsEvent::UtteranceFinished { position, .. } => {
+                    assert_eq!(position.utterance_id, urgent.utterance_id);
+                    break;
+                }
+                TtsEvent::UtteranceStarted { position, .. } => {
+                    assert_eq!(position.utterance_id, urgent.utterance_id);
+                }
+                _ => {}
+            }
+        }
+        let lifted = tokio::time::timeout(Duration::from_secs(5), status.wait_for(|s| !s.ducked)).await;
+        assert!(lifted.expect("duck never lifted").is_ok());
+        assert_eq!(engine.status().state, TtsState::Playing);
+
+        // With nothing else playing it's queued as usual.
+        engine.stop();
+        let alone = engine.speak("Hey.", priority).unwrap();
+        loop {
+            if let TtsEvent::UtteranceFinished { position, interrupted: false, .. } = next_event(&mut events).await {
+                assert_eq!(position.utterance_id, alone.utterance_id);
+                break;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn shutdown_drains_or_drops_the_queue() {
+        let drained = |events: &mut broadcast::Receiver<TtsEvent>| {
+            std::iter::from_fn(|| events.try_recv().ok())
+                .filter_map(|event| match event {
+                    TtsEvent::UtteranceFinished { interrupted, .. } => Some(interrupted),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let engine = null_engine(false);
+        let mut events = engine.subscribe_events();
+        engine.speak("One two. Three. Four.", SpeakOptions::default()).unwrap();
+        engine.pause();
+        tokio::time::timeout(Duration::from_secs(5), engine.shutdown(ShutdownMode::Drain))
+            .await
+            .expect("drain stalled");
+        assert_eq!(drained(&mut events), vec![false, false, false]);
+
+        let engine = null_engine(true);
+        let mut events = engine.subscribe_events();
+        engine.speak("One two. Three. Four.", SpeakOptions::default()).unwrap();
+        while !matches!(next_event(&mut events).await, TtsEvent::UtteranceStarted { .. }) {}
+        tokio::time::timeout(Duration::from_secs(5), engine.shutdown(ShutdownMode::Immediate))
+            .await
+            .expect("shutdown stalled");
+        assert_eq!(drained(&mut events), vec![true]);
+        assert_eq!(engine.status().state, TtsState::Idle);
+        // Already down: returns at once.
+        engine.shutdown(ShutdownMode::Drain).await;
+    }
+
+    #[tokio::test]
+    async fn volume_rate_and_ducking_show_in_status() {
+        let engine = null_engine(false);
+        assert!(engine.set_volume(1.5).is_err());
+        assert!(engine.set_rate(0.25).is_err());
+        assert!(engine.duck(-0.1, None).is_err());
+        let loud = SpeakOptions {
+            gain: Some(10.0),
+            ..SpeakOptions::default()
+        };
+        assert!(engine.validate(&loud).unwrap_err().contains("gain"));
+        let endless = SpeakOptions {
+            pauses: Some(Pauses {
+                paragraph_ms: u64::MAX,
+                ..Pauses::default()
+            }),
+            ..SpeakOptions::default()
+        };
+        assert!(engine.validate(&endless).unwrap_err().contains("pause"));
+
+        let mut status = engine.subscribe_status();
+        engine.set_volume(0.5).unwrap();
+        engine.set_rate(1.5).unwrap();
+        engine.duck(0.2, Some(Duration::from_millis(50))).unwrap();
+        let ducked = tokio::time::timeout(
+            Duration::from_secs(5),
+            status.wait_for(|s| s.volume == 0.5 && s.rate == 1.5 && s.ducked),
+        )
+        .await;
+        assert!(ducked.expect("duck not applied").is_ok());
+        // The timeout lifts the duck by itself.
+        let lifted = tokio::time::timeout(Duration::from_secs(5), status.wait_for(|s| !s.ducked)).await;
+        assert!(lifted.expect("duck never lifted").is_ok());
+    }
+
+    /// A [`MockSynth`] that remembers the voice each text was last
+    /// synthesized with.
+    #[derive(Default)]
+    struct VoiceLog {
+        mock: MockSynth,
+        voices: std::sync::Mutex<std::collections::HashMap<String, String>>,
+    }
+
+    impl Synthesizer for VoiceLog {
+        fn synth<'a>(
+            &'a self,
+            text: &'a str,
+            voice: &'a str,
+            speed: f32,
+            language: Option<&'a str>,
+        ) -> futures_util::future::BoxFuture<'a, Result<crate::synth::Synthesis, String>> {
+            self.voices.lock().unwrap().insert(text.to_string(), voice.to_string());
+            self.mock.synth(text, voice, speed, language)
+        }
+
+        fn voices(&self) -> Vec<String> {
+            self.mock.voices()
+        }
+    }
+
+    #[tokio::test]
+    async fn update_config_revoices_queued_sentences() {
+        let synth = Arc::new(VoiceLog::default());
+        let engine = TtsEngine::with_sink(TtsConfig::default(), synth.clone(), || {
+            Box::new(audio_sink::NullSink::realtime(Duration::ZERO))
+        });
+        assert!(engine.update_config(|c| c.voice = "nope".into()).is_err());
+        assert!(engine.update_config(|c| c.speed = 0.0).is_err());
+        assert!(engine.update_config(|c| c.max_chunk_len = 0).is_err());
+        assert_eq!(engine.config().voice, "af_heart");
+
+        let mut events = engine.subscribe_events();
+        engine
+            .speak("First one. Second one. Third one.", SpeakOptions::default())
+            .unwrap();
+        // All three are synthesized (they're short) by the time the first plays.
+        while !matches!(next_event(&mut events).await, TtsEvent::UtteranceStarted { .. }) {}
+        engine.update_config(|c| c.voice = "am_adam".into()).unwrap();
+
+        assert_eq!(
+            finished(&mut events, 3).await,
+            vec!["First one.", "Second one.", "Third one."]
+        );
+        let voices = synth.voices.lock().unwrap().clone();
+        assert_eq!(voices["First one."], "af_heart");
+        assert_eq!(voices["Second one."], "am_adam");
+        assert_eq!(voices["Third one."], "am_adam");
+        assert_eq!(engine.status().voice, "am_adam");
+    }
+
+    #[tokio::test]
+    async fn render_text_inserts_sentence_gaps() {
+        let mock = MockSynth::default();
+        let mut progress = Vec::new();
+        let config = TtsConfig {
+            pauses: Pauses {
+                sentence_ms: 100,
+                list_item_ms: 0,
+                paragraph_ms: 500,
+            },
+            ..TtsConfig::default()
+        };
+        let audio = render_text(
+            &mock,
+            &config,
+            "**One** two. Three.\n\nFour.",
+            &SpeakOptions::default(),
+            |done, total| progress.push((done, total)),
+        )
+        .await
+        .unwrap();
+        let speech: usize = ["One two.", "Three.", "Four."]
+            .iter()
+            .map(|s| mock.render(s, "af_heart", 1.0).samples.len())
+            .sum();
+        assert_eq!(audio.pcm.len(), speech + 2_400 + 12_000);
+        assert_eq!(progress, vec![(1, 3), (2, 3), (3, 3)]);
+    }
+
+    #[test]
+    fn trim_clip_cuts_edge_silence_and_shifts_words() {
+        let mut samples = vec![0.0; 2_400];
+        samples.extend(vec![0.5; 100]);
+        samples.extend(vec![0.001; 4_800]);
+        let mut words = vec![WordTiming {
+            word: "hi".into(),
+            start: 0.1,
+            end: 0.11,
+        }];
+        trim_clip(&mut samples, 24_000, &mut words, -50.0);
+        // 20 ms of margin on each side of the loud part.
+        assert_eq!(samples.len(), 480 + 100 + 480);
+        assert!((words[0].start - 0.02).abs() < 1e-6);
+
+        let mut silence = vec![0.0; 100];
+        trim_clip(&mut silence, 24_000, &mut [], -50.0);
+        assert_eq!(silence.len(), 100);
+    }
+
+    #[test]
+    fn pad_clip_adds_silence_and_shifts_words() {
+        let mut words = vec![WordTiming {
+            word: "hi".into(),
+            start: 0.0,
+            end: 0.5,
+        }];
+        let samples = pad_clip(
+            vec![0.5; 10],
+            24_000,
+            &mut words,
+            Duration::from_millis(10),
+            Duration::from_millis(5),
+        );
+        assert_eq!(samples.len(), 240 + 10 + 120);
+        assert_eq!(samples[239], 0.0);
+        assert_eq!(samples[240], 0.5);
+        assert!((words[0].start - 0.01).abs() < 1e-6);
+        assert!((words[0].end - 0.51).abs() < 1e-6);
+    }
+
     #[test]
-    fn parse_voice_known() {
-        let v = parse_voice("af_heart", 1.0);
-        assert!(matches!(v, Voice::AfHeart(_)));
+    fn ssml_sentences_group_segments() {
+        let segments = parse_ssml(
+            r#"<speak><break time="1s"/>Say <phoneme ph="təˈmɑːtoʊ">tomato</phoneme> now. Next one.<break time="500ms"/><voice name="am_adam">Bye.</voice></speak>"#,
+        )
+        .unwrap();
+        let sentences = SsmlSentences::build(&segments, &SpeakOptions::default(), &TtsConfig::default());
+        assert_eq!(sentences.len(), 3);
+
+        assert_eq!(sentences[0].text, "Say tomato now.");
+        assert_eq!(
+            sentences[0].parts,
+            Some(vec![
+                SpeechPart::Text("Say".into()),
+                SpeechPart::Ipa("təˈmɑːtoʊ".into()),
+                SpeechPart::Text("now.".into()),
+            ])
+        );
+        assert_eq!(sentences[0].pause_before, Duration::from_secs(1));
+        assert_eq!(sentences[0].pause_after, Duration::from_millis(250));
+
+        assert_eq!(sentences[1].text, "Next one.");
+        assert_eq!(sentences[1].parts, None);
+        assert_eq!(sentences[1].pause_after, Duration::from_millis(500));
+
+        assert_eq!(sentences[2].options.voice.as_deref(), Some("am_adam"));
+        assert_eq!(sentences[2].pause_after, Duration::ZERO);
     }
 
     #[test]
-    fn parse_voice_unknown_falls_back() {
-        let v = parse_voice("nonexistent_voice", 1.0);
-        assert!(matches!(v, Voice::AfHeart(_)));
+    fn ssml_rate_scales_speed() {
+        let segments = parse_ssml(r#"<prosody rate="50%">Slowly now.</prosody>"#).unwrap();
+        let options = SpeakOptions {
+            speed: Some(1.2),
+            ..SpeakOptions::default()
+        };
+        let sentences = SsmlSentences::build(&segments, &options, &TtsConfig::default());
+        assert!((sentences[0].options.speed.unwrap() - 0.6).abs() < 1e-6);
+
+        let sentences = SsmlSentences::build(&segments, &SpeakOptions::default(), &TtsConfig::default());
+        assert!((sentences[0].options.speed.unwrap() - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn plain_sentences_follow_voice_language() {
+        let config = TtsConfig::default();
+        let english = Sentence::plain("I have 2 cats.", SentenceEnd::Sentence, &SpeakOptions::default(), &config);
+        assert_eq!(english.text, "I have two cats.");
+        assert_eq!(english.options.language.as_deref(), Some("en-us"));
+
+        let options = SpeakOptions {
+            voice: Some("ef_dora".into()),
+            ..SpeakOptions::default()
+        };
+        let spanish = Sentence::plain("Tengo 2 gatos.", SentenceEnd::Paragraph, &options, &config);
+        assert_eq!(spanish.text, "Tengo dos gatos.");
+        assert_eq!(spanish.options.language.as_deref(), Some("es"));
+        assert_eq!(spanish.pause_after, Duration::from_millis(700));
     }
 }
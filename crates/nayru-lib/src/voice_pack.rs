@@ -0,0 +1,457 @@
+//! Kokoro voice packs — stock voices, weighted blends, and user voices.
+//!
+//! A voice pack holds one style vector per input length. Blends such as
+//! `af_heart:0.6+bf_emma:0.4` interpolate those vectors, weights normalized
+//! to sum to 1 (a missing weight counts as 1). A blend is accepted anywhere
+//! a voice name is, and can be saved under a name of its own.
+//!
+//! User voices live in a directory of `.bin` files in the same format as
+//! `voices.bin` (bincode `HashMap<String, VoicePack>`), loaded next to the
+//! stock voices. Saved blends are written there as `<name>.bin`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+
+use nayru_core::types::VoiceKind;
+
+use crate::synth::unknown_voice;
+
+/// Voice style pack: indexed by [sequence_length][style_index][feature_dim].
+pub type VoicePack = Vec<Vec<Vec<f32>>>;
+
+/// Blends kept after first use, so per-sentence lookups don't re-mix.
+const BLEND_CACHE_CAPACITY: usize = 32;
+
+/// Where user voices live by default (e.g. `~/.local/share/nayru/voices`).
+pub fn default_user_voices_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("nayru").join("voices"))
+}
+
+/// Decode a voices file (`voices.bin` format).
+pub fn decode_voices(data: &[u8]) -> Result<HashMap<String, VoicePack>, String> {
+    let (voices, _): (HashMap<String, VoicePack>, _) =
+        bincode::decode_from_slice(data, bincode::config::standard())
+            .map_err(|e| format!("failed to decode voices: {e}"))?;
+    Ok(voices)
+}
+
+fn encode_voices(voices: &HashMap<String, VoicePack>) -> Result<Vec<u8>, String> {
+    bincode::encode_to_vec(voices, bincode::config::standard())
+        .map_err(|e| format!("failed to encode voices: {e}"))
+}
+
+/// A parsed blend: voice names and weights summing to 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoiceBlend {
+    pub components: Vec<(String, f32)>,
+}
+
+impl VoiceBlend {
+    /// Whether `voice` uses blend syntax rather than naming one voice.
+    pub fn is_blend(voice: &str) -> bool {
+        voice.contains(['+', ':'])
+    }
+
+    /// Parse `name[:weight]+name[:weight]...`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut components = Vec::new();
+        for part in spec.split('+') {
+            let (name, weight) = match part.split_once(':') {
+                Some((name, weight)) => {
+                    let weight: f32 = weight.trim().parse().map_err(|_| {
+                        format!("invalid weight '{}' in voice blend '{spec}'", weight.trim())
+                    })?;
+                    (name.trim(), weight)
+                }
+                None => (part.trim(), 1.0),
+            };
+            if name.is_empty() {
+                return Err(format!("empty voice name in blend '{spec}'"));
+            }
+            if !(weight.is_finite() && weight > 0.0) {
+                return Err(format!(
+                    "invalid weight {weight} for '{name}' in voice blend '{spec}'; must be positive"
+                ));
+            }
+            components.push((name.to_string(), weight));
+        }
+        let total: f32 = components.iter().map(|(_, w)| w).sum();
+        for (_, weight) in &mut components {
+            *weight /= total;
+        }
+        Ok(Self { components })
+    }
+}
+
+/// Weighted sum of same-shaped packs.
+pub fn blend_packs(packs: &[(&VoicePack, f32)]) -> Result<VoicePack, String> {
+    let Some(((first, _), rest)) = packs.split_first() else {
+        return Err("empty voice blend".to_string());
+    };
+    if rest.iter().any(|(p, _)| shape(p) != shape(first)) {
+        return Err("voice packs have different shapes and can't be blended".to_string());
+    }
+
+    let mut out: VoicePack = first
+        .iter()
+        .map(|styles| styles.iter().map(|v| vec![0.0; v.len()]).collect())
+        .collect();
+    for (pack, weight) in packs {
+        for (out_styles, styles) in out.iter_mut().zip(pack.iter()) {
+            for (out_vec, vec) in out_styles.iter_mut().zip(styles) {
+                for (o, x) in out_vec.iter_mut().zip(vec) {
+                    *o += weight * x;
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Lengths, styles per length, and style vector size of a pack, as far as
+/// its first entries show.
+fn shape(pack: &VoicePack) -> (usize, usize, usize) {
+    (
+        pack.len(),
+        pack.first().map_or(0, Vec::len),
+        pack.first().and_then(|s| s.first()).map_or(0, Vec::len),
+    )
+}
+
+/// Stock voices plus user voices, with blend resolution.
+pub struct VoiceLibrary {
+    stock: HashMap<String, Arc<VoicePack>>,
+    custom: RwLock<HashMap<String, Arc<VoicePack>>>,
+    /// Where [`save`](Self::save) writes; set by [`load_dir`](Self::load_dir).
+    user_dir: RwLock<Option<PathBuf>>,
+    blends: Mutex<HashMap<String, Arc<VoicePack>>>,
+}
+
+impl VoiceLibrary {
+    pub fn new(stock: HashMap<String, VoicePack>) -> Self {
+        Self {
+            stock: stock.into_iter().map(|(k, v)| (k, Arc::new(v))).collect(),
+            custom: RwLock::default(),
+            user_dir: RwLock::default(),
+            blends: Mutex::default(),
+        }
+    }
+
+    /// Names of all stock and user voices, sorted.
+    pub fn names(&self) -> Vec<String> {
+        let custom = self.custom.read().unwrap();
+        let mut names: Vec<String> = self.stock.keys().chain(custom.keys()).cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Names of user voices, sorted.
+    pub fn custom_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.custom.read().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Identifies what `voice` sounds like, for synthesis cache keys. Stock
+    /// voices are pinned by `voices.bin` itself, so only user voices and
+    /// blends hash their pack: saving over a voice changes its id and
+    /// nobody else's.
+    pub fn content_id(&self, voice: &str) -> Result<String, String> {
+        if self.stock.contains_key(voice) {
+            return Ok(String::new());
+        }
+        let pack = self.get(voice)?;
+        let mut hasher = Sha256::new();
+        for styles in pack.iter() {
+            for vec in styles {
+                for x in vec {
+                    hasher.update(x.to_le_bytes());
+                }
+                hasher.update([0]);
+            }
+            hasher.update([1]);
+        }
+        Ok(hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect())
+    }
+
+    /// Whether `voice` is stock, custom, or (anything else) a blend.
+    pub fn kind(&self, voice: &str) -> VoiceKind {
+        if self.stock.contains_key(voice) {
+            VoiceKind::Builtin
+        } else if self.custom.read().unwrap().contains_key(voice) {
+            VoiceKind::Custom
+        } else {
+            VoiceKind::Blend
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<Arc<VoicePack>> {
+        if let Some(pack) = self.stock.get(name) {
+            return Some(pack.clone());
+        }
+        self.custom.read().unwrap().get(name).cloned()
+    }
+
+    /// The pack for a voice name or blend.
+    pub fn get(&self, voice: &str) -> Result<Arc<VoicePack>, String> {
+        if let Some(pack) = self.lookup(voice) {
+            return Ok(pack);
+        }
+        if !VoiceBlend::is_blend(voice) {
+            return Err(unknown_voice(voice, &self.names()));
+        }
+        if let Some(pack) = self.blends.lock().unwrap().get(voice) {
+            return Ok(pack.clone());
+        }
+
+        let blend = VoiceBlend::parse(voice)?;
+        let packs = blend
+            .components
+            .iter()
+            .map(|(name, weight)| {
+                self.lookup(name)
+                    .map(|pack| (pack, *weight))
+                    .ok_or_else(|| unknown_voice(name, &self.names()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let refs: Vec<(&VoicePack, f32)> = packs.iter().map(|(p, w)| (p.as_ref(), *w)).collect();
+        let pack = Arc::new(blend_packs(&refs)?);
+
+        let mut blends = self.blends.lock().unwrap();
+        if blends.len() >= BLEND_CACHE_CAPACITY {
+            blends.clear();
+        }
+        blends.insert(voice.to_string(), pack.clone());
+        Ok(pack)
+    }
+
+    /// Load every `.bin` voices file in `dir` as user voices, and save new
+    /// voices there. A missing directory is fine. Names that clash with
+    /// stock voices are skipped. Returns the number of voices loaded.
+    pub fn load_dir(&self, dir: &Path) -> Result<usize, String> {
+        *self.user_dir.write().unwrap() = Some(dir.to_path_buf());
+        // Cached blends may mix in user voices this load replaces.
+        self.blends.lock().unwrap().clear();
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(format!("read {}: {e}", dir.display())),
+        };
+
+        let stock_shape = self.stock.values().next().map(|p| shape(p));
+        let mut loaded = 0;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "bin") {
+                continue;
+            }
+            let voices = match std::fs::read(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|data| decode_voices(&data))
+            {
+                Ok(voices) => voices,
+                Err(e) => {
+                    warn!("voices: skipping {}: {e}", path.display());
+                    continue;
+                }
+            };
+            let mut custom = self.custom.write().unwrap();
+            for (name, pack) in voices {
+                if self.stock.contains_key(&name) {
+                    warn!("voices: {} redefines stock voice '{name}', skipping", path.display());
+                    continue;
+                }
+                if pack.is_empty() || stock_shape.is_some_and(|s| s != shape(&pack)) {
+                    warn!("voices: {} has a malformed pack for '{name}', skipping", path.display());
+                    continue;
+                }
+                custom.insert(name, Arc::new(pack));
+                loaded += 1;
+            }
+        }
+        debug!("voices: {loaded} user voices from {}", dir.display());
+        Ok(loaded)
+    }
+
+    /// Check that [`save`](Self::save) would accept `name` and `spec`,
+    /// without writing anything. Whatever `save` fails with beyond this is
+    /// a filesystem error.
+    pub fn check_save(&self, name: &str, spec: &str) -> Result<(), String> {
+        self.prepare_save(name, spec).map(|_| ())
+    }
+
+    /// The user voices directory and the pack to save as `name`.
+    fn prepare_save(&self, name: &str, spec: &str) -> Result<(PathBuf, Arc<VoicePack>), String> {
+        validate_custom_name(name)?;
+        if self.stock.contains_key(name) {
+            return Err(format!("'{name}' is a stock voice; pick another name"));
+        }
+        let dir = self
+            .user_dir
+            .read()
+            .unwrap()
+            .clone()
+            .ok_or("no user voices directory configured")?;
+        Ok((dir, self.get(spec)?))
+    }
+
+    /// Save the voice or blend `spec` as user voice `name`, replacing an
+    /// earlier user voice of that name. Returns the file written.
+    pub fn save(&self, name: &str, spec: &str) -> Result<PathBuf, String> {
+        let (dir, pack) = self.prepare_save(name, spec)?;
+
+        std::fs::create_dir_all(&dir).map_err(|e| format!("create {}: {e}", dir.display()))?;
+        let path = dir.join(format!("{name}.bin"));
+        let data = encode_voices(&HashMap::from([(name.to_string(), pack.as_ref().clone())]))?;
+        std::fs::write(&path, data).map_err(|e| format!("write {}: {e}", path.display()))?;
+
+        self.custom.write().unwrap().insert(name.to_string(), pack);
+        // Drop blends built from the voice this one replaces.
+        self.blends.lock().unwrap().clear();
+        Ok(path)
+    }
+}
+
+/// Custom voice names: letters, digits, `_` and `-`, so they stay usable in
+/// blends and as file names.
+fn validate_custom_name(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(format!(
+            "invalid voice name '{name}'; use letters, digits, '_' and '-'"
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 2-length pack with one 2-dim style vector per length, all `x`.
+    fn pack(x: f32) -> VoicePack {
+        vec![vec![vec![x, x]]; 2]
+    }
+
+    fn library() -> VoiceLibrary {
+        VoiceLibrary::new(HashMap::from([
+            ("af_heart".to_string(), pack(1.0)),
+            ("am_adam".to_string(), pack(2.0)),
+            ("bf_emma".to_string(), pack(3.0)),
+        ]))
+    }
+
+    #[test]
+    fn parse_blend() {
+        let blend = VoiceBlend::parse("af_heart:0.6 + bf_emma:0.2").unwrap();
+        assert_eq!(blend.components[0].0, "af_heart");
+        assert!((blend.components[0].1 - 0.75).abs() < 1e-6);
+        assert!((blend.components[1].1 - 0.25).abs() < 1e-6);
+
+        let even = VoiceBlend::parse("a+b").unwrap();
+        assert_eq!(even.components, [("a".into(), 0.5), ("b".into(), 0.5)]);
+
+        assert!(VoiceBlend::parse("a:x").is_err());
+        assert!(VoiceBlend::parse("a:-1+b").is_err());
+        assert!(VoiceBlend::parse("a++b").is_err());
+    }
+
+    #[test]
+    fn blends_interpolate() {
+        let voices = library();
+        let mixed = voices.get("af_heart:0.5+bf_emma:0.5").unwrap();
+        assert_eq!(*mixed, pack(2.0));
+        assert!(Arc::ptr_eq(&mixed, &voices.get("af_heart:0.5+bf_emma:0.5").unwrap()));
+    }
+
+    #[test]
+    fn mismatched_shapes_fail() {
+        let odd = vec![vec![vec![1.0]]];
+        assert!(blend_packs(&[(&pack(1.0), 0.5), (&odd, 0.5)]).is_err());
+    }
+
+    #[test]
+    fn unknown_voice_lists_valid_names() {
+        let voices = library();
+        assert!(voices.get("af_heart").is_ok());
+        let err = voices.get("nonexistent_voice").unwrap_err();
+        assert!(err.contains("nonexistent_voice"));
+        assert!(err.ends_with("af_heart, am_adam, bf_emma"), "{err}");
+
+        let err = voices.get("af_heart+nope").unwrap_err();
+        assert!(err.starts_with("unknown voice 'nope'"), "{err}");
+    }
+
+    #[test]
+    fn save_and_reload_custom_voice() {
+        let dir = std::env::temp_dir().join(format!("nayru-voices-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let voices = library();
+        assert!(voices.save("house", "af_heart+am_adam").is_err(), "no dir yet");
+        assert_eq!(voices.load_dir(&dir).unwrap(), 0);
+        assert!(voices.check_save("af_heart", "am_adam").is_err());
+        assert!(voices.save("af_heart", "am_adam").is_err());
+        assert!(voices.save("bad name", "am_adam").is_err());
+        assert!(voices.check_save("house", "af_heart+nope").is_err());
+        assert!(voices.check_save("house", "af_heart+am_adam").is_ok());
+
+        let path = voices.save("house", "af_heart+am_adam").unwrap();
+        assert_eq!(path, dir.join("house.bin"));
+        let house = voices.content_id("house").unwrap();
+        let stock = voices.content_id("af_heart").unwrap();
+        assert_eq!(*voices.get("house").unwrap(), pack(1.5));
+        assert!(voices.names().contains(&"house".to_string()));
+        assert_eq!(voices.kind("house"), VoiceKind::Custom);
+        assert_eq!(voices.kind("af_heart"), VoiceKind::Builtin);
+        assert_eq!(voices.kind("house+af_heart"), VoiceKind::Blend);
+
+        let fresh = library();
+        assert_eq!(fresh.load_dir(&dir).unwrap(), 1);
+        assert_eq!(fresh.custom_names(), ["house"]);
+        assert_eq!(*fresh.get("house:1+bf_emma:1").unwrap(), pack(2.25));
+
+        // Saving over a voice rebuilds blends that use it.
+        fresh.save("house", "bf_emma").unwrap();
+        assert_eq!(*fresh.get("house:1+bf_emma:1").unwrap(), pack(3.0));
+
+        // Cache ids follow a voice's content, not the directory.
+        assert_eq!(fresh.content_id("af_heart").unwrap(), stock);
+        assert_ne!(fresh.content_id("house").unwrap(), house);
+        voices.save("other", "am_adam").unwrap();
+        assert_eq!(voices.content_id("house").unwrap(), house);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn malformed_user_packs_are_skipped() {
+        let dir = std::env::temp_dir().join(format!("nayru-voices-bad-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let packs = HashMap::from([
+            ("empty".to_string(), VoicePack::new()),
+            ("short".to_string(), vec![vec![vec![1.0, 1.0]]]),
+            ("fine".to_string(), pack(4.0)),
+        ]);
+        std::fs::write(dir.join("mixed.bin"), encode_voices(&packs).unwrap()).unwrap();
+
+        let voices = library();
+        assert_eq!(voices.load_dir(&dir).unwrap(), 1);
+        assert_eq!(voices.custom_names(), ["fine"]);
+        assert!(voices.get("empty").is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}